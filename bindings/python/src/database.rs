@@ -0,0 +1,168 @@
+// -*- coding: utf-8 -*-
+// ------------------------------------------------------------------------------------------------
+// Copyright © 2023, stack-graphs authors.
+// Licensed under either of Apache License, Version 2.0, or MIT license, at your option.
+// Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
+// ------------------------------------------------------------------------------------------------
+
+use pyo3::prelude::*;
+use stack_graphs::storage::FileStatus;
+use stack_graphs::storage::SQLiteReader;
+use stack_graphs::storage::SQLiteWriter;
+use std::path::Path;
+use std::path::PathBuf;
+use tree_sitter_stack_graphs::cli::index::Indexer;
+use tree_sitter_stack_graphs::cli::query::Querier;
+use tree_sitter_stack_graphs::cli::util::reporter::ConsoleReporter;
+use tree_sitter_stack_graphs::cli::util::SourcePosition;
+use tree_sitter_stack_graphs::cli::util::SourceSpan;
+use tree_sitter_stack_graphs::NoCancellation;
+
+use crate::graph::PyGraph;
+use crate::graph::PySpan;
+use crate::loader::PyLoader;
+use crate::to_py_err;
+
+/// A SQLite database of indexed files, in the same format as used by the `tree-sitter-stack-graphs`
+/// CLI. The database is opened for reading on first use, and kept open. Data that is loaded for
+/// queries is reused by later queries, until files are indexed through this database.
+#[pyclass(name = "Database", module = "stack_graphs")]
+pub struct PyDatabase {
+    path: PathBuf,
+    reader: Option<SQLiteReader>,
+}
+
+#[pymethods]
+impl PyDatabase {
+    #[new]
+    fn new(path: PathBuf) -> Self {
+        Self { path, reader: None }
+    }
+
+    #[getter]
+    fn path(&self) -> PathBuf {
+        self.path.clone()
+    }
+
+    /// Indexes the given source files and directories. Files that are already indexed and did not
+    /// change are skipped, unless `force` is set. Files that fail to index are stored with their
+    /// error, which can be checked with `status`.
+    #[pyo3(signature = (loader, source_paths, force = false))]
+    fn index(
+        &mut self,
+        mut loader: PyRefMut<PyLoader>,
+        source_paths: Vec<PathBuf>,
+        force: bool,
+    ) -> PyResult<()> {
+        let source_paths = source_paths
+            .into_iter()
+            .map(|p| p.canonicalize())
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(to_py_err)?;
+        let mut db = SQLiteWriter::open(&self.path).map_err(to_py_err)?;
+        let reporter = ConsoleReporter::none();
+        let mut indexer = Indexer::new(&mut db, &mut loader.inner, &reporter);
+        indexer.force = force;
+        let result = indexer
+            .index_all(source_paths, None::<&Path>, &NoCancellation)
+            .map_err(to_py_err);
+        // loaded data may be outdated by the newly indexed files
+        if let Some(reader) = &mut self.reader {
+            reader.clear();
+        }
+        result
+    }
+
+    /// Returns the status of the given file, which is one of `"indexed"`, `"error"`, or
+    /// `"missing"`.
+    fn status(&mut self, path: PathBuf) -> PyResult<&'static str> {
+        let path = path.canonicalize().map_err(to_py_err)?;
+        let status = self
+            .reader()?
+            .status_for_file(&path.to_string_lossy(), None::<&str>)
+            .map_err(to_py_err)?;
+        Ok(match status {
+            FileStatus::Indexed => "indexed",
+            FileStatus::Error(_) => "error",
+            FileStatus::Missing => "missing",
+        })
+    }
+
+    /// Finds the definitions of the references at the given position. Lines and columns are
+    /// 0-based, and columns count graphemes. Returns a result for every reference at the
+    /// position, which is empty if the file is not indexed.
+    fn definitions(
+        &mut self,
+        path: PathBuf,
+        line: usize,
+        column: usize,
+    ) -> PyResult<Vec<PyQueryResult>> {
+        let mut reference = SourcePosition { path, line, column };
+        reference.canonicalize().map_err(to_py_err)?;
+        let reporter = ConsoleReporter::none();
+        let mut querier = Querier::new(self.reader()?, &reporter);
+        let results = querier
+            .definitions(reference, &NoCancellation)
+            .map_err(to_py_err)?;
+        Ok(results
+            .into_iter()
+            .map(|result| PyQueryResult {
+                source: span(&result.source),
+                targets: result.targets.iter().map(span).collect(),
+            })
+            .collect())
+    }
+
+    /// Loads the stack graph of the given indexed file.
+    fn load_graph(&mut self, path: PathBuf) -> PyResult<PyGraph> {
+        let path = path.canonicalize().map_err(to_py_err)?;
+        let db = self.reader()?;
+        let file = db
+            .load_graph_for_file(&path.to_string_lossy())
+            .map_err(to_py_err)?;
+        let (graph, _, _) = db.get();
+        Ok(PyGraph::from_file(graph, file))
+    }
+}
+
+impl PyDatabase {
+    /// Returns the reader of this database, and opens it if it is not open yet.
+    fn reader(&mut self) -> PyResult<&mut SQLiteReader> {
+        if self.reader.is_none() {
+            self.reader = Some(SQLiteReader::open(&self.path).map_err(to_py_err)?);
+        }
+        Ok(self.reader.as_mut().unwrap())
+    }
+}
+
+fn span(source_span: &SourceSpan) -> PySpan {
+    PySpan::from_span(
+        Some(source_span.path.to_string_lossy().to_string()),
+        &source_span.span,
+    )
+}
+
+/// The definitions found for a reference.
+#[pyclass(name = "QueryResult", module = "stack_graphs")]
+#[derive(Clone)]
+pub struct PyQueryResult {
+    #[pyo3(get)]
+    source: PySpan,
+    #[pyo3(get)]
+    targets: Vec<PySpan>,
+}
+
+#[pymethods]
+impl PyQueryResult {
+    fn __repr__(&self) -> String {
+        format!(
+            "QueryResult(source={}, targets=[{}])",
+            self.source.__repr__(),
+            self.targets
+                .iter()
+                .map(|t| t.__repr__())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
@@ -0,0 +1,276 @@
+// -*- coding: utf-8 -*-
+// ------------------------------------------------------------------------------------------------
+// Copyright © 2023, stack-graphs authors.
+// Licensed under either of Apache License, Version 2.0, or MIT license, at your option.
+// Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
+// ------------------------------------------------------------------------------------------------
+
+use pyo3::prelude::*;
+use stack_graphs::arena::Handle;
+use stack_graphs::graph::File;
+use stack_graphs::graph::Node;
+use stack_graphs::graph::StackGraph;
+
+/// A copy of the nodes and edges of the stack graph of a file.
+#[pyclass(name = "Graph", module = "stack_graphs")]
+pub struct PyGraph {
+    nodes: Vec<PyNode>,
+    edges: Vec<PyEdge>,
+}
+
+impl PyGraph {
+    /// Copies the nodes of the given file, the singleton root and jump-to nodes, and all edges
+    /// from or to nodes of the file. Nodes of other files that are loaded in the same graph are
+    /// not copied.
+    pub(crate) fn from_file(graph: &StackGraph, file: Handle<File>) -> Self {
+        let in_file = |node: Handle<Node>| graph[node].file() == Some(file);
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+        for node in graph.iter_nodes() {
+            if graph[node].file().map_or(false, |f| f != file) {
+                continue;
+            }
+            nodes.push(PyNode::from_node(graph, node));
+            edges.extend(
+                graph
+                    .outgoing_edges(node)
+                    .filter(|edge| in_file(edge.source) || in_file(edge.sink))
+                    .map(|edge| PyEdge {
+                        source: edge.source.as_u32(),
+                        sink: edge.sink.as_u32(),
+                        precedence: edge.precedence,
+                    }),
+            );
+        }
+        Self { nodes, edges }
+    }
+}
+
+#[pymethods]
+impl PyGraph {
+    /// Returns all nodes in the graph.
+    fn nodes(&self) -> Vec<PyNode> {
+        self.nodes.clone()
+    }
+
+    /// Returns all edges in the graph.
+    fn edges(&self) -> Vec<PyEdge> {
+        self.edges.clone()
+    }
+
+    /// Returns the node with the given handle, or `None` if there is no such node.
+    fn node(&self, handle: u32) -> Option<PyNode> {
+        self.nodes.iter().find(|n| n.handle == handle).cloned()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Graph(nodes={}, edges={})",
+            self.nodes.len(),
+            self.edges.len()
+        )
+    }
+}
+
+/// A node in a stack graph. Nodes are identified by their handle, which is unique within a graph.
+#[pyclass(name = "Node", module = "stack_graphs")]
+#[derive(Clone)]
+pub struct PyNode {
+    #[pyo3(get)]
+    handle: u32,
+    /// The kind of node, e.g. `push_symbol` or `scope`.
+    #[pyo3(get)]
+    kind: &'static str,
+    /// The file the node belongs to, or `None` for the root and jump-to nodes.
+    #[pyo3(get)]
+    file: Option<String>,
+    #[pyo3(get)]
+    local_id: u32,
+    #[pyo3(get)]
+    symbol: Option<String>,
+    #[pyo3(get)]
+    is_definition: bool,
+    #[pyo3(get)]
+    is_reference: bool,
+    #[pyo3(get)]
+    is_exported: bool,
+    #[pyo3(get)]
+    source_info: Option<PySourceInfo>,
+}
+
+impl PyNode {
+    fn from_node(graph: &StackGraph, handle: Handle<Node>) -> Self {
+        let node = &graph[handle];
+        let file = node.file().map(|f| graph[f].name().to_string());
+        let source_info = graph.source_info(handle).map(|source_info| PySourceInfo {
+            span: PySpan::from_span(file.clone(), &source_info.span),
+            syntax_type: source_info
+                .syntax_type
+                .into_option()
+                .map(|s| graph[s].to_string()),
+            fully_qualified_name: source_info
+                .fully_qualified_name
+                .into_option()
+                .map(|s| graph[s].to_string()),
+            documentation: source_info
+                .documentation
+                .into_option()
+                .map(|s| graph[s].to_string()),
+        });
+        Self {
+            handle: handle.as_u32(),
+            kind: node_kind(node),
+            file,
+            local_id: node.id().local_id(),
+            symbol: node.symbol().map(|s| graph[s].to_string()),
+            is_definition: node.is_definition(),
+            is_reference: node.is_reference(),
+            is_exported: node.is_exported_scope(),
+            source_info,
+        }
+    }
+}
+
+#[pymethods]
+impl PyNode {
+    fn __repr__(&self) -> String {
+        let mut repr = format!("Node(handle={}, kind={:?}", self.handle, self.kind);
+        if let Some(file) = &self.file {
+            repr += &format!(", file={:?}, local_id={}", file, self.local_id);
+        }
+        if let Some(symbol) = &self.symbol {
+            repr += &format!(", symbol={:?}", symbol);
+        }
+        repr += ")";
+        repr
+    }
+}
+
+fn node_kind(node: &Node) -> &'static str {
+    match node {
+        Node::DropScopes(_) => "drop_scopes",
+        Node::JumpTo(_) => "jump_to",
+        Node::PopScopedSymbol(_) => "pop_scoped_symbol",
+        Node::PopSymbol(_) => "pop_symbol",
+        Node::PushScopedSymbol(_) => "push_scoped_symbol",
+        Node::PushSymbol(_) => "push_symbol",
+        Node::Root(_) => "root",
+        Node::Scope(_) => "scope",
+    }
+}
+
+/// An edge between two nodes, identified by their handles.
+#[pyclass(name = "Edge", module = "stack_graphs")]
+#[derive(Clone)]
+pub struct PyEdge {
+    #[pyo3(get)]
+    source: u32,
+    #[pyo3(get)]
+    sink: u32,
+    #[pyo3(get)]
+    precedence: i32,
+}
+
+#[pymethods]
+impl PyEdge {
+    fn __repr__(&self) -> String {
+        format!(
+            "Edge(source={}, sink={}, precedence={})",
+            self.source, self.sink, self.precedence
+        )
+    }
+}
+
+/// Source information of a node.
+#[pyclass(name = "SourceInfo", module = "stack_graphs")]
+#[derive(Clone)]
+pub struct PySourceInfo {
+    #[pyo3(get)]
+    span: PySpan,
+    #[pyo3(get)]
+    syntax_type: Option<String>,
+    #[pyo3(get)]
+    fully_qualified_name: Option<String>,
+    #[pyo3(get)]
+    documentation: Option<String>,
+}
+
+#[pymethods]
+impl PySourceInfo {
+    fn __repr__(&self) -> String {
+        format!(
+            "SourceInfo(span={}, syntax_type={:?}, fully_qualified_name={:?}, documentation={:?})",
+            self.span.__repr__(),
+            self.syntax_type,
+            self.fully_qualified_name,
+            self.documentation
+        )
+    }
+}
+
+/// A span in a source file.
+#[pyclass(name = "Span", module = "stack_graphs")]
+#[derive(Clone)]
+pub struct PySpan {
+    #[pyo3(get)]
+    path: Option<String>,
+    #[pyo3(get)]
+    start: PyPosition,
+    #[pyo3(get)]
+    end: PyPosition,
+}
+
+impl PySpan {
+    pub(crate) fn from_span(path: Option<String>, span: &lsp_positions::Span) -> Self {
+        Self {
+            path,
+            start: PyPosition::from_position(&span.start),
+            end: PyPosition::from_position(&span.end),
+        }
+    }
+}
+
+#[pymethods]
+impl PySpan {
+    pub(crate) fn __repr__(&self) -> String {
+        format!(
+            "Span(path={:?}, start={}, end={})",
+            self.path,
+            self.start.__repr__(),
+            self.end.__repr__()
+        )
+    }
+}
+
+/// A position in a source file. Lines are 0-based. The column is the 0-based grapheme offset in
+/// the line, which is also available as UTF-8 and UTF-16 offsets.
+#[pyclass(name = "Position", module = "stack_graphs")]
+#[derive(Clone)]
+pub struct PyPosition {
+    #[pyo3(get)]
+    line: usize,
+    #[pyo3(get)]
+    column: usize,
+    #[pyo3(get)]
+    utf8_offset: usize,
+    #[pyo3(get)]
+    utf16_offset: usize,
+}
+
+impl PyPosition {
+    fn from_position(position: &lsp_positions::Position) -> Self {
+        Self {
+            line: position.line,
+            column: position.column.grapheme_offset,
+            utf8_offset: position.column.utf8_offset,
+            utf16_offset: position.column.utf16_offset,
+        }
+    }
+}
+
+#[pymethods]
+impl PyPosition {
+    pub(crate) fn __repr__(&self) -> String {
+        format!("Position(line={}, column={})", self.line, self.column)
+    }
+}
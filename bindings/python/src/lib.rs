@@ -0,0 +1,72 @@
+// -*- coding: utf-8 -*-
+// ------------------------------------------------------------------------------------------------
+// Copyright © 2023, stack-graphs authors.
+// Licensed under either of Apache License, Version 2.0, or MIT license, at your option.
+// Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
+// ------------------------------------------------------------------------------------------------
+
+//! Python bindings for stack graphs.
+//!
+//! The bindings are exposed as the `stack_graphs` Python module, which provides:
+//!
+//!  - `Loader`, which loads language configurations from tree-sitter grammar directories.
+//!  - `Database`, which indexes source files into a SQLite database, answers definition queries,
+//!    and loads the stack graphs of indexed files.
+//!  - `Graph`, `Node`, `Edge`, `SourceInfo`, `Span`, and `Position`, which are plain Python
+//!    objects holding a copy of the data in a stack graph.
+//!  - `StackGraphsError`, which is raised for all errors.
+//!
+//! All data is copied into Python objects, so Python code never holds references into Rust data
+//! structures.
+
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+
+mod database;
+mod graph;
+mod loader;
+
+pub use database::PyDatabase;
+pub use database::PyQueryResult;
+pub use graph::PyEdge;
+pub use graph::PyGraph;
+pub use graph::PyNode;
+pub use graph::PyPosition;
+pub use graph::PySourceInfo;
+pub use graph::PySpan;
+pub use loader::PyLoader;
+
+create_exception!(
+    stack_graphs,
+    StackGraphsError,
+    PyException,
+    "Raised for all errors in stack graphs operations."
+);
+
+/// Converts any error into a Python `StackGraphsError`.
+pub(crate) fn to_py_err<E: std::fmt::Display>(err: E) -> PyErr {
+    StackGraphsError::new_err(err.to_string())
+}
+
+#[pymodule]
+#[pyo3(name = "stack_graphs")]
+fn module(py: Python, m: &PyModule) -> PyResult<()> {
+    register(py, m)
+}
+
+/// Adds all classes and exceptions of the bindings to the given module.  This is used to create
+/// the extension module, and can be used to embed the bindings in a Rust-hosted interpreter.
+pub fn register(py: Python, m: &PyModule) -> PyResult<()> {
+    m.add("StackGraphsError", py.get_type::<StackGraphsError>())?;
+    m.add_class::<PyLoader>()?;
+    m.add_class::<PyDatabase>()?;
+    m.add_class::<PyQueryResult>()?;
+    m.add_class::<PyGraph>()?;
+    m.add_class::<PyNode>()?;
+    m.add_class::<PyEdge>()?;
+    m.add_class::<PySourceInfo>()?;
+    m.add_class::<PySpan>()?;
+    m.add_class::<PyPosition>()?;
+    Ok(())
+}
@@ -0,0 +1,58 @@
+// -*- coding: utf-8 -*-
+// ------------------------------------------------------------------------------------------------
+// Copyright © 2023, stack-graphs authors.
+// Licensed under either of Apache License, Version 2.0, or MIT license, at your option.
+// Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
+// ------------------------------------------------------------------------------------------------
+
+use pyo3::prelude::*;
+use std::path::PathBuf;
+use tree_sitter_stack_graphs::loader::LoadPath;
+use tree_sitter_stack_graphs::loader::Loader;
+use tree_sitter_stack_graphs::loader::DEFAULT_BUILTINS_PATHS;
+use tree_sitter_stack_graphs::loader::DEFAULT_TSG_PATHS;
+
+use crate::to_py_err;
+
+/// Loads language configurations for source files.
+///
+/// Languages are discovered in the given tree-sitter grammar directories. The stack graph
+/// definitions and builtins are loaded from the given paths, or from the default locations in the
+/// grammar directory if no paths are given. If a scope is given, only languages with that scope
+/// are used.
+#[pyclass(name = "Loader", module = "stack_graphs", unsendable)]
+pub struct PyLoader {
+    pub(crate) inner: Loader,
+}
+
+#[pymethods]
+impl PyLoader {
+    #[new]
+    #[pyo3(signature = (paths, scope = None, tsg_paths = None, builtins_paths = None))]
+    fn new(
+        paths: Vec<PathBuf>,
+        scope: Option<String>,
+        tsg_paths: Option<Vec<PathBuf>>,
+        builtins_paths: Option<Vec<PathBuf>>,
+    ) -> PyResult<Self> {
+        let tsg_paths = match tsg_paths {
+            Some(tsg_paths) => tsg_paths.into_iter().map(LoadPath::Regular).collect(),
+            None => DEFAULT_TSG_PATHS.clone(),
+        };
+        let builtins_paths = match builtins_paths {
+            Some(builtins_paths) => builtins_paths.into_iter().map(LoadPath::Regular).collect(),
+            None => DEFAULT_BUILTINS_PATHS.clone(),
+        };
+        let inner =
+            Loader::from_paths(paths, scope, tsg_paths, builtins_paths).map_err(to_py_err)?;
+        Ok(Self { inner })
+    }
+}
+
+impl From<Loader> for PyLoader {
+    /// Wraps a loader created in Rust, for example from language configurations that are compiled
+    /// into the host program.
+    fn from(inner: Loader) -> Self {
+        Self { inner }
+    }
+}
@@ -0,0 +1,144 @@
+// -*- coding: utf-8 -*-
+// ------------------------------------------------------------------------------------------------
+// Copyright © 2023, stack-graphs authors.
+// Licensed under either of Apache License, Version 2.0, or MIT license, at your option.
+// Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
+// ------------------------------------------------------------------------------------------------
+
+//! Smoke test that runs a Python script against the bindings, using an interpreter embedded in
+//! the test executable.  The language is provided from Rust, so the test does not depend on any
+//! grammars being available on disk.
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use stack_graphs::graph::StackGraph;
+use stack_graphs_python::PyLoader;
+use std::path::PathBuf;
+use tree_sitter_stack_graphs::loader::FileAnalyzers;
+use tree_sitter_stack_graphs::loader::LanguageConfiguration;
+use tree_sitter_stack_graphs::loader::Loader;
+use tree_sitter_stack_graphs::StackGraphLanguage;
+
+static TSG: &str = r#"
+  (module) @mod {
+    node @mod.scope
+  }
+
+  (module (expression_statement (assignment left: (identifier) @name))) @mod {
+    node def
+    attr (def) type = "pop_symbol", symbol = (source-text @name), source_node = @name, is_definition
+    edge @mod.scope -> def
+  }
+
+  (module (expression_statement (call arguments: (argument_list (identifier) @name)))) @mod {
+    node ref
+    attr (ref) type = "push_symbol", symbol = (source-text @name), source_node = @name, is_reference
+    edge ref -> @mod.scope
+  }
+"#;
+
+static SOURCE: &str = "x = 1\nprint(x)\n";
+
+static SCRIPT: &str = r#"
+db = stack_graphs.Database(db_path)
+db.index(loader, [source_path])
+assert db.status(source_path) == "indexed", db.status(source_path)
+
+results = db.definitions(source_path, 1, 6)
+assert len(results) == 1, results
+[result] = results
+assert (result.source.start.line, result.source.start.column) == (1, 6), result
+assert [(t.start.line, t.start.column, t.end.column) for t in result.targets] == [(0, 0, 1)], result
+
+assert db.definitions(source_path, 0, 4) == []
+
+graph = db.load_graph(source_path)
+definitions = [n for n in graph.nodes() if n.is_definition]
+references = [n for n in graph.nodes() if n.is_reference]
+assert [(n.kind, n.symbol) for n in definitions] == [("pop_symbol", "x")], definitions
+assert [(n.kind, n.symbol) for n in references] == [("push_symbol", "x")], references
+assert definitions[0].source_info.span.start.line == 0, definitions[0].source_info
+assert references[0].source_info.span.start.line == 1, references[0].source_info
+[edge] = [e for e in graph.edges() if e.source == references[0].handle]
+assert graph.node(edge.sink).kind == "scope", edge
+assert any(e.sink == definitions[0].handle for e in graph.edges())
+
+# the database stays open, and data loaded by earlier queries is dropped when files are indexed
+with open(source_path, "w") as f:
+    f.write("y = 1\nx = 2\nprint(x)\n")
+with open(other_path, "w") as f:
+    f.write("z = 3\n")
+db.index(loader, [source_path, other_path])
+[result] = db.definitions(source_path, 2, 6)
+assert [(t.start.line, t.start.column, t.end.column) for t in result.targets] == [(1, 0, 1)], result
+
+# graphs only contain the nodes of the requested file, even if other files were loaded before
+db.load_graph(other_path)
+graph = db.load_graph(source_path)
+definitions = [n for n in graph.nodes() if n.is_definition]
+assert [n.symbol for n in definitions] == ["y", "x"], definitions
+assert all(n.file is None or n.file.endswith("test.py") for n in graph.nodes()), graph.nodes()
+
+try:
+    stack_graphs.Database(missing_db_path).definitions(source_path, 1, 6)
+    assert False, "expected an error for a missing database"
+except stack_graphs.StackGraphsError:
+    pass
+"#;
+
+fn loader() -> Loader {
+    let language = tree_sitter_python::language();
+    let sgl = StackGraphLanguage::from_str(language, TSG).unwrap();
+    let lc = LanguageConfiguration {
+        language,
+        scope: Some("source.py".into()),
+        content_regex: None,
+        file_types: vec!["py".into()],
+        sgl,
+        builtins: StackGraph::new(),
+        special_files: FileAnalyzers::new(),
+        no_similar_paths_in_file: false,
+    };
+    Loader::from_language_configurations(vec![lc], None).unwrap()
+}
+
+fn temp_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("stack-graphs-python-{}", std::process::id()));
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn can_index_query_and_inspect_from_python() {
+    let dir = temp_dir();
+    let source_path = dir.join("test.py");
+    std::fs::write(&source_path, SOURCE).unwrap();
+
+    let result = Python::with_gil(|py| -> PyResult<()> {
+        let module = PyModule::new(py, "stack_graphs")?;
+        stack_graphs_python::register(py, module)?;
+        let globals = PyDict::new(py);
+        globals.set_item("stack_graphs", module)?;
+        globals.set_item("loader", Py::new(py, PyLoader::from(loader()))?)?;
+        globals.set_item("db_path", dir.join("db.sqlite").to_string_lossy().as_ref())?;
+        globals.set_item(
+            "missing_db_path",
+            dir.join("missing.sqlite").to_string_lossy().as_ref(),
+        )?;
+        globals.set_item("source_path", source_path.to_string_lossy().as_ref())?;
+        globals.set_item(
+            "other_path",
+            dir.join("other.py").to_string_lossy().as_ref(),
+        )?;
+        py.run(SCRIPT, Some(globals), None).map_err(|err| {
+            err.print(py);
+            err
+        })
+    });
+
+    std::fs::remove_dir_all(&dir).unwrap();
+    result.expect("Python script failed");
+}
@@ -0,0 +1,249 @@
+// -*- coding: utf-8 -*-
+// ------------------------------------------------------------------------------------------------
+// Copyright © 2023, stack-graphs authors.
+// Licensed under either of Apache License, Version 2.0, or MIT license, at your option.
+// Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
+// ------------------------------------------------------------------------------------------------
+
+//! WebAssembly bindings for resolving names with precomputed stack graphs.
+//!
+//! The [`StackGraphs`] type loads stack graphs and partial paths that were serialized to JSON
+//! using the [`serde`][stack_graphs::serde] module of the `stack-graphs` crate, and answers
+//! definition queries using the forward partial path stitcher.  No source code is parsed, so the
+//! data must be computed beforehand, for example by an indexing service.
+//!
+//! Positions use 0-based lines, and 0-based UTF-16 columns, which matches the indexing of
+//! JavaScript strings.
+
+use serde::Deserialize;
+use serde::Serialize;
+use stack_graphs::arena::Handle;
+use stack_graphs::graph::Node;
+use stack_graphs::graph::StackGraph;
+use stack_graphs::partial::PartialPath;
+use stack_graphs::partial::PartialPaths;
+use stack_graphs::stitching::Database;
+use stack_graphs::stitching::DatabaseCandidates;
+use stack_graphs::stitching::ForwardPartialPathStitcher;
+use stack_graphs::stitching::StitcherConfig;
+use stack_graphs::NoCancellation;
+use thiserror::Error;
+use wasm_bindgen::prelude::*;
+
+/// A stack graph and a database of partial paths, that are loaded from JSON and used to resolve
+/// references.
+#[wasm_bindgen]
+pub struct StackGraphs {
+    graph: StackGraph,
+    partials: PartialPaths,
+    db: Database,
+}
+
+#[wasm_bindgen]
+impl StackGraphs {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self {
+            graph: StackGraph::new(),
+            partials: PartialPaths::new(),
+            db: Database::new(),
+        }
+    }
+
+    /// Loads a serialized stack graph, and adds its files to the graph.  Files that are already
+    /// loaded cannot be loaded again.
+    #[wasm_bindgen(js_name = loadGraph)]
+    pub fn load_graph_js(&mut self, json: &str) -> Result<(), JsError> {
+        self.load_graph(json).map_err(to_js_error)
+    }
+
+    /// Loads serialized partial paths, and adds them to the database.  The graphs of all files
+    /// that the partial paths refer to must be loaded first.
+    #[wasm_bindgen(js_name = loadPartialPaths)]
+    pub fn load_partial_paths_js(&mut self, json: &str) -> Result<(), JsError> {
+        self.load_partial_paths(json).map_err(to_js_error)
+    }
+
+    /// Returns the names of all loaded files.
+    #[wasm_bindgen(js_name = files)]
+    pub fn files_js(&self) -> Result<JsValue, JsError> {
+        serde_wasm_bindgen::to_value(&self.files()).map_err(to_js_error)
+    }
+
+    /// Finds the definitions of the references at the given position.  Returns an array with a
+    /// `{ reference, definitions }` object for every reference at the position.  Each location is
+    /// a `{ file, span: { start: { line, column }, end: { line, column } } }` object.
+    #[wasm_bindgen(js_name = definitions)]
+    pub fn definitions_js(
+        &mut self,
+        file: &str,
+        line: u32,
+        column: u32,
+    ) -> Result<JsValue, JsError> {
+        serde_wasm_bindgen::to_value(&self.definitions(file, line, column)).map_err(to_js_error)
+    }
+}
+
+impl StackGraphs {
+    /// Loads a serialized stack graph, and adds its files to the graph.
+    pub fn load_graph(&mut self, json: &str) -> Result<(), LoadError> {
+        let graph: stack_graphs::serde::StackGraph = serde_json::from_str(json)?;
+        graph.load_into(&mut self.graph)?;
+        Ok(())
+    }
+
+    /// Loads serialized partial paths, and adds them to the database.
+    pub fn load_partial_paths(&mut self, json: &str) -> Result<(), LoadError> {
+        let db: stack_graphs::serde::Database = serde_json::from_str(json)?;
+        db.load_into(&mut self.graph, &mut self.partials, &mut self.db)?;
+        Ok(())
+    }
+
+    /// Returns the names of all loaded files.
+    pub fn files(&self) -> Vec<String> {
+        self.graph
+            .iter_files()
+            .map(|f| self.graph[f].name().to_string())
+            .collect()
+    }
+
+    /// Finds the definitions of the references at the given position.  Definitions that are
+    /// shadowed by other definitions of the same reference are omitted.
+    pub fn definitions(&mut self, file: &str, line: u32, column: u32) -> Vec<QueryResult> {
+        let references = self.references_at(file, line as usize, column as usize);
+        let mut results = Vec::new();
+        for reference in references {
+            let mut paths = Vec::new();
+            let config = StitcherConfig::default()
+                // always detect similar paths, we don't know the language configurations for the loaded data
+                .with_detect_similar_paths(true);
+            ForwardPartialPathStitcher::find_all_complete_partial_paths(
+                &mut DatabaseCandidates::new(&self.graph, &mut self.partials, &mut self.db),
+                std::iter::once(reference),
+                config,
+                &NoCancellation,
+                |_, _, p| paths.push(p.clone()),
+            )
+            .expect("should never be cancelled");
+
+            let mut definitions = Vec::new();
+            for path in &paths {
+                if !paths
+                    .iter()
+                    .any(|other| other.shadows(&mut self.partials, path))
+                    && !definitions.contains(&path.end_node)
+                {
+                    definitions.push(path.end_node);
+                }
+            }
+            results.push(QueryResult {
+                reference: self.location(reference),
+                definitions: definitions
+                    .into_iter()
+                    .map(|definition| self.location(definition))
+                    .collect(),
+            });
+        }
+        results
+    }
+
+    /// Returns all references in the given file whose span contains the given position.
+    fn references_at(&self, file: &str, line: usize, column: usize) -> Vec<Handle<Node>> {
+        let file = match self.graph.get_file(file) {
+            Some(file) => file,
+            None => return Vec::new(),
+        };
+        self.graph
+            .nodes_for_file(file)
+            .filter(|node| self.graph[*node].is_reference())
+            .filter(|node| match self.graph.source_info(*node) {
+                Some(source_info) => within_span(&source_info.span, line, column),
+                None => false,
+            })
+            .collect()
+    }
+
+    fn location(&self, node: Handle<Node>) -> Location {
+        let file = self.graph[node]
+            .file()
+            .map(|f| self.graph[f].name().to_string())
+            .unwrap_or_default();
+        let span = self
+            .graph
+            .source_info(node)
+            .map(|source_info| Span::from(&source_info.span))
+            .unwrap_or_default();
+        Location { file, span }
+    }
+}
+
+impl Default for StackGraphs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn within_span(span: &lsp_positions::Span, line: usize, column: usize) -> bool {
+    ((span.start.line < line)
+        || (span.start.line == line && span.start.column.utf16_offset <= column))
+        && ((span.end.line == line && span.end.column.utf16_offset >= column)
+            || (span.end.line > line))
+}
+
+fn to_js_error<E: std::fmt::Display>(err: E) -> JsError {
+    JsError::new(&err.to_string())
+}
+
+/// An error that occurred while loading serialized data.
+#[derive(Debug, Error)]
+pub enum LoadError {
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    StackGraph(#[from] stack_graphs::serde::Error),
+}
+
+/// The definitions found for a reference.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct QueryResult {
+    pub reference: Location,
+    pub definitions: Vec<Location>,
+}
+
+/// A span in a file.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Location {
+    pub file: String,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl From<&lsp_positions::Span> for Span {
+    fn from(span: &lsp_positions::Span) -> Self {
+        Self {
+            start: Position::from(&span.start),
+            end: Position::from(&span.end),
+        }
+    }
+}
+
+/// A position, with a 0-based line and a 0-based UTF-16 column.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl From<&lsp_positions::Position> for Position {
+    fn from(position: &lsp_positions::Position) -> Self {
+        Self {
+            line: position.line,
+            column: position.column.utf16_offset,
+        }
+    }
+}
@@ -0,0 +1,143 @@
+// -*- coding: utf-8 -*-
+// ------------------------------------------------------------------------------------------------
+// Copyright © 2023, stack-graphs authors.
+// Licensed under either of Apache License, Version 2.0, or MIT license, at your option.
+// Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
+// ------------------------------------------------------------------------------------------------
+
+use stack_graphs::arena::Handle;
+use stack_graphs::graph::Node;
+use stack_graphs::graph::StackGraph;
+use stack_graphs::partial::PartialPaths;
+use stack_graphs::stitching::Database;
+use stack_graphs::stitching::ForwardPartialPathStitcher;
+use stack_graphs::stitching::StitcherConfig;
+use stack_graphs::NoCancellation;
+use stack_graphs_wasm::Location;
+use stack_graphs_wasm::Position;
+use stack_graphs_wasm::QueryResult;
+use stack_graphs_wasm::Span;
+use stack_graphs_wasm::StackGraphs;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+/// Creates a graph where `b.py` references `x`, which is exported from `a.py` via the root node,
+/// and returns the serialized graph and partial paths.
+fn create_data() -> (String, String) {
+    let mut graph = StackGraph::new();
+    let root = StackGraph::root_node();
+    let x = graph.add_symbol("x");
+
+    let a = graph.get_or_create_file("a.py");
+    let id = graph.new_node_id(a);
+    let definition = graph.add_pop_symbol_node(id, x, true).unwrap();
+    set_span(&mut graph, definition, 0, 0, 1);
+    graph.add_edge(root, definition, 0);
+
+    let b = graph.get_or_create_file("b.py");
+    let id = graph.new_node_id(b);
+    let reference = graph.add_push_symbol_node(id, x, true).unwrap();
+    set_span(&mut graph, reference, 1, 6, 7);
+    graph.add_edge(reference, root, 0);
+
+    let mut partials = PartialPaths::new();
+    let mut db = Database::new();
+    for file in [a, b] {
+        ForwardPartialPathStitcher::find_minimal_partial_path_set_in_file(
+            &graph,
+            &mut partials,
+            file,
+            StitcherConfig::default(),
+            &NoCancellation,
+            |graph, partials, path| {
+                db.add_partial_path(graph, partials, path.clone());
+            },
+        )
+        .unwrap();
+    }
+
+    let graph_json = serde_json::to_string(&graph.to_serializable()).unwrap();
+    let paths_json = serde_json::to_string(&db.to_serializable(&graph, &mut partials)).unwrap();
+    (graph_json, paths_json)
+}
+
+fn set_span(graph: &mut StackGraph, node: Handle<Node>, line: usize, start: usize, end: usize) {
+    let span = &mut graph.source_info_mut(node).span;
+    span.start.line = line;
+    span.start.column.utf8_offset = start;
+    span.start.column.utf16_offset = start;
+    span.start.column.grapheme_offset = start;
+    span.end.line = line;
+    span.end.column.utf8_offset = end;
+    span.end.column.utf16_offset = end;
+    span.end.column.grapheme_offset = end;
+}
+
+fn location(file: &str, line: usize, start: usize, end: usize) -> Location {
+    Location {
+        file: file.to_string(),
+        span: Span {
+            start: Position {
+                line,
+                column: start,
+            },
+            end: Position { line, column: end },
+        },
+    }
+}
+
+fn load() -> StackGraphs {
+    let (graph_json, paths_json) = create_data();
+    let mut stack_graphs = StackGraphs::new();
+    stack_graphs.load_graph_js(&graph_json).unwrap();
+    stack_graphs.load_partial_paths_js(&paths_json).unwrap();
+    stack_graphs
+}
+
+#[wasm_bindgen_test]
+fn can_load_files() {
+    let stack_graphs = load();
+    let files: Vec<String> =
+        serde_wasm_bindgen::from_value(stack_graphs.files_js().unwrap()).unwrap();
+    assert_eq!(vec!["a.py".to_string(), "b.py".to_string()], files);
+}
+
+#[wasm_bindgen_test]
+fn can_find_definitions_across_files() {
+    let mut stack_graphs = load();
+    let results: Vec<QueryResult> =
+        serde_wasm_bindgen::from_value(stack_graphs.definitions_js("b.py", 1, 6).unwrap()).unwrap();
+    assert_eq!(
+        vec![QueryResult {
+            reference: location("b.py", 1, 6, 7),
+            definitions: vec![location("a.py", 0, 0, 1)],
+        }],
+        results
+    );
+}
+
+#[wasm_bindgen_test]
+fn finds_nothing_outside_references() {
+    let mut stack_graphs = load();
+    assert_eq!(
+        Vec::<QueryResult>::new(),
+        stack_graphs.definitions("b.py", 0, 0)
+    );
+    assert_eq!(
+        Vec::<QueryResult>::new(),
+        stack_graphs.definitions("c.py", 1, 6)
+    );
+}
+
+#[wasm_bindgen_test]
+fn cannot_load_file_twice() {
+    let (graph_json, _) = create_data();
+    let mut stack_graphs = StackGraphs::new();
+    stack_graphs.load_graph(&graph_json).unwrap();
+    assert!(stack_graphs.load_graph(&graph_json).is_err());
+}
+
+#[wasm_bindgen_test]
+fn cannot_load_invalid_json() {
+    let mut stack_graphs = StackGraphs::new();
+    assert!(stack_graphs.load_graph("{").is_err());
+}
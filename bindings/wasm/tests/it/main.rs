@@ -0,0 +1,11 @@
+// -*- coding: utf-8 -*-
+// ------------------------------------------------------------------------------------------------
+// Copyright © 2023, stack-graphs authors.
+// Licensed under either of Apache License, Version 2.0, or MIT license, at your option.
+// Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
+// ------------------------------------------------------------------------------------------------
+
+// These tests use JavaScript values, and must run in a WebAssembly runtime.
+#![cfg(target_arch = "wasm32")]
+
+mod definitions;
@@ -0,0 +1,60 @@
+use stack_graphs::graph::StackGraph;
+use std::collections::BTreeSet;
+use tree_sitter_stack_graphs::NoCancellation;
+use tree_sitter_stack_graphs::Variables;
+
+/// Builds the source, and returns the symbols of all definitions with documentation, together with
+/// their documentation.
+fn documented_definitions(source: &str) -> BTreeSet<(String, String)> {
+    let lc = tree_sitter_stack_graphs_java::language_configuration(&NoCancellation);
+    let globals = Variables::new();
+    let mut graph = StackGraph::new();
+    let file = graph.get_or_create_file("Foo.java");
+    lc.sgl
+        .build_stack_graph_into(&mut graph, file, source, &globals, &NoCancellation)
+        .expect("Failed to build graph");
+    graph
+        .nodes_for_file(file)
+        .filter(|node| graph[*node].is_definition())
+        .filter_map(|node| {
+            let symbol = graph[node].symbol()?;
+            let documentation = graph.source_info(node)?.documentation.into_option()?;
+            Some((graph[symbol].to_string(), graph[documentation].to_string()))
+        })
+        .collect()
+}
+
+fn expected(definitions: &[(&str, &str)]) -> BTreeSet<(String, String)> {
+    definitions
+        .iter()
+        .map(|(symbol, documentation)| (symbol.to_string(), documentation.to_string()))
+        .collect()
+}
+
+#[test]
+fn can_attach_documentation_to_definitions() {
+    let source = r#"
+/** A documented class. */
+class Foo {
+    /** A documented field. */
+    int x;
+
+    /** A documented method. */
+    int bar() { return x; }
+
+    /* Not a Javadoc comment. */
+    int baz() { return 0; }
+
+    /**/
+    int qux() { return 0; }
+}
+"#;
+    assert_eq!(
+        expected(&[
+            ("Foo", "/** A documented class. */"),
+            ("x", "/** A documented field. */"),
+            ("bar", "/** A documented method. */"),
+        ]),
+        documented_definitions(source)
+    );
+}
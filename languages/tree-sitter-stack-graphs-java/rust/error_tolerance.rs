@@ -0,0 +1,37 @@
+use stack_graphs::graph::StackGraph;
+use tree_sitter_stack_graphs::NoCancellation;
+use tree_sitter_stack_graphs::Variables;
+
+#[test]
+fn can_build_file_with_parse_errors_in_block() {
+    let source = r#"
+class A {
+    void f() {
+        int x = 1;
+        ) )
+        int y = x;
+    }
+}
+"#;
+
+    let mut lc = tree_sitter_stack_graphs_java::language_configuration(&NoCancellation);
+    lc.sgl.set_error_tolerant(true);
+    let globals = Variables::new();
+
+    let mut graph = StackGraph::new();
+    let file = graph.get_or_create_file("A.java");
+    let output = lc
+        .sgl
+        .build_stack_graph_with_output_into(&mut graph, file, source, &globals, &NoCancellation)
+        .expect("Failed to build graph");
+    assert!(output.parse_errors.is_some());
+
+    let definitions = graph
+        .nodes_for_file(file)
+        .filter(|node| graph[*node].is_definition())
+        .filter_map(|node| graph[node].symbol())
+        .map(|symbol| graph[symbol].to_string())
+        .collect::<Vec<_>>();
+    assert!(definitions.contains(&"x".to_string()));
+    assert!(definitions.contains(&"y".to_string()));
+}
@@ -0,0 +1,32 @@
+/** A documented class. */
+class Foo {
+  /** A documented field. */
+  int x, y;
+
+  /** A documented method. */
+  int bar() {
+    return x + y;
+    //     ^ defined: 4
+    //         ^ defined: 4
+  }
+
+  /* Not a Javadoc comment. */
+  int baz() {
+    return bar();
+    //     ^ defined: 7
+  }
+}
+
+/** A documented interface. */
+interface Qux {}
+
+/** A documented enum. */
+enum Color { RED }
+
+class Test implements Qux {
+  //                  ^ defined: 21
+  void f() {
+    f(Color.RED);
+    //^ defined: 24
+  }
+}
@@ -0,0 +1,67 @@
+// -*- coding: utf-8 -*-
+// ------------------------------------------------------------------------------------------------
+// Copyright © 2023, stack-graphs authors.
+// Licensed under either of Apache License, Version 2.0, or MIT license, at your option.
+// Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
+// ------------------------------------------------------------------------------------------------
+
+use stack_graphs::graph::StackGraph;
+use std::collections::BTreeSet;
+use tree_sitter_stack_graphs::NoCancellation;
+use tree_sitter_stack_graphs::Variables;
+
+/// Builds the source, and returns the symbols of all definitions with documentation, together with
+/// their documentation.
+fn documented_definitions(source: &str) -> BTreeSet<(String, String)> {
+    let lc = tree_sitter_stack_graphs_javascript::language_configuration(&NoCancellation);
+    let globals = Variables::new();
+    let mut graph = StackGraph::new();
+    let file = graph.get_or_create_file("test.js");
+    lc.sgl
+        .build_stack_graph_into(&mut graph, file, source, &globals, &NoCancellation)
+        .expect("Failed to build graph");
+    graph
+        .nodes_for_file(file)
+        .filter(|node| graph[*node].is_definition())
+        .filter_map(|node| {
+            let symbol = graph[node].symbol()?;
+            let documentation = graph.source_info(node)?.documentation.into_option()?;
+            Some((graph[symbol].to_string(), graph[documentation].to_string()))
+        })
+        .collect()
+}
+
+fn expected(definitions: &[(&str, &str)]) -> BTreeSet<(String, String)> {
+    definitions
+        .iter()
+        .map(|(symbol, documentation)| (symbol.to_string(), documentation.to_string()))
+        .collect()
+}
+
+#[test]
+fn can_attach_documentation_to_definitions() {
+    let source = r#"
+/** A documented variable. */
+let x = 1;
+
+/** A documented function. */
+function foo() {}
+
+/** An exported class. */
+export class Bar {}
+
+/* Not a JSDoc comment. */
+let y = 2;
+
+/**/
+function baz() {}
+"#;
+    assert_eq!(
+        expected(&[
+            ("x", "/** A documented variable. */"),
+            ("foo", "/** A documented function. */"),
+            ("Bar", "/** An exported class. */"),
+        ]),
+        documented_definitions(source)
+    );
+}
@@ -0,0 +1,26 @@
+/** A documented variable. */
+let x = 1;
+
+/** A documented function. */
+function foo() {
+    return x;
+    //     ^ defined: 2
+}
+
+/** A documented class. */
+class Bar {
+    /** A documented method. */
+    baz() {
+        return foo();
+        //     ^ defined: 5
+    }
+}
+
+/** An exported function. */
+export function qux() {
+    return new Bar();
+    //         ^ defined: 11
+}
+
+/**/ qux();
+//   ^ defined: 20
@@ -0,0 +1,23 @@
+/** A documented type. */
+type V = { value: number; }
+
+/** A documented interface. */
+interface I {
+    /** A documented method. */
+    m(x: V): V;
+    //   ^ defined: 2
+}
+
+/** A documented variable. */
+let foo: I;
+//       ^ defined: 5
+
+/** A documented function. */
+export function bar(): V {
+    //                 ^ defined: 2
+    return foo.m(null);
+    //     ^ defined: 12
+    //         ^ defined: 7
+}
+
+export {};
@@ -27,6 +27,16 @@ enum sg_deque_direction {
     SG_DEQUE_BACKWARDS,
 };
 
+// The status of a file in a SQLite database.
+enum sg_file_status {
+    // The file is not in the database, or its tag does not match.
+    SG_FILE_STATUS_MISSING,
+    // The file was indexed successfully.
+    SG_FILE_STATUS_INDEXED,
+    // Indexing the file failed.
+    SG_FILE_STATUS_ERROR,
+};
+
 // The different kinds of node that can appear in a stack graph.
 enum sg_node_kind {
     // Removes everything from the current scope stack.
@@ -57,6 +67,8 @@ enum sg_node_kind {
 enum sg_result {
     SG_RESULT_SUCCESS,
     SG_RESULT_CANCELLED,
+    SG_RESULT_STORAGE_ERROR,
+    SG_RESULT_SERIALIZATION_ERROR,
 };
 
 // Manages the state of a collection of partial paths to be used in the path-stitching algorithm.
@@ -76,6 +88,13 @@ struct sg_partial_path_database;
 // A list of paths found by the path-finding algorithm.
 struct sg_partial_path_list;
 
+// Reads stack graphs and partial paths from a SQLite database.  Data is loaded lazily into a
+// stack graph, partial path arena, and partial path database that are owned by the reader.
+struct sg_sqlite_reader;
+
+// Writes stack graphs and partial paths to a SQLite database.
+struct sg_sqlite_writer;
+
 // Contains all of the nodes and edges that make up a stack graph.
 struct sg_stack_graph;
 
@@ -262,6 +281,10 @@ struct sg_source_info {
     // The fully qualified name is a representation of the symbol that captures its name and its
     // embedded context (e.g. `foo.bar` for the symbol `bar` defined in the module `foo`).
     sg_string_handle fully_qualified_name;
+    // The documentation of the syntax entity this node represents, such as the doc comment of a
+    // function or class. It is stored as given by the language, which may include comment markers
+    // such as `/**` or `*/`, that consumers must strip if they need plain text.
+    sg_string_handle documentation;
 };
 
 // An array of all of the source information in a stack graph.  Source information is associated
@@ -500,6 +523,14 @@ struct sg_forward_partial_path_stitcher {
     bool is_complete;
 };
 
+// The stack graph, partial path arena, and partial path database that hold the data loaded by a
+// SQLite reader.  They are owned by the reader, and must not be freed.
+struct sg_sqlite_reader_data {
+    struct sg_stack_graph *graph;
+    struct sg_partial_path_arena *partials;
+    struct sg_partial_path_database *db;
+};
+
 // The handle of the singleton root node.
 #define SG_ROOT_NODE_HANDLE 1
 
@@ -852,6 +883,165 @@ void sg_forward_partial_path_stitcher_process_next_phase(const struct sg_stack_g
 // Frees a forward path stitcher.
 void sg_forward_partial_path_stitcher_free(struct sg_forward_partial_path_stitcher *stitcher);
 
+// Serializes a stack graph to JSON.  If `file` is not the null handle, only the nodes and edges
+// of that file are included.
+//
+// The JSON is written into the caller-owned `buffer`, which can hold `buffer_length` bytes.  The
+// length of the JSON is always written to `length_out`.  If the buffer is null or too small,
+// nothing is written into it, and you should call this function again with a buffer of at least
+// `*length_out` bytes.  The JSON is not NUL-terminated.
+enum sg_result sg_stack_graph_to_json(const struct sg_stack_graph *graph,
+                                      sg_file_handle file,
+                                      char *buffer,
+                                      size_t buffer_length,
+                                      size_t *length_out);
+
+// Loads a stack graph from JSON, as produced by `sg_stack_graph_to_json`, adding its files,
+// nodes, and edges to `graph`.  Files in the JSON must not already exist in the graph.
+enum sg_result sg_stack_graph_load_json(struct sg_stack_graph *graph,
+                                        const char *json,
+                                        size_t json_length);
+
+// Serializes the partial paths in a partial path database to JSON.  If `file` is not the null
+// handle, only the partial paths that are entirely within that file, or that connect it to the
+// root node, are included.
+//
+// The JSON is written into the caller-owned `buffer` in the same way as for
+// `sg_stack_graph_to_json`.
+enum sg_result sg_partial_path_database_to_json(const struct sg_stack_graph *graph,
+                                                struct sg_partial_path_arena *partials,
+                                                const struct sg_partial_path_database *db,
+                                                sg_file_handle file,
+                                                char *buffer,
+                                                size_t buffer_length,
+                                                size_t *length_out);
+
+// Loads partial paths from JSON, as produced by `sg_partial_path_database_to_json`, and adds them
+// to a partial path database.  The stack graph must already contain all nodes that the partial
+// paths refer to, for example by loading them using `sg_stack_graph_load_json`.
+enum sg_result sg_partial_path_database_load_json(struct sg_stack_graph *graph,
+                                                  struct sg_partial_path_arena *partials,
+                                                  struct sg_partial_path_database *db,
+                                                  const char *json,
+                                                  size_t json_length);
+
+// Opens a SQLite database for writing.  The database is created if it does not exist.  Returns
+// null if the path is not valid UTF-8, or if the database could not be opened, for example
+// because it was written by an incompatible version of this library.
+struct sg_sqlite_writer *sg_sqlite_writer_open(const char *path, size_t path_length);
+
+// Opens a new, initially empty, in-memory SQLite database for writing.  Returns null if the
+// database could not be created.
+struct sg_sqlite_writer *sg_sqlite_writer_open_in_memory(void);
+
+// Frees a SQLite writer, and closes its database.
+void sg_sqlite_writer_free(struct sg_sqlite_writer *writer);
+
+// Converts a SQLite writer into a reader for the same database.  The writer is consumed by this
+// function, and must not be used or freed afterwards.
+struct sg_sqlite_reader *sg_sqlite_writer_into_reader(struct sg_sqlite_writer *writer);
+
+// Removes all data from the database.
+enum sg_result sg_sqlite_writer_clean_all(struct sg_sqlite_writer *writer);
+
+// Removes all data for the given file, or for all files in the given directory, from the
+// database.
+enum sg_result sg_sqlite_writer_clean_file_or_directory(struct sg_sqlite_writer *writer,
+                                                        const char *path,
+                                                        size_t path_length);
+
+// Stores an error for the given file, indicating that indexing it failed.  The tag can be used
+// to identify the version of the file that was indexed, for example using a content hash.
+enum sg_result sg_sqlite_writer_store_error_for_file(struct sg_sqlite_writer *writer,
+                                                     const char *file,
+                                                     size_t file_length,
+                                                     const char *tag,
+                                                     size_t tag_length,
+                                                     const char *error,
+                                                     size_t error_length);
+
+// Stores the stack graph and partial paths of the given file, replacing any data that was
+// previously stored for the file.  All partial paths must start in the given file, or at the
+// root node.
+enum sg_result sg_sqlite_writer_store_result_for_file(struct sg_sqlite_writer *writer,
+                                                      const struct sg_stack_graph *graph,
+                                                      sg_file_handle file,
+                                                      const char *tag,
+                                                      size_t tag_length,
+                                                      struct sg_partial_path_arena *partials,
+                                                      size_t partial_path_count,
+                                                      const struct sg_partial_path *partial_paths);
+
+// Gets the status of the given file in the database.  If a tag is given, the file is reported
+// missing if the stored tag is different.  Pass a null tag to ignore the stored tag.
+enum sg_result sg_sqlite_writer_status_for_file(struct sg_sqlite_writer *writer,
+                                                const char *file,
+                                                size_t file_length,
+                                                const char *tag,
+                                                size_t tag_length,
+                                                enum sg_file_status *status_out);
+
+// Opens an existing SQLite database for reading.  Returns null if the path is not valid UTF-8,
+// the database does not exist, or the database could not be opened, for example because it was
+// written by an incompatible version of this library.
+struct sg_sqlite_reader *sg_sqlite_reader_open(const char *path, size_t path_length);
+
+// Frees a SQLite reader, closes its database, and frees all data that was loaded by it.
+void sg_sqlite_reader_free(struct sg_sqlite_reader *reader);
+
+// Returns the stack graph, partial path arena, and partial path database of the reader.  The
+// returned pointers stay valid until the reader is freed.  All handles returned by other reader
+// functions refer to these instances.
+struct sg_sqlite_reader_data sg_sqlite_reader_get(struct sg_sqlite_reader *reader);
+
+// Clears all data that was loaded by the reader.  All handles that were previously returned for
+// the reader's stack graph, partial path arena, and partial path database become invalid.
+void sg_sqlite_reader_clear(struct sg_sqlite_reader *reader);
+
+// Gets the status of the given file in the database.  If a tag is given, the file is reported
+// missing if the stored tag is different.  Pass a null tag to ignore the stored tag.
+enum sg_result sg_sqlite_reader_status_for_file(struct sg_sqlite_reader *reader,
+                                                const char *file,
+                                                size_t file_length,
+                                                const char *tag,
+                                                size_t tag_length,
+                                                enum sg_file_status *status_out);
+
+// Ensures the stack graph for the given file is loaded into the reader's stack graph, and
+// returns the handle of the file.
+enum sg_result sg_sqlite_reader_load_graph_for_file(struct sg_sqlite_reader *reader,
+                                                    const char *file,
+                                                    size_t file_length,
+                                                    sg_file_handle *file_out);
+
+// Ensures the stack graphs for the given file, or for all files in the given directory, are
+// loaded into the reader's stack graph.
+enum sg_result sg_sqlite_reader_load_graphs_for_file_or_directory(struct sg_sqlite_reader *reader,
+                                                                  const char *path,
+                                                                  size_t path_length,
+                                                                  const size_t *cancellation_flag);
+
+// Ensures all partial paths that might extend the given partial paths are loaded into the
+// reader's partial path database.  The partial paths must be from the reader's partial path
+// arena.
+enum sg_result sg_sqlite_reader_load_partial_path_extensions(struct sg_sqlite_reader *reader,
+                                                             size_t count,
+                                                             const struct sg_partial_path *partial_paths,
+                                                             const size_t *cancellation_flag);
+
+// Runs the next phase of the algorithm, using the partial paths from the reader's database.
+// Unlike `sg_forward_partial_path_stitcher_process_next_phase`, this function loads all partial
+// paths that might extend the candidate partial paths from the database before the phase is
+// processed.  The stitcher must have been created from the reader's stack graph and partial path
+// arena.
+//
+// After this method returns, you can retrieve a list of the (possibly incomplete) partial paths
+// that were encountered during this phase via the `previous_phase_partial_paths` and
+// `previous_phase_partial_paths_length` fields.
+enum sg_result sg_forward_partial_path_stitcher_process_next_phase_with_sqlite_reader(struct sg_sqlite_reader *reader,
+                                                                                      struct sg_forward_partial_path_stitcher *stitcher,
+                                                                                      const size_t *cancellation_flag);
+
 #ifdef __cplusplus
 } // extern "C"
 #endif // __cplusplus
@@ -12,6 +12,7 @@ use lsp_positions::Position;
 
 use crate::arena::Handle;
 use crate::graph::File;
+use crate::graph::InternedString;
 use crate::graph::Node;
 use crate::graph::StackGraph;
 use crate::graph::Symbol;
@@ -39,6 +40,20 @@ pub enum Assertion {
         source: AssertionSource,
         symbols: Vec<Handle<Symbol>>,
     },
+    DefinedSyntaxType {
+        source: AssertionSource,
+        syntax_type: Handle<InternedString>,
+    },
+    DefinedFullyQualifiedName {
+        source: AssertionSource,
+        fully_qualified_name: Handle<InternedString>,
+    },
+    DefinedUniquely {
+        source: AssertionSource,
+    },
+    Undefined {
+        source: AssertionSource,
+    },
 }
 
 /// Source position of an assertion
@@ -134,6 +149,32 @@ pub enum AssertionError {
         missing_symbols: Vec<Handle<Symbol>>,
         unexpected_symbols: Vec<Handle<Symbol>>,
     },
+    NoDefinitions {
+        source: AssertionSource,
+        references: Vec<Handle<Node>>,
+    },
+    IncorrectDefinitionSyntaxTypes {
+        source: AssertionSource,
+        references: Vec<Handle<Node>>,
+        syntax_type: Handle<InternedString>,
+        unexpected_paths: Vec<PartialPath>,
+    },
+    IncorrectDefinitionFullyQualifiedNames {
+        source: AssertionSource,
+        references: Vec<Handle<Node>>,
+        fully_qualified_name: Handle<InternedString>,
+        unexpected_paths: Vec<PartialPath>,
+    },
+    NotUniquelyDefined {
+        source: AssertionSource,
+        references: Vec<Handle<Node>>,
+        actual_paths: Vec<PartialPath>,
+    },
+    UnexpectedlyDefined {
+        source: AssertionSource,
+        references: Vec<Handle<Node>>,
+        actual_paths: Vec<PartialPath>,
+    },
     Cancelled(CancellationError),
 }
 
@@ -144,6 +185,19 @@ impl From<CancellationError> for AssertionError {
 }
 
 impl Assertion {
+    /// Returns the source position of this assertion.
+    pub fn source(&self) -> &AssertionSource {
+        match self {
+            Self::Defined { source, .. }
+            | Self::Defines { source, .. }
+            | Self::Refers { source, .. }
+            | Self::DefinedSyntaxType { source, .. }
+            | Self::DefinedFullyQualifiedName { source, .. }
+            | Self::DefinedUniquely { source }
+            | Self::Undefined { source } => source,
+        }
+    }
+
     /// Run this assertion against the given graph, using the given paths object for path search.
     pub fn run(
         &self,
@@ -165,19 +219,60 @@ impl Assertion {
             ),
             Self::Defines { source, symbols } => self.run_defines(graph, source, symbols),
             Self::Refers { source, symbols } => self.run_refers(graph, source, symbols),
+            Self::DefinedSyntaxType {
+                source,
+                syntax_type,
+            } => self.run_defined_syntax_type(
+                graph,
+                partials,
+                db,
+                source,
+                *syntax_type,
+                stitcher_config,
+                cancellation_flag,
+            ),
+            Self::DefinedFullyQualifiedName {
+                source,
+                fully_qualified_name,
+            } => self.run_defined_fully_qualified_name(
+                graph,
+                partials,
+                db,
+                source,
+                *fully_qualified_name,
+                stitcher_config,
+                cancellation_flag,
+            ),
+            Self::DefinedUniquely { source } => self.run_defined_uniquely(
+                graph,
+                partials,
+                db,
+                source,
+                stitcher_config,
+                cancellation_flag,
+            ),
+            Self::Undefined { source } => self.run_undefined(
+                graph,
+                partials,
+                db,
+                source,
+                stitcher_config,
+                cancellation_flag,
+            ),
         }
     }
 
-    fn run_defined(
+    /// Resolve the references at the source position, and return the references together with
+    /// the non-shadowed complete paths starting at them.
+    fn resolve(
         &self,
         graph: &StackGraph,
         partials: &mut PartialPaths,
         db: &mut Database,
         source: &AssertionSource,
-        expected_targets: &Vec<AssertionTarget>,
         stitcher_config: StitcherConfig,
         cancellation_flag: &dyn CancellationFlag,
-    ) -> Result<(), AssertionError> {
+    ) -> Result<(Vec<Handle<Node>>, Vec<PartialPath>), AssertionError> {
         let references = source.iter_references(graph).collect::<Vec<_>>();
         if references.is_empty() {
             return Err(AssertionError::NoReferences {
@@ -207,6 +302,28 @@ impl Assertion {
             }
         }
 
+        Ok((references, actual_paths))
+    }
+
+    fn run_defined(
+        &self,
+        graph: &StackGraph,
+        partials: &mut PartialPaths,
+        db: &mut Database,
+        source: &AssertionSource,
+        expected_targets: &Vec<AssertionTarget>,
+        stitcher_config: StitcherConfig,
+        cancellation_flag: &dyn CancellationFlag,
+    ) -> Result<(), AssertionError> {
+        let (references, actual_paths) = self.resolve(
+            graph,
+            partials,
+            db,
+            source,
+            stitcher_config,
+            cancellation_flag,
+        )?;
+
         let missing_targets = expected_targets
             .iter()
             .filter(|t| {
@@ -238,6 +355,162 @@ impl Assertion {
         Ok(())
     }
 
+    fn run_defined_syntax_type(
+        &self,
+        graph: &StackGraph,
+        partials: &mut PartialPaths,
+        db: &mut Database,
+        source: &AssertionSource,
+        expected_syntax_type: Handle<InternedString>,
+        stitcher_config: StitcherConfig,
+        cancellation_flag: &dyn CancellationFlag,
+    ) -> Result<(), AssertionError> {
+        let (references, actual_paths) = self.resolve(
+            graph,
+            partials,
+            db,
+            source,
+            stitcher_config,
+            cancellation_flag,
+        )?;
+        if actual_paths.is_empty() {
+            return Err(AssertionError::NoDefinitions {
+                source: source.clone(),
+                references,
+            });
+        }
+
+        let unexpected_paths = actual_paths
+            .into_iter()
+            .filter(|p| {
+                graph
+                    .source_info(p.end_node)
+                    .and_then(|si| si.syntax_type.into_option())
+                    != Some(expected_syntax_type)
+            })
+            .collect::<Vec<_>>();
+        if !unexpected_paths.is_empty() {
+            return Err(AssertionError::IncorrectDefinitionSyntaxTypes {
+                source: source.clone(),
+                references,
+                syntax_type: expected_syntax_type,
+                unexpected_paths,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn run_defined_fully_qualified_name(
+        &self,
+        graph: &StackGraph,
+        partials: &mut PartialPaths,
+        db: &mut Database,
+        source: &AssertionSource,
+        expected_fully_qualified_name: Handle<InternedString>,
+        stitcher_config: StitcherConfig,
+        cancellation_flag: &dyn CancellationFlag,
+    ) -> Result<(), AssertionError> {
+        let (references, actual_paths) = self.resolve(
+            graph,
+            partials,
+            db,
+            source,
+            stitcher_config,
+            cancellation_flag,
+        )?;
+        if actual_paths.is_empty() {
+            return Err(AssertionError::NoDefinitions {
+                source: source.clone(),
+                references,
+            });
+        }
+
+        let unexpected_paths = actual_paths
+            .into_iter()
+            .filter(|p| {
+                graph
+                    .source_info(p.end_node)
+                    .and_then(|si| si.fully_qualified_name.into_option())
+                    != Some(expected_fully_qualified_name)
+            })
+            .collect::<Vec<_>>();
+        if !unexpected_paths.is_empty() {
+            return Err(AssertionError::IncorrectDefinitionFullyQualifiedNames {
+                source: source.clone(),
+                references,
+                fully_qualified_name: expected_fully_qualified_name,
+                unexpected_paths,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn run_defined_uniquely(
+        &self,
+        graph: &StackGraph,
+        partials: &mut PartialPaths,
+        db: &mut Database,
+        source: &AssertionSource,
+        stitcher_config: StitcherConfig,
+        cancellation_flag: &dyn CancellationFlag,
+    ) -> Result<(), AssertionError> {
+        let (references, actual_paths) = self.resolve(
+            graph,
+            partials,
+            db,
+            source,
+            stitcher_config,
+            cancellation_flag,
+        )?;
+        if actual_paths.is_empty() {
+            return Err(AssertionError::NoDefinitions {
+                source: source.clone(),
+                references,
+            });
+        }
+
+        if actual_paths.iter().map(|p| p.end_node).unique().count() > 1 {
+            return Err(AssertionError::NotUniquelyDefined {
+                source: source.clone(),
+                references,
+                actual_paths,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn run_undefined(
+        &self,
+        graph: &StackGraph,
+        partials: &mut PartialPaths,
+        db: &mut Database,
+        source: &AssertionSource,
+        stitcher_config: StitcherConfig,
+        cancellation_flag: &dyn CancellationFlag,
+    ) -> Result<(), AssertionError> {
+        let (references, actual_paths) = self.resolve(
+            graph,
+            partials,
+            db,
+            source,
+            stitcher_config,
+            cancellation_flag,
+        )?;
+
+        if !actual_paths.is_empty() {
+            return Err(AssertionError::UnexpectedlyDefined {
+                source: source.clone(),
+                references,
+                actual_paths,
+            });
+        }
+
+        Ok(())
+    }
+
     fn run_defines(
         &self,
         graph: &StackGraph,
@@ -10,10 +10,9 @@
 #![allow(non_camel_case_types)]
 
 use std::convert::TryInto;
+use std::os::raw::c_char;
 use std::sync::atomic::AtomicUsize;
 
-use libc::c_char;
-
 use crate::arena::Handle;
 use crate::graph::File;
 use crate::graph::InternedString;
@@ -37,6 +36,7 @@ use crate::CancellationError;
 use crate::CancellationFlag;
 
 /// Contains all of the nodes and edges that make up a stack graph.
+#[repr(transparent)]
 pub struct sg_stack_graph {
     pub inner: StackGraph,
 }
@@ -56,6 +56,7 @@ pub extern "C" fn sg_stack_graph_free(graph: *mut sg_stack_graph) {
 }
 
 /// Manages the state of a collection of partial paths to be used in the path-stitching algorithm.
+#[repr(transparent)]
 pub struct sg_partial_path_arena {
     pub inner: PartialPaths,
 }
@@ -83,6 +84,7 @@ pub extern "C" fn sg_partial_path_arena_free(partials: *mut sg_partial_path_aren
 /// We've written the path-stitching algorithm so that you have a chance to only load in the
 /// partial paths that are actually needed, placing them into a sg_partial_path_database instance
 /// as they're needed.
+#[repr(transparent)]
 pub struct sg_partial_path_database {
     pub inner: Database,
 }
@@ -638,6 +640,10 @@ pub struct sg_source_info {
     /// The fully qualified name is a representation of the symbol that captures its name and its
     /// embedded context (e.g. `foo.bar` for the symbol `bar` defined in the module `foo`).
     pub fully_qualified_name: sg_string_handle,
+    /// The documentation of the syntax entity this node represents, such as the doc comment of a
+    /// function or class. It is stored as given by the language, which may include comment markers
+    /// such as `/**` or `*/`, that consumers must strip if they need plain text.
+    pub documentation: sg_string_handle,
 }
 
 /// All of the position information that we have about a range of content in a source file
@@ -1594,6 +1600,8 @@ impl CancellationFlag for AtomicUsizeCancellationFlag<'_> {
 pub enum sg_result {
     SG_RESULT_SUCCESS,
     SG_RESULT_CANCELLED,
+    SG_RESULT_STORAGE_ERROR,
+    SG_RESULT_SERIALIZATION_ERROR,
 }
 
 impl<T> From<Result<T, CancellationError>> for sg_result {
@@ -1604,3 +1612,15 @@ impl<T> From<Result<T, CancellationError>> for sg_result {
         }
     }
 }
+
+//-------------------------------------------------------------------------------------------------
+// JSON
+
+#[cfg(feature = "serde")]
+pub mod json;
+
+//-------------------------------------------------------------------------------------------------
+// Storage
+
+#[cfg(feature = "storage")]
+pub mod storage;
@@ -0,0 +1,142 @@
+// -*- coding: utf-8 -*-
+// ------------------------------------------------------------------------------------------------
+// Copyright © 2023, stack-graphs authors.
+// Licensed under either of Apache License, Version 2.0, or MIT license, at your option.
+// Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
+// ------------------------------------------------------------------------------------------------
+
+//! Defines the C API for serializing stack graphs and partial path databases to JSON, and for
+//! loading them back.
+//!
+//! The JSON format is the same as the one produced by the Rust [`serde`][crate::serde] module.
+
+use std::os::raw::c_char;
+
+use crate::c::sg_file_handle;
+use crate::c::sg_partial_path_arena;
+use crate::c::sg_partial_path_database;
+use crate::c::sg_result;
+use crate::c::sg_stack_graph;
+use crate::c::SG_NULL_HANDLE;
+use crate::serde::FileFilter;
+use crate::serde::Filter;
+use crate::serde::NoFilter;
+
+/// Returns the filter to use for the given file handle.  The null handle selects all files.
+fn file_filter(file: sg_file_handle) -> Box<dyn Filter> {
+    if file == SG_NULL_HANDLE {
+        Box::new(NoFilter)
+    } else {
+        Box::new(FileFilter(file.into()))
+    }
+}
+
+/// Copies the serialized JSON into the caller's buffer if it fits, and always sets `length_out`
+/// to the length of the JSON.
+fn write_json<T: serde::Serialize>(
+    value: &T,
+    buffer: *mut c_char,
+    buffer_length: usize,
+    length_out: *mut usize,
+) -> sg_result {
+    let json = match serde_json::to_vec(value) {
+        Ok(json) => json,
+        Err(_) => return sg_result::SG_RESULT_SERIALIZATION_ERROR,
+    };
+    unsafe { *length_out = json.len() };
+    if !buffer.is_null() && json.len() <= buffer_length {
+        let buffer = unsafe { std::slice::from_raw_parts_mut(buffer as *mut u8, json.len()) };
+        buffer.copy_from_slice(&json);
+    }
+    sg_result::SG_RESULT_SUCCESS
+}
+
+/// Serializes a stack graph to JSON.  If `file` is not the null handle, only the nodes and edges
+/// of that file are included.
+///
+/// The JSON is written into the caller-owned `buffer`, which can hold `buffer_length` bytes.  The
+/// length of the JSON is always written to `length_out`.  If the buffer is null or too small,
+/// nothing is written into it, and you should call this function again with a buffer of at least
+/// `*length_out` bytes.  The JSON is not NUL-terminated.
+#[no_mangle]
+pub extern "C" fn sg_stack_graph_to_json(
+    graph: *const sg_stack_graph,
+    file: sg_file_handle,
+    buffer: *mut c_char,
+    buffer_length: usize,
+    length_out: *mut usize,
+) -> sg_result {
+    let graph = unsafe { &(*graph).inner };
+    let filter = file_filter(file);
+    let value = graph.to_serializable_filter(&*filter);
+    write_json(&value, buffer, buffer_length, length_out)
+}
+
+/// Loads a stack graph from JSON, as produced by `sg_stack_graph_to_json`, adding its files,
+/// nodes, and edges to `graph`.  Files in the JSON must not already exist in the graph.
+#[no_mangle]
+pub extern "C" fn sg_stack_graph_load_json(
+    graph: *mut sg_stack_graph,
+    json: *const c_char,
+    json_length: usize,
+) -> sg_result {
+    let graph = unsafe { &mut (*graph).inner };
+    let json = unsafe { std::slice::from_raw_parts(json as *const u8, json_length) };
+    let value = match serde_json::from_slice::<crate::serde::StackGraph>(json) {
+        Ok(value) => value,
+        Err(_) => return sg_result::SG_RESULT_SERIALIZATION_ERROR,
+    };
+    match value.load_into(graph) {
+        Ok(_) => sg_result::SG_RESULT_SUCCESS,
+        Err(_) => sg_result::SG_RESULT_SERIALIZATION_ERROR,
+    }
+}
+
+/// Serializes the partial paths in a partial path database to JSON.  If `file` is not the null
+/// handle, only the partial paths that are entirely within that file, or that connect it to the
+/// root node, are included.
+///
+/// The JSON is written into the caller-owned `buffer` in the same way as for
+/// `sg_stack_graph_to_json`.
+#[no_mangle]
+pub extern "C" fn sg_partial_path_database_to_json(
+    graph: *const sg_stack_graph,
+    partials: *mut sg_partial_path_arena,
+    db: *const sg_partial_path_database,
+    file: sg_file_handle,
+    buffer: *mut c_char,
+    buffer_length: usize,
+    length_out: *mut usize,
+) -> sg_result {
+    let graph = unsafe { &(*graph).inner };
+    let partials = unsafe { &mut (*partials).inner };
+    let db = unsafe { &(*db).inner };
+    let filter = file_filter(file);
+    let value = db.to_serializable_filter(graph, partials, &*filter);
+    write_json(&value, buffer, buffer_length, length_out)
+}
+
+/// Loads partial paths from JSON, as produced by `sg_partial_path_database_to_json`, and adds them
+/// to a partial path database.  The stack graph must already contain all nodes that the partial
+/// paths refer to, for example by loading them using `sg_stack_graph_load_json`.
+#[no_mangle]
+pub extern "C" fn sg_partial_path_database_load_json(
+    graph: *mut sg_stack_graph,
+    partials: *mut sg_partial_path_arena,
+    db: *mut sg_partial_path_database,
+    json: *const c_char,
+    json_length: usize,
+) -> sg_result {
+    let graph = unsafe { &mut (*graph).inner };
+    let partials = unsafe { &mut (*partials).inner };
+    let db = unsafe { &mut (*db).inner };
+    let json = unsafe { std::slice::from_raw_parts(json as *const u8, json_length) };
+    let value = match serde_json::from_slice::<crate::serde::Database>(json) {
+        Ok(value) => value,
+        Err(_) => return sg_result::SG_RESULT_SERIALIZATION_ERROR,
+    };
+    match value.load_into(graph, partials, db) {
+        Ok(_) => sg_result::SG_RESULT_SUCCESS,
+        Err(_) => sg_result::SG_RESULT_SERIALIZATION_ERROR,
+    }
+}
@@ -0,0 +1,436 @@
+// -*- coding: utf-8 -*-
+// ------------------------------------------------------------------------------------------------
+// Copyright © 2023, stack-graphs authors.
+// Licensed under either of Apache License, Version 2.0, or MIT license, at your option.
+// Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
+// ------------------------------------------------------------------------------------------------
+
+//! Defines the C API for storing stack graphs and partial paths in SQLite databases.
+//!
+//! Databases written using this API use the same format as the databases written by the Rust
+//! [`storage`][crate::storage] module, so they can be shared with other tools that use it.
+
+use std::os::raw::c_char;
+use std::path::Path;
+use std::sync::atomic::AtomicUsize;
+
+use crate::c::sg_file_handle;
+use crate::c::sg_forward_partial_path_stitcher;
+use crate::c::sg_partial_path;
+use crate::c::sg_partial_path_arena;
+use crate::c::sg_partial_path_database;
+use crate::c::sg_result;
+use crate::c::sg_stack_graph;
+use crate::c::AtomicUsizeCancellationFlag;
+use crate::c::InternalForwardPartialPathStitcher;
+use crate::partial::PartialPath;
+use crate::storage::FileStatus;
+use crate::storage::SQLiteReader;
+use crate::storage::SQLiteWriter;
+use crate::storage::StorageError;
+use crate::CancellationFlag;
+
+/// Writes stack graphs and partial paths to a SQLite database.
+pub struct sg_sqlite_writer {
+    pub inner: SQLiteWriter,
+}
+
+/// Reads stack graphs and partial paths from a SQLite database.  Data is loaded lazily into a
+/// stack graph, partial path arena, and partial path database that are owned by the reader.
+pub struct sg_sqlite_reader {
+    pub inner: SQLiteReader,
+}
+
+/// The status of a file in a SQLite database.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum sg_file_status {
+    /// The file is not in the database, or its tag does not match.
+    SG_FILE_STATUS_MISSING,
+    /// The file was indexed successfully.
+    SG_FILE_STATUS_INDEXED,
+    /// Indexing the file failed.
+    SG_FILE_STATUS_ERROR,
+}
+
+impl From<FileStatus> for sg_file_status {
+    fn from(status: FileStatus) -> Self {
+        match status {
+            FileStatus::Missing => Self::SG_FILE_STATUS_MISSING,
+            FileStatus::Indexed => Self::SG_FILE_STATUS_INDEXED,
+            FileStatus::Error(_) => Self::SG_FILE_STATUS_ERROR,
+        }
+    }
+}
+
+impl<T> From<Result<T, StorageError>> for sg_result {
+    fn from(result: Result<T, StorageError>) -> Self {
+        match result {
+            Ok(_) => Self::SG_RESULT_SUCCESS,
+            Err(StorageError::Cancelled(_)) => Self::SG_RESULT_CANCELLED,
+            Err(_) => Self::SG_RESULT_STORAGE_ERROR,
+        }
+    }
+}
+
+/// Returns the string with the given content and length, or `None` if it is not valid UTF-8.
+fn str_from_raw_parts<'a>(string: *const c_char, length: usize) -> Option<&'a str> {
+    let bytes = unsafe { std::slice::from_raw_parts(string as *const u8, length) };
+    std::str::from_utf8(bytes).ok()
+}
+
+//-------------------------------------------------------------------------------------------------
+// Writers
+
+/// Opens a SQLite database for writing.  The database is created if it does not exist.  Returns
+/// null if the path is not valid UTF-8, or if the database could not be opened, for example
+/// because it was written by an incompatible version of this library.
+#[no_mangle]
+pub extern "C" fn sg_sqlite_writer_open(
+    path: *const c_char,
+    path_length: usize,
+) -> *mut sg_sqlite_writer {
+    let path = match str_from_raw_parts(path, path_length) {
+        Some(path) => path,
+        None => return std::ptr::null_mut(),
+    };
+    match SQLiteWriter::open(path) {
+        Ok(inner) => Box::into_raw(Box::new(sg_sqlite_writer { inner })),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Opens a new, initially empty, in-memory SQLite database for writing.  Returns null if the
+/// database could not be created.
+#[no_mangle]
+pub extern "C" fn sg_sqlite_writer_open_in_memory() -> *mut sg_sqlite_writer {
+    match SQLiteWriter::open_in_memory() {
+        Ok(inner) => Box::into_raw(Box::new(sg_sqlite_writer { inner })),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Frees a SQLite writer, and closes its database.
+#[no_mangle]
+pub extern "C" fn sg_sqlite_writer_free(writer: *mut sg_sqlite_writer) {
+    drop(unsafe { Box::from_raw(writer) })
+}
+
+/// Converts a SQLite writer into a reader for the same database.  The writer is consumed by this
+/// function, and must not be used or freed afterwards.
+#[no_mangle]
+pub extern "C" fn sg_sqlite_writer_into_reader(
+    writer: *mut sg_sqlite_writer,
+) -> *mut sg_sqlite_reader {
+    let writer = unsafe { Box::from_raw(writer) };
+    Box::into_raw(Box::new(sg_sqlite_reader {
+        inner: writer.inner.into_reader(),
+    }))
+}
+
+/// Removes all data from the database.
+#[no_mangle]
+pub extern "C" fn sg_sqlite_writer_clean_all(writer: *mut sg_sqlite_writer) -> sg_result {
+    let writer = unsafe { &mut (*writer).inner };
+    writer.clean_all().into()
+}
+
+/// Removes all data for the given file, or for all files in the given directory, from the
+/// database.
+#[no_mangle]
+pub extern "C" fn sg_sqlite_writer_clean_file_or_directory(
+    writer: *mut sg_sqlite_writer,
+    path: *const c_char,
+    path_length: usize,
+) -> sg_result {
+    let writer = unsafe { &mut (*writer).inner };
+    let path = match str_from_raw_parts(path, path_length) {
+        Some(path) => path,
+        None => return sg_result::SG_RESULT_STORAGE_ERROR,
+    };
+    writer.clean_file_or_directory(Path::new(path)).into()
+}
+
+/// Stores an error for the given file, indicating that indexing it failed.  The tag can be used
+/// to identify the version of the file that was indexed, for example using a content hash.
+#[no_mangle]
+pub extern "C" fn sg_sqlite_writer_store_error_for_file(
+    writer: *mut sg_sqlite_writer,
+    file: *const c_char,
+    file_length: usize,
+    tag: *const c_char,
+    tag_length: usize,
+    error: *const c_char,
+    error_length: usize,
+) -> sg_result {
+    let writer = unsafe { &mut (*writer).inner };
+    let (file, tag, error) = match (
+        str_from_raw_parts(file, file_length),
+        str_from_raw_parts(tag, tag_length),
+        str_from_raw_parts(error, error_length),
+    ) {
+        (Some(file), Some(tag), Some(error)) => (file, tag, error),
+        _ => return sg_result::SG_RESULT_STORAGE_ERROR,
+    };
+    writer
+        .store_error_for_file(Path::new(file), tag, error)
+        .into()
+}
+
+/// Stores the stack graph and partial paths of the given file, replacing any data that was
+/// previously stored for the file.  All partial paths must start in the given file, or at the
+/// root node.
+#[no_mangle]
+pub extern "C" fn sg_sqlite_writer_store_result_for_file(
+    writer: *mut sg_sqlite_writer,
+    graph: *const sg_stack_graph,
+    file: sg_file_handle,
+    tag: *const c_char,
+    tag_length: usize,
+    partials: *mut sg_partial_path_arena,
+    partial_path_count: usize,
+    partial_paths: *const sg_partial_path,
+) -> sg_result {
+    let writer = unsafe { &mut (*writer).inner };
+    let graph = unsafe { &(*graph).inner };
+    let partials = unsafe { &mut (*partials).inner };
+    let partial_paths = unsafe {
+        std::slice::from_raw_parts(partial_paths as *const PartialPath, partial_path_count)
+    };
+    let tag = match str_from_raw_parts(tag, tag_length) {
+        Some(tag) => tag,
+        None => return sg_result::SG_RESULT_STORAGE_ERROR,
+    };
+    writer
+        .store_result_for_file(graph, file.into(), tag, partials, partial_paths)
+        .into()
+}
+
+/// Gets the status of the given file in the database.  If a tag is given, the file is reported
+/// missing if the stored tag is different.  Pass a null tag to ignore the stored tag.
+#[no_mangle]
+pub extern "C" fn sg_sqlite_writer_status_for_file(
+    writer: *mut sg_sqlite_writer,
+    file: *const c_char,
+    file_length: usize,
+    tag: *const c_char,
+    tag_length: usize,
+    status_out: *mut sg_file_status,
+) -> sg_result {
+    let writer = unsafe { &mut (*writer).inner };
+    let (file, tag) = match file_and_tag_from_raw_parts(file, file_length, tag, tag_length) {
+        Some(file_and_tag) => file_and_tag,
+        None => return sg_result::SG_RESULT_STORAGE_ERROR,
+    };
+    let status = match writer.status_for_file(file, tag) {
+        Ok(status) => status,
+        Err(err) => return Err::<(), _>(err).into(),
+    };
+    unsafe { *status_out = status.into() };
+    sg_result::SG_RESULT_SUCCESS
+}
+
+fn file_and_tag_from_raw_parts<'a>(
+    file: *const c_char,
+    file_length: usize,
+    tag: *const c_char,
+    tag_length: usize,
+) -> Option<(&'a str, Option<&'a str>)> {
+    let file = str_from_raw_parts(file, file_length)?;
+    let tag = if tag.is_null() {
+        None
+    } else {
+        Some(str_from_raw_parts(tag, tag_length)?)
+    };
+    Some((file, tag))
+}
+
+//-------------------------------------------------------------------------------------------------
+// Readers
+
+/// Opens an existing SQLite database for reading.  Returns null if the path is not valid UTF-8,
+/// the database does not exist, or the database could not be opened, for example because it was
+/// written by an incompatible version of this library.
+#[no_mangle]
+pub extern "C" fn sg_sqlite_reader_open(
+    path: *const c_char,
+    path_length: usize,
+) -> *mut sg_sqlite_reader {
+    let path = match str_from_raw_parts(path, path_length) {
+        Some(path) => path,
+        None => return std::ptr::null_mut(),
+    };
+    match SQLiteReader::open(path) {
+        Ok(inner) => Box::into_raw(Box::new(sg_sqlite_reader { inner })),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Frees a SQLite reader, closes its database, and frees all data that was loaded by it.
+#[no_mangle]
+pub extern "C" fn sg_sqlite_reader_free(reader: *mut sg_sqlite_reader) {
+    drop(unsafe { Box::from_raw(reader) })
+}
+
+/// The stack graph, partial path arena, and partial path database that hold the data loaded by a
+/// SQLite reader.  They are owned by the reader, and must not be freed.
+#[repr(C)]
+pub struct sg_sqlite_reader_data {
+    pub graph: *mut sg_stack_graph,
+    pub partials: *mut sg_partial_path_arena,
+    pub db: *mut sg_partial_path_database,
+}
+
+/// Returns the stack graph, partial path arena, and partial path database of the reader.  The
+/// returned pointers stay valid until the reader is freed.  All handles returned by other reader
+/// functions refer to these instances.
+#[no_mangle]
+pub extern "C" fn sg_sqlite_reader_get(reader: *mut sg_sqlite_reader) -> sg_sqlite_reader_data {
+    let reader = unsafe { &mut (*reader).inner };
+    let (graph, partials, db) = reader.get();
+    // These casts are safe because the C API types are transparent wrappers.
+    sg_sqlite_reader_data {
+        graph: graph as *mut _ as *mut sg_stack_graph,
+        partials: partials as *mut _ as *mut sg_partial_path_arena,
+        db: db as *mut _ as *mut sg_partial_path_database,
+    }
+}
+
+/// Clears all data that was loaded by the reader.  All handles that were previously returned for
+/// the reader's stack graph, partial path arena, and partial path database become invalid.
+#[no_mangle]
+pub extern "C" fn sg_sqlite_reader_clear(reader: *mut sg_sqlite_reader) {
+    let reader = unsafe { &mut (*reader).inner };
+    reader.clear();
+}
+
+/// Gets the status of the given file in the database.  If a tag is given, the file is reported
+/// missing if the stored tag is different.  Pass a null tag to ignore the stored tag.
+#[no_mangle]
+pub extern "C" fn sg_sqlite_reader_status_for_file(
+    reader: *mut sg_sqlite_reader,
+    file: *const c_char,
+    file_length: usize,
+    tag: *const c_char,
+    tag_length: usize,
+    status_out: *mut sg_file_status,
+) -> sg_result {
+    let reader = unsafe { &mut (*reader).inner };
+    let (file, tag) = match file_and_tag_from_raw_parts(file, file_length, tag, tag_length) {
+        Some(file_and_tag) => file_and_tag,
+        None => return sg_result::SG_RESULT_STORAGE_ERROR,
+    };
+    let status = match reader.status_for_file(file, tag) {
+        Ok(status) => status,
+        Err(err) => return Err::<(), _>(err).into(),
+    };
+    unsafe { *status_out = status.into() };
+    sg_result::SG_RESULT_SUCCESS
+}
+
+/// Ensures the stack graph for the given file is loaded into the reader's stack graph, and
+/// returns the handle of the file.
+#[no_mangle]
+pub extern "C" fn sg_sqlite_reader_load_graph_for_file(
+    reader: *mut sg_sqlite_reader,
+    file: *const c_char,
+    file_length: usize,
+    file_out: *mut sg_file_handle,
+) -> sg_result {
+    let reader = unsafe { &mut (*reader).inner };
+    let file = match str_from_raw_parts(file, file_length) {
+        Some(file) => file,
+        None => return sg_result::SG_RESULT_STORAGE_ERROR,
+    };
+    let handle = match reader.load_graph_for_file(file) {
+        Ok(handle) => handle,
+        Err(err) => return Err::<(), _>(err).into(),
+    };
+    unsafe { *file_out = handle.as_u32() };
+    sg_result::SG_RESULT_SUCCESS
+}
+
+/// Ensures the stack graphs for the given file, or for all files in the given directory, are
+/// loaded into the reader's stack graph.
+#[no_mangle]
+pub extern "C" fn sg_sqlite_reader_load_graphs_for_file_or_directory(
+    reader: *mut sg_sqlite_reader,
+    path: *const c_char,
+    path_length: usize,
+    cancellation_flag: *const usize,
+) -> sg_result {
+    let reader = unsafe { &mut (*reader).inner };
+    let path = match str_from_raw_parts(path, path_length) {
+        Some(path) => path,
+        None => return sg_result::SG_RESULT_STORAGE_ERROR,
+    };
+    let cancellation_flag: Option<&AtomicUsize> =
+        unsafe { std::mem::transmute(cancellation_flag.as_ref()) };
+    reader
+        .load_graphs_for_file_or_directory(
+            Path::new(path),
+            &AtomicUsizeCancellationFlag(cancellation_flag),
+        )
+        .into()
+}
+
+/// Ensures all partial paths that might extend the given partial paths are loaded into the
+/// reader's partial path database.  The partial paths must be from the reader's partial path
+/// arena.
+#[no_mangle]
+pub extern "C" fn sg_sqlite_reader_load_partial_path_extensions(
+    reader: *mut sg_sqlite_reader,
+    count: usize,
+    partial_paths: *const sg_partial_path,
+    cancellation_flag: *const usize,
+) -> sg_result {
+    let reader = unsafe { &mut (*reader).inner };
+    let partial_paths =
+        unsafe { std::slice::from_raw_parts(partial_paths as *const PartialPath, count) };
+    let cancellation_flag: Option<&AtomicUsize> =
+        unsafe { std::mem::transmute(cancellation_flag.as_ref()) };
+    let cancellation_flag = AtomicUsizeCancellationFlag(cancellation_flag);
+    for partial_path in partial_paths {
+        if let Err(err) = reader.load_partial_path_extensions(partial_path, &cancellation_flag) {
+            return Err::<(), _>(err).into();
+        }
+    }
+    sg_result::SG_RESULT_SUCCESS
+}
+
+//-------------------------------------------------------------------------------------------------
+// Path stitching
+
+/// Runs the next phase of the algorithm, using the partial paths from the reader's database.
+/// Unlike `sg_forward_partial_path_stitcher_process_next_phase`, this function loads all partial
+/// paths that might extend the candidate partial paths from the database before the phase is
+/// processed.  The stitcher must have been created from the reader's stack graph and partial path
+/// arena.
+///
+/// After this method returns, you can retrieve a list of the (possibly incomplete) partial paths
+/// that were encountered during this phase via the `previous_phase_partial_paths` and
+/// `previous_phase_partial_paths_length` fields.
+#[no_mangle]
+pub extern "C" fn sg_forward_partial_path_stitcher_process_next_phase_with_sqlite_reader(
+    reader: *mut sg_sqlite_reader,
+    stitcher: *mut sg_forward_partial_path_stitcher,
+    cancellation_flag: *const usize,
+) -> sg_result {
+    let reader = unsafe { &mut (*reader).inner };
+    let stitcher = unsafe { &mut *(stitcher as *mut InternalForwardPartialPathStitcher) };
+    let cancellation_flag: Option<&AtomicUsize> =
+        unsafe { std::mem::transmute(cancellation_flag.as_ref()) };
+    let cancellation_flag = AtomicUsizeCancellationFlag(cancellation_flag);
+    if let Err(err) = cancellation_flag.check("processing next phase") {
+        return Err::<(), _>(err).into();
+    }
+    for partial_path in stitcher.stitcher.previous_phase_partial_paths() {
+        if let Err(err) = reader.load_partial_path_extensions(partial_path, &cancellation_flag) {
+            return Err::<(), _>(err).into();
+        }
+    }
+    stitcher.stitcher.process_next_phase(reader, |_, _, _| true);
+    let (_, partials, _) = reader.get();
+    stitcher.update_previous_phase_partial_paths(partials);
+    sg_result::SG_RESULT_SUCCESS
+}
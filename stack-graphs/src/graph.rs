@@ -1359,6 +1359,10 @@ pub struct SourceInfo {
     /// The fully qualified name is a representation of the symbol that captures its name and its
     /// embedded context (e.g. `foo.bar` for the symbol `bar` defined in the module `foo`).
     pub fully_qualified_name: ControlledOption<Handle<InternedString>>,
+    /// The documentation of the syntax entity this node represents, such as the doc comment of a
+    /// function or class. It is stored as given by the language, which may include comment markers
+    /// such as `/**` or `*/`, that consumers must strip if they need plain text.
+    pub documentation: ControlledOption<Handle<InternedString>>,
 }
 
 impl StackGraph {
@@ -1620,6 +1624,11 @@ impl StackGraph {
                             .into(),
                         definiens_span: source_info.definiens_span.clone(),
                         fully_qualified_name: ControlledOption::default(),
+                        documentation: source_info
+                            .documentation
+                            .into_option()
+                            .map(|doc| self.add_string(&other[doc]))
+                            .into(),
                     };
                 }
                 if let Some(debug_info) = other.node_debug_info(other_node) {
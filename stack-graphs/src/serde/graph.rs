@@ -143,6 +143,11 @@ impl StackGraph {
                             .as_ref()
                             .map(|st| graph.add_string(&st))
                             .into(),
+                        documentation: source_info
+                            .documentation
+                            .as_ref()
+                            .map(|doc| graph.add_string(&doc))
+                            .into(),
                         ..Default::default()
                     };
                 }
@@ -332,6 +337,7 @@ impl Node {
 pub struct SourceInfo {
     pub span: lsp_positions::Span,
     pub syntax_type: Option<String>,
+    pub documentation: Option<String>,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -473,6 +479,10 @@ impl crate::graph::StackGraph {
         self.source_info(handle).map(|info| SourceInfo {
             span: info.span.clone(),
             syntax_type: info.syntax_type.into_option().map(|ty| self[ty].to_owned()),
+            documentation: info
+                .documentation
+                .into_option()
+                .map(|doc| self[doc].to_owned()),
         })
     }
 
@@ -34,12 +34,16 @@ use crate::stitching::ForwardCandidates;
 use crate::CancellationError;
 use crate::CancellationFlag;
 
-const VERSION: usize = 6;
+const VERSION: usize = 7;
 
 const SCHEMA: &str = r#"
         CREATE TABLE metadata (
             version INTEGER NOT NULL
         ) STRICT;
+        CREATE TABLE metadata_values (
+            key   TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        ) STRICT;
         CREATE TABLE graphs (
             file   TEXT PRIMARY KEY,
             tag    TEXT NOT NULL,
@@ -128,6 +132,58 @@ pub struct FileEntry {
     pub status: FileStatus,
 }
 
+/// Size information about a file in the database. Sizes are in bytes.
+pub struct FileSummary {
+    pub path: PathBuf,
+    pub status: FileStatus,
+    /// The size of the stored graph.
+    pub graph_size: usize,
+    pub graph_node_count: usize,
+    pub graph_edge_count: usize,
+    pub node_path_count: usize,
+    /// The total size of the stored node paths.
+    pub node_paths_size: usize,
+    pub root_path_count: usize,
+    /// The total size of the stored root paths.
+    pub root_paths_size: usize,
+}
+
+/// The number of root paths stored for a symbol stack precondition.
+pub struct SymbolStackSummary {
+    pub symbols: Vec<String>,
+    pub has_variable: bool,
+    pub root_path_count: usize,
+}
+
+impl SymbolStackSummary {
+    fn from_storage_key(key: &str, root_path_count: usize) -> Self {
+        let (has_variable, symbols) = key.split_once('\u{241E}').unwrap_or(("X", key));
+        Self {
+            symbols: if symbols.is_empty() {
+                Vec::new()
+            } else {
+                symbols.split('\u{241F}').map(String::from).collect()
+            },
+            has_variable: has_variable == "V",
+            root_path_count,
+        }
+    }
+}
+
+impl std::fmt::Display for SymbolStackSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.symbols.join(","))?;
+        if self.has_variable {
+            if self.symbols.is_empty() {
+                write!(f, "%")?;
+            } else {
+                write!(f, ",%")?;
+            }
+        }
+        Ok(())
+    }
+}
+
 /// An iterator over a query returning rows with (path,tag,error) tuples.
 pub struct Files<'a, P: Params>(Statement<'a>, P);
 
@@ -183,7 +239,7 @@ impl SQLiteWriter {
         Ok(())
     }
 
-    /// Clean all data from the database.
+    /// Clean all data from the database, including metadata values.
     pub fn clean_all(&mut self) -> Result<usize> {
         let tx = self.conn.transaction()?;
         let count = Self::clean_all_inner(&tx)?;
@@ -203,6 +259,10 @@ impl SQLiteWriter {
             let mut stmt = conn.prepare_cached("DELETE FROM root_paths")?;
             stmt.execute([])?;
         }
+        {
+            let mut stmt = conn.prepare_cached("DELETE FROM metadata_values")?;
+            stmt.execute([])?;
+        }
         let count = {
             let mut stmt = conn.prepare_cached("DELETE FROM graphs")?;
             stmt.execute([])?
@@ -409,6 +469,31 @@ impl SQLiteWriter {
         status_for_file(&self.conn, file, tag)
     }
 
+    /// Set a metadata value, replacing any previous value for the key. Metadata values can be used
+    /// by clients to record information about the database as a whole, such as the version of the
+    /// sources that were indexed.
+    pub fn set_metadata_value(&mut self, key: &str, value: &str) -> Result<()> {
+        let mut stmt = self
+            .conn
+            .prepare_cached("INSERT OR REPLACE INTO metadata_values (key, value) VALUES (?, ?)")?;
+        stmt.execute([key, value])?;
+        Ok(())
+    }
+
+    /// Get a metadata value, if one was set for the key.
+    pub fn metadata_value(&mut self, key: &str) -> Result<Option<String>> {
+        metadata_value(&self.conn, key)
+    }
+
+    /// Remove a metadata value, if one was set for the key.
+    pub fn remove_metadata_value(&mut self, key: &str) -> Result<()> {
+        let mut stmt = self
+            .conn
+            .prepare_cached("DELETE FROM metadata_values WHERE key = ?")?;
+        stmt.execute([key])?;
+        Ok(())
+    }
+
     /// Convert this writer into a reader for the same database.
     pub fn into_reader(self) -> SQLiteReader {
         SQLiteReader {
@@ -496,6 +581,11 @@ impl SQLiteReader {
         status_for_file(&self.conn, file, tag)
     }
 
+    /// Get a metadata value, if one was set for the key.
+    pub fn metadata_value(&mut self, key: &str) -> Result<Option<String>> {
+        metadata_value(&self.conn, key)
+    }
+
     /// Returns a [`Files`][] value that can be used to iterate over all files in the database.
     pub fn list_all<'a>(&'a mut self) -> Result<Files<'a, ()>> {
         self.conn
@@ -523,6 +613,68 @@ impl SQLiteReader {
             .map_err(|e| e.into())
     }
 
+    /// Returns size information for all files in the database, ordered by path. This decodes the
+    /// graphs of all files, but does not load them.
+    pub fn file_summaries(&mut self) -> Result<Vec<FileSummary>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT g.file, g.error, g.value,
+                   coalesce(f.count, 0), coalesce(f.size, 0),
+                   coalesce(r.count, 0), coalesce(r.size, 0)
+            FROM graphs g
+            LEFT JOIN (SELECT file, count(*) AS count, sum(length(value)) AS size
+                       FROM file_paths GROUP BY file) f ON f.file = g.file
+            LEFT JOIN (SELECT file, count(*) AS count, sum(length(value)) AS size
+                       FROM root_paths GROUP BY file) r ON r.file = g.file
+            ORDER BY g.file
+            "#,
+        )?;
+        let mut rows = stmt.query([])?;
+        let mut summaries = Vec::new();
+        while let Some(row) = rows.next()? {
+            let value = row.get::<_, Vec<u8>>(2)?;
+            let (graph, _): (serde::StackGraph, usize) =
+                bincode::decode_from_slice(&value, BINCODE_CONFIG)?;
+            summaries.push(FileSummary {
+                path: PathBuf::from(row.get::<_, String>(0)?),
+                status: row.get_ref(1)?.into(),
+                graph_size: value.len(),
+                graph_node_count: graph.nodes.data.len(),
+                graph_edge_count: graph.edges.data.len(),
+                node_path_count: row.get(3)?,
+                node_paths_size: row.get(4)?,
+                root_path_count: row.get(5)?,
+                root_paths_size: row.get(6)?,
+            });
+        }
+        Ok(summaries)
+    }
+
+    /// Returns the symbol stacks with the most root paths, at most `limit` of them, in descending
+    /// order of their number of root paths.
+    pub fn symbol_stacks_by_root_path_count(
+        &mut self,
+        limit: usize,
+    ) -> Result<Vec<SymbolStackSummary>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT symbol_stack, count(*) AS count
+            FROM root_paths
+            GROUP BY symbol_stack
+            ORDER BY count DESC, symbol_stack
+            LIMIT ?
+            "#,
+        )?;
+        let summaries = stmt
+            .query_map([limit], |row| {
+                let key = row.get::<_, String>(0)?;
+                let root_path_count = row.get(1)?;
+                Ok(SymbolStackSummary::from_storage_key(&key, root_path_count))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(summaries)
+    }
+
     /// Ensure the graph for the given file is loaded.
     pub fn load_graph_for_file(&mut self, file: &str) -> Result<Handle<File>> {
         Self::load_graph_for_file_inner(
@@ -850,6 +1002,14 @@ fn init_indexes(conn: &mut Connection) -> Result<()> {
     Ok(())
 }
 
+fn metadata_value(conn: &Connection, key: &str) -> Result<Option<String>> {
+    let mut stmt = conn.prepare_cached("SELECT value FROM metadata_values WHERE key = ?")?;
+    let value = stmt
+        .query_row([key], |r| r.get::<_, String>(0))
+        .optional()?;
+    Ok(value)
+}
+
 fn status_for_file<T: AsRef<str>>(
     conn: &Connection,
     file: &str,
@@ -0,0 +1,200 @@
+// -*- coding: utf-8 -*-
+// ------------------------------------------------------------------------------------------------
+// Copyright © 2023, stack-graphs authors.
+// Licensed under either of Apache License, Version 2.0, or MIT license, at your option.
+// Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
+// ------------------------------------------------------------------------------------------------
+
+use libc::c_char;
+use pretty_assertions::assert_eq;
+use stack_graphs::c::json::sg_partial_path_database_load_json;
+use stack_graphs::c::json::sg_partial_path_database_to_json;
+use stack_graphs::c::json::sg_stack_graph_load_json;
+use stack_graphs::c::json::sg_stack_graph_to_json;
+use stack_graphs::c::sg_partial_path_arena_find_partial_paths_in_file;
+use stack_graphs::c::sg_partial_path_arena_free;
+use stack_graphs::c::sg_partial_path_arena_new;
+use stack_graphs::c::sg_partial_path_database_add_partial_paths;
+use stack_graphs::c::sg_partial_path_database_free;
+use stack_graphs::c::sg_partial_path_database_new;
+use stack_graphs::c::sg_partial_path_database_partial_paths;
+use stack_graphs::c::sg_partial_path_handle;
+use stack_graphs::c::sg_partial_path_list_count;
+use stack_graphs::c::sg_partial_path_list_free;
+use stack_graphs::c::sg_partial_path_list_new;
+use stack_graphs::c::sg_partial_path_list_paths;
+use stack_graphs::c::sg_result;
+use stack_graphs::c::sg_stack_graph;
+use stack_graphs::c::sg_stack_graph_free;
+use stack_graphs::c::sg_stack_graph_new;
+use stack_graphs::c::sg_stitcher_config;
+use stack_graphs::c::SG_NULL_HANDLE;
+
+use crate::c::test_graph::TestGraph;
+use crate::test_graphs;
+
+/// Serializes using the given function, first querying the length and then filling a buffer of
+/// exactly that length.
+fn to_json<F>(f: F) -> Vec<u8>
+where
+    F: Fn(*mut c_char, usize, *mut usize) -> sg_result,
+{
+    let mut length = 0;
+    assert_eq!(
+        sg_result::SG_RESULT_SUCCESS,
+        f(std::ptr::null_mut(), 0, &mut length)
+    );
+    let mut buffer = vec![0u8; length];
+    let mut written = 0;
+    assert_eq!(
+        sg_result::SG_RESULT_SUCCESS,
+        f(
+            buffer.as_mut_ptr() as *mut c_char,
+            buffer.len(),
+            &mut written
+        )
+    );
+    assert_eq!(length, written);
+    buffer
+}
+
+fn graph_to_json(graph: *const sg_stack_graph, file: u32) -> Vec<u8> {
+    to_json(|buffer, length, length_out| {
+        sg_stack_graph_to_json(graph, file, buffer, length, length_out)
+    })
+}
+
+fn file_names(graph: *const sg_stack_graph) -> Vec<String> {
+    let graph = unsafe { &(*graph).inner };
+    graph
+        .iter_files()
+        .map(|f| graph[f].name().to_string())
+        .collect()
+}
+
+#[test]
+fn can_round_trip_graph() {
+    let graph: TestGraph = test_graphs::class_field_through_function_parameter::new();
+    let json = graph_to_json(graph.graph, SG_NULL_HANDLE);
+
+    let loaded = sg_stack_graph_new();
+    let result = sg_stack_graph_load_json(loaded, json.as_ptr() as *const c_char, json.len());
+    assert_eq!(sg_result::SG_RESULT_SUCCESS, result);
+    assert_eq!(file_names(graph.graph), file_names(loaded));
+    assert_eq!(json, graph_to_json(loaded, SG_NULL_HANDLE));
+    sg_stack_graph_free(loaded);
+}
+
+#[test]
+fn can_serialize_single_file() {
+    let graph: TestGraph = test_graphs::class_field_through_function_parameter::new();
+    let file = unsafe { &(*graph.graph).inner }
+        .get_file("a.py")
+        .expect("missing file");
+    let json = graph_to_json(graph.graph, file.as_u32());
+
+    let loaded = sg_stack_graph_new();
+    let result = sg_stack_graph_load_json(loaded, json.as_ptr() as *const c_char, json.len());
+    assert_eq!(sg_result::SG_RESULT_SUCCESS, result);
+    assert_eq!(vec!["a.py".to_string()], file_names(loaded));
+    sg_stack_graph_free(loaded);
+}
+
+#[test]
+fn does_not_write_into_small_buffer() {
+    let graph: TestGraph = test_graphs::class_field_through_function_parameter::new();
+    let mut buffer = vec![0u8; 8];
+    let mut length = 0;
+    let result = sg_stack_graph_to_json(
+        graph.graph,
+        SG_NULL_HANDLE,
+        buffer.as_mut_ptr() as *mut c_char,
+        buffer.len(),
+        &mut length,
+    );
+    assert_eq!(sg_result::SG_RESULT_SUCCESS, result);
+    assert!(length > buffer.len());
+    assert_eq!(vec![0u8; 8], buffer);
+}
+
+#[test]
+fn cannot_load_invalid_json() {
+    let graph = sg_stack_graph_new();
+    let json = "{ \"files\": ";
+    let result = sg_stack_graph_load_json(graph, json.as_ptr() as *const c_char, json.len());
+    assert_eq!(sg_result::SG_RESULT_SERIALIZATION_ERROR, result);
+    sg_stack_graph_free(graph);
+}
+
+#[test]
+fn can_round_trip_database() {
+    let graph: TestGraph = test_graphs::class_field_through_function_parameter::new();
+    let partials = sg_partial_path_arena_new();
+    let db = sg_partial_path_database_new();
+    let file = unsafe { &(*graph.graph).inner }
+        .get_file("main.py")
+        .expect("missing file");
+    let path_list = sg_partial_path_list_new();
+    let stitcher_config = sg_stitcher_config {
+        detect_similar_paths: false,
+    };
+    sg_partial_path_arena_find_partial_paths_in_file(
+        graph.graph,
+        partials,
+        file.as_u32(),
+        path_list,
+        &stitcher_config,
+        std::ptr::null(),
+    );
+    let count = sg_partial_path_list_count(path_list);
+    let mut handles = vec![sg_partial_path_handle::default(); count];
+    sg_partial_path_database_add_partial_paths(
+        graph.graph,
+        partials,
+        db,
+        count,
+        sg_partial_path_list_paths(path_list),
+        handles.as_mut_ptr(),
+    );
+    sg_partial_path_list_free(path_list);
+    let json = to_json(|buffer, length, length_out| {
+        sg_partial_path_database_to_json(
+            graph.graph,
+            partials,
+            db,
+            file.as_u32(),
+            buffer,
+            length,
+            length_out,
+        )
+    });
+
+    let loaded_graph = sg_stack_graph_new();
+    let graph_json = graph_to_json(graph.graph, SG_NULL_HANDLE);
+    let result = sg_stack_graph_load_json(
+        loaded_graph,
+        graph_json.as_ptr() as *const c_char,
+        graph_json.len(),
+    );
+    assert_eq!(sg_result::SG_RESULT_SUCCESS, result);
+    let loaded_partials = sg_partial_path_arena_new();
+    let loaded_db = sg_partial_path_database_new();
+    let result = sg_partial_path_database_load_json(
+        loaded_graph,
+        loaded_partials,
+        loaded_db,
+        json.as_ptr() as *const c_char,
+        json.len(),
+    );
+    assert_eq!(sg_result::SG_RESULT_SUCCESS, result);
+    assert_eq!(
+        count,
+        sg_partial_path_database_partial_paths(loaded_db).count
+    );
+
+    sg_partial_path_database_free(loaded_db);
+    sg_partial_path_arena_free(loaded_partials);
+    sg_stack_graph_free(loaded_graph);
+    sg_partial_path_database_free(db);
+    sg_partial_path_arena_free(partials);
+}
@@ -12,7 +12,11 @@ mod can_find_qualified_definitions_with_phased_partial_path_stitching;
 mod can_jump_to_definition;
 mod can_jump_to_definition_with_phased_partial_path_stitching;
 mod files;
+#[cfg(feature = "serde")]
+mod json;
 mod nodes;
 mod partial;
+#[cfg(feature = "storage")]
+mod storage;
 mod symbols;
 mod test_graph;
@@ -675,6 +675,7 @@ fn can_create_source_info() {
     let syntax_type = add_string(graph, "function");
     let containing_line = add_string(graph, "def foo():");
     let fully_qualified_name = add_string(graph, "bar.foo");
+    let documentation = add_string(graph, "/** Does foo. */");
 
     let mut infos = [sg_node_source_info {
         node: handles[1],
@@ -684,6 +685,7 @@ fn can_create_source_info() {
             containing_line,
             definiens_span: sg_span::default(),
             fully_qualified_name,
+            documentation,
         },
     }];
     infos[0].source_info.span.start.line = 17;
@@ -697,6 +699,7 @@ fn can_create_source_info() {
     let actual = get_source_info(graph, handles[1]).unwrap();
     assert_eq!(actual.syntax_type, syntax_type);
     assert_eq!(actual.containing_line, containing_line);
+    assert_eq!(actual.documentation, documentation);
     assert_eq!(actual.span.start.line, 17);
     assert_eq!(actual.span.end.column.utf8_offset, 23);
 
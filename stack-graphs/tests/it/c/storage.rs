@@ -0,0 +1,235 @@
+// -*- coding: utf-8 -*-
+// ------------------------------------------------------------------------------------------------
+// Copyright © 2023, stack-graphs authors.
+// Licensed under either of Apache License, Version 2.0, or MIT license, at your option.
+// Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
+// ------------------------------------------------------------------------------------------------
+
+use std::collections::BTreeSet;
+
+use libc::c_char;
+use pretty_assertions::assert_eq;
+use stack_graphs::c::sg_forward_partial_path_stitcher_free;
+use stack_graphs::c::sg_forward_partial_path_stitcher_from_nodes;
+use stack_graphs::c::sg_partial_path_arena_find_partial_paths_in_file;
+use stack_graphs::c::sg_partial_path_arena_free;
+use stack_graphs::c::sg_partial_path_arena_new;
+use stack_graphs::c::sg_partial_path_list_count;
+use stack_graphs::c::sg_partial_path_list_free;
+use stack_graphs::c::sg_partial_path_list_new;
+use stack_graphs::c::sg_partial_path_list_paths;
+use stack_graphs::c::sg_result;
+use stack_graphs::c::sg_stitcher_config;
+use stack_graphs::c::storage::sg_file_status;
+use stack_graphs::c::storage::sg_forward_partial_path_stitcher_process_next_phase_with_sqlite_reader;
+use stack_graphs::c::storage::sg_sqlite_reader;
+use stack_graphs::c::storage::sg_sqlite_reader_free;
+use stack_graphs::c::storage::sg_sqlite_reader_get;
+use stack_graphs::c::storage::sg_sqlite_reader_load_graph_for_file;
+use stack_graphs::c::storage::sg_sqlite_reader_status_for_file;
+use stack_graphs::c::storage::sg_sqlite_writer_into_reader;
+use stack_graphs::c::storage::sg_sqlite_writer_open_in_memory;
+use stack_graphs::c::storage::sg_sqlite_writer_store_error_for_file;
+use stack_graphs::c::storage::sg_sqlite_writer_store_result_for_file;
+use stack_graphs::c::SG_NULL_HANDLE;
+use stack_graphs::partial::PartialPath;
+
+use crate::c::test_graph::TestGraph;
+use crate::test_graphs;
+
+static TAG: &str = "tag";
+
+/// Stores all files of the test graph, with their partial paths, in an in-memory database, and
+/// returns a reader for it.
+fn create_database(graph: &TestGraph) -> *mut sg_sqlite_reader {
+    let rust_graph = unsafe { &(*graph.graph).inner };
+    let writer = sg_sqlite_writer_open_in_memory();
+    assert!(!writer.is_null());
+    for file in rust_graph.iter_files() {
+        let partials = sg_partial_path_arena_new();
+        let path_list = sg_partial_path_list_new();
+        let stitcher_config = sg_stitcher_config {
+            detect_similar_paths: false,
+        };
+        sg_partial_path_arena_find_partial_paths_in_file(
+            graph.graph,
+            partials,
+            file.as_u32(),
+            path_list,
+            &stitcher_config,
+            std::ptr::null(),
+        );
+        let result = sg_sqlite_writer_store_result_for_file(
+            writer,
+            graph.graph,
+            file.as_u32(),
+            TAG.as_ptr() as *const c_char,
+            TAG.len(),
+            partials,
+            sg_partial_path_list_count(path_list),
+            sg_partial_path_list_paths(path_list),
+        );
+        assert_eq!(sg_result::SG_RESULT_SUCCESS, result);
+        sg_partial_path_list_free(path_list);
+        sg_partial_path_arena_free(partials);
+    }
+    sg_sqlite_writer_into_reader(writer)
+}
+
+fn check_jump_to_definition(graph: &TestGraph, file: &str, expected_partial_paths: &[&str]) {
+    let reader = create_database(graph);
+
+    let mut file_handle = SG_NULL_HANDLE;
+    let result = sg_sqlite_reader_load_graph_for_file(
+        reader,
+        file.as_ptr() as *const c_char,
+        file.len(),
+        &mut file_handle,
+    );
+    assert_eq!(sg_result::SG_RESULT_SUCCESS, result);
+
+    let data = sg_sqlite_reader_get(reader);
+    let references = {
+        let rust_graph = unsafe { &(*data.graph).inner };
+        rust_graph
+            .nodes_for_file(file_handle.into())
+            .filter(|handle| rust_graph[*handle].is_reference())
+            .collect::<Vec<_>>()
+    };
+
+    let stitcher = sg_forward_partial_path_stitcher_from_nodes(
+        data.graph,
+        data.partials,
+        references.len(),
+        references.as_ptr() as *const _,
+    );
+    let rust_stitcher = unsafe { &mut *stitcher };
+
+    // The reader loads the graphs and partial paths that are needed for each phase.
+    let mut results = BTreeSet::new();
+    loop {
+        let data = sg_sqlite_reader_get(reader);
+        let rust_graph = unsafe { &(*data.graph).inner };
+        let rust_partials = unsafe { &mut (*data.partials).inner };
+        let partial_paths_slice = unsafe {
+            std::slice::from_raw_parts(
+                rust_stitcher.previous_phase_partial_paths as *const PartialPath,
+                rust_stitcher.previous_phase_partial_paths_length,
+            )
+        };
+        for partial_path in partial_paths_slice {
+            if partial_path.is_complete(rust_graph) {
+                results.insert(partial_path.display(rust_graph, rust_partials).to_string());
+            }
+        }
+        if rust_stitcher.is_complete {
+            break;
+        }
+        let result = sg_forward_partial_path_stitcher_process_next_phase_with_sqlite_reader(
+            reader,
+            stitcher,
+            std::ptr::null(),
+        );
+        assert_eq!(sg_result::SG_RESULT_SUCCESS, result);
+    }
+
+    let expected_partial_paths = expected_partial_paths
+        .iter()
+        .map(|s| s.to_string())
+        .collect::<BTreeSet<_>>();
+    assert_eq!(expected_partial_paths, results);
+
+    sg_forward_partial_path_stitcher_free(stitcher);
+    sg_sqlite_reader_free(reader);
+}
+
+#[test]
+fn class_field_through_function_parameter() {
+    let graph = test_graphs::class_field_through_function_parameter::new();
+    check_jump_to_definition(
+        &graph,
+        "main.py",
+        &[
+            "<> () [main.py(17) reference a] -> [a.py(0) definition a] <> ()",
+            "<> () [main.py(15) reference b] -> [b.py(0) definition b] <> ()",
+            "<> () [main.py(13) reference foo] -> [a.py(5) definition foo] <> ()",
+            "<> () [main.py(9) reference A] -> [b.py(5) definition A] <> ()",
+            "<> () [main.py(10) reference bar] -> [b.py(8) definition bar] <> ()",
+        ],
+    );
+}
+
+#[test]
+fn cyclic_imports_python() {
+    let graph = test_graphs::cyclic_imports_python::new();
+    check_jump_to_definition(
+        &graph,
+        "main.py",
+        &[
+            "<> () [main.py(8) reference a] -> [a.py(0) definition a] <> ()",
+            "<> () [main.py(6) reference foo] -> [b.py(6) definition foo] <> ()",
+        ],
+    );
+}
+
+#[test]
+fn can_get_file_status() {
+    let graph = test_graphs::class_field_through_function_parameter::new();
+    let reader = create_database(&graph);
+    let check_status = |file: &str, tag: Option<&str>, expected: sg_file_status| {
+        let mut status = sg_file_status::SG_FILE_STATUS_MISSING;
+        let (tag_ptr, tag_len) = match tag {
+            Some(tag) => (tag.as_ptr() as *const c_char, tag.len()),
+            None => (std::ptr::null(), 0),
+        };
+        let result = sg_sqlite_reader_status_for_file(
+            reader,
+            file.as_ptr() as *const c_char,
+            file.len(),
+            tag_ptr,
+            tag_len,
+            &mut status,
+        );
+        assert_eq!(sg_result::SG_RESULT_SUCCESS, result);
+        assert_eq!(expected, status);
+    };
+    check_status("main.py", None, sg_file_status::SG_FILE_STATUS_INDEXED);
+    check_status("main.py", Some(TAG), sg_file_status::SG_FILE_STATUS_INDEXED);
+    check_status(
+        "main.py",
+        Some("other"),
+        sg_file_status::SG_FILE_STATUS_MISSING,
+    );
+    check_status("missing.py", None, sg_file_status::SG_FILE_STATUS_MISSING);
+    sg_sqlite_reader_free(reader);
+}
+
+#[test]
+fn can_store_file_error() {
+    let writer = sg_sqlite_writer_open_in_memory();
+    let file = "test.py";
+    let error = "parse error";
+    let result = sg_sqlite_writer_store_error_for_file(
+        writer,
+        file.as_ptr() as *const c_char,
+        file.len(),
+        TAG.as_ptr() as *const c_char,
+        TAG.len(),
+        error.as_ptr() as *const c_char,
+        error.len(),
+    );
+    assert_eq!(sg_result::SG_RESULT_SUCCESS, result);
+    let reader = sg_sqlite_writer_into_reader(writer);
+    let mut status = sg_file_status::SG_FILE_STATUS_MISSING;
+    let result = sg_sqlite_reader_status_for_file(
+        reader,
+        file.as_ptr() as *const c_char,
+        file.len(),
+        std::ptr::null(),
+        0,
+        &mut status,
+    );
+    assert_eq!(sg_result::SG_RESULT_SUCCESS, result);
+    assert_eq!(sg_file_status::SG_FILE_STATUS_ERROR, status);
+    sg_sqlite_reader_free(reader);
+}
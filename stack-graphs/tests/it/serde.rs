@@ -53,6 +53,7 @@ fn serde_json_stack_graph() {
                         },
                     },
                     syntax_type: None,
+                    documentation: None,
                 }),
                 debug_info: Some(serde::DebugInfo { data: vec![] }),
             }],
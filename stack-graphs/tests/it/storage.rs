@@ -8,8 +8,10 @@
 use itertools::Itertools;
 use stack_graphs::graph::StackGraph;
 use stack_graphs::partial::PartialPaths;
+use stack_graphs::storage::FileStatus;
 use stack_graphs::storage::SQLiteWriter;
 use stack_graphs::NoCancellation;
+use std::path::Path;
 
 use crate::util::create_partial_path_and_edges;
 use crate::util::create_pop_symbol_node;
@@ -125,3 +127,110 @@ fn find_candidates_for_shorter_symbol_stack_without_variable() {
     let results = test_foo_bar_root_candidate_paths(&["foo"], false);
     assert_eq!(0, results);
 }
+
+#[test]
+fn can_store_and_remove_metadata_values() {
+    let mut writer = SQLiteWriter::open_in_memory().unwrap();
+    assert_eq!(None, writer.metadata_value("revision").unwrap());
+
+    writer.set_metadata_value("revision", "abc").unwrap();
+    writer.set_metadata_value("revision", "def").unwrap();
+    assert_eq!(
+        Some("def".to_string()),
+        writer.metadata_value("revision").unwrap()
+    );
+
+    writer.remove_metadata_value("revision").unwrap();
+    assert_eq!(None, writer.metadata_value("revision").unwrap());
+
+    writer.set_metadata_value("revision", "abc").unwrap();
+    let mut reader = writer.into_reader();
+    assert_eq!(
+        Some("abc".to_string()),
+        reader.metadata_value("revision").unwrap()
+    );
+}
+
+#[test]
+fn can_store_and_load_documentation() {
+    let mut writer = SQLiteWriter::open_in_memory().unwrap();
+
+    let mut graph = StackGraph::new();
+    let file = graph.add_file("test1").unwrap();
+    let mut partials = PartialPaths::new();
+
+    let foo = create_pop_symbol_node(&mut graph, file, "foo", true);
+    let documentation = graph.add_string("/** Does foo. */");
+    graph.source_info_mut(foo).documentation = documentation.into();
+
+    writer
+        .store_result_for_file(&graph, file, "", &mut partials, Vec::new())
+        .unwrap();
+    let mut reader = writer.into_reader();
+
+    let file = reader.load_graph_for_file("test1").unwrap();
+    let (graph, _, _) = reader.get();
+    let documentation = graph
+        .nodes_for_file(file)
+        .filter_map(|node| graph.source_info(node))
+        .filter_map(|source_info| source_info.documentation.into_option())
+        .map(|documentation| graph[documentation].to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(vec!["/** Does foo. */".to_string()], documentation);
+}
+
+#[test]
+fn can_summarize_files_and_symbol_stacks() {
+    let mut writer = SQLiteWriter::open_in_memory().unwrap();
+
+    let mut graph = StackGraph::new();
+    let file = graph.add_file("test1").unwrap();
+    let mut partials = PartialPaths::new();
+
+    let r = StackGraph::root_node();
+    let foo = create_pop_symbol_node(&mut graph, file, "foo", true);
+    let bar = create_pop_symbol_node(&mut graph, file, "bar", true);
+
+    let path_with_variable =
+        create_partial_path_and_edges(&mut graph, &mut partials, &[r, foo, bar]).unwrap();
+    let mut path_without_variable = path_with_variable.clone();
+    path_without_variable.eliminate_precondition_stack_variables(&mut partials);
+
+    writer
+        .store_result_for_file(
+            &graph,
+            file,
+            "",
+            &mut partials,
+            vec![&path_with_variable, &path_without_variable],
+        )
+        .unwrap();
+    writer
+        .store_error_for_file(Path::new("test2"), "", "failed")
+        .unwrap();
+    let mut reader = writer.into_reader();
+
+    let summaries = reader.file_summaries().unwrap();
+    assert_eq!(2, summaries.len());
+    assert_eq!(Path::new("test1"), summaries[0].path);
+    assert!(matches!(summaries[0].status, FileStatus::Indexed));
+    assert!(summaries[0].graph_node_count >= 2);
+    assert_eq!(2, summaries[0].graph_edge_count);
+    assert_eq!(0, summaries[0].node_path_count);
+    assert_eq!(2, summaries[0].root_path_count);
+    assert!(summaries[0].root_paths_size > 0);
+    assert_eq!(Path::new("test2"), summaries[1].path);
+    assert!(matches!(summaries[1].status, FileStatus::Error(_)));
+    assert_eq!(0, summaries[1].graph_node_count);
+    assert_eq!(0, summaries[1].root_path_count);
+
+    let symbol_stacks = reader.symbol_stacks_by_root_path_count(10).unwrap();
+    assert_eq!(
+        vec!["foo,bar,%", "foo,bar"],
+        symbol_stacks
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>()
+    );
+    assert!(symbol_stacks.iter().all(|s| s.root_path_count == 1));
+}
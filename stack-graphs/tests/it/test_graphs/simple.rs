@@ -79,6 +79,7 @@ pub fn new() -> StackGraph {
         containing_line: str_line0.into(),
         definiens_span: Span::default(),
         fully_qualified_name: ControlledOption::default(),
+        documentation: ControlledOption::default(),
     };
     *graph.source_info_mut(ref_x) = SourceInfo {
         span: Span {
@@ -107,6 +108,7 @@ pub fn new() -> StackGraph {
         containing_line: str_line1.into(),
         definiens_span: Span::default(),
         fully_qualified_name: ControlledOption::default(),
+        documentation: ControlledOption::default(),
     };
 
     let str_dsl_var = graph.add_string("dsl_var");
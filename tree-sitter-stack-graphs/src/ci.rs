@@ -26,6 +26,8 @@
 //! ```
 //!
 //! By default tests time out after 60 seconds. Set `Tester::max_test_time` to change the timeout.
+//! Test files are run in parallel, using all available cores. Set `Tester::jobs` to change the
+//! number of test files that are run at the same time.
 
 use std::path::PathBuf;
 use std::time::Duration;
@@ -39,6 +41,7 @@ pub struct Tester {
     configurations: Vec<LanguageConfiguration>,
     test_paths: Vec<PathBuf>,
     pub max_test_time: Option<Duration>,
+    pub jobs: Option<usize>,
 }
 
 impl Tester {
@@ -47,6 +50,7 @@ impl Tester {
             configurations,
             test_paths,
             max_test_time: Some(Duration::from_secs(60)),
+            jobs: None,
         }
     }
 
@@ -70,6 +74,7 @@ impl Tester {
             .expect("Expected loader");
         let mut args = TestArgs::new(test_paths);
         args.max_test_time = self.max_test_time;
+        args.jobs = self.jobs;
         args.run(loader)
     }
 }
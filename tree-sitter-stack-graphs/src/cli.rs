@@ -32,6 +32,12 @@
 //! }
 //! ```
 //!
+//! ## Project configuration
+//!
+//! Both kinds of CLIs read defaults for their arguments from a `stack-graphs.toml` file in the
+//! current directory or one of its ancestors, if it exists. See the [`config`][] module for
+//! details.
+//!
 //! ## Provided languages CLIs
 //!
 //! Provided languages CLIs use directly provided language configuration instances.
@@ -57,16 +63,22 @@
 //! }
 //! ```
 
+pub mod bench;
 pub mod clean;
+pub mod config;
 pub mod database;
+pub mod export;
 pub mod index;
 pub mod init;
+pub mod inspect;
 pub mod load;
 #[cfg(feature = "lsp")]
 pub mod lsp;
 pub mod r#match;
 pub mod parse;
 pub mod query;
+#[cfg(feature = "server")]
+pub mod serve;
 pub mod status;
 pub mod test;
 pub mod util;
@@ -77,51 +89,110 @@ pub mod path_loading {
 
     use clap::Subcommand;
 
+    use crate::cli::bench::BenchArgs;
     use crate::cli::clean::CleanArgs;
+    use crate::cli::config::ProjectConfig;
     use crate::cli::database::DatabaseArgs;
+    use crate::cli::export::ExportArgs;
     use crate::cli::index::IndexArgs;
     use crate::cli::init::InitArgs;
+    use crate::cli::inspect::InspectArgs;
     use crate::cli::load::PathLoaderArgs;
     #[cfg(feature = "lsp")]
     use crate::cli::lsp::LspArgs;
     use crate::cli::parse::ParseArgs;
     use crate::cli::query::QueryArgs;
     use crate::cli::r#match::MatchArgs;
+    #[cfg(feature = "server")]
+    use crate::cli::serve::ServeArgs;
     use crate::cli::status::StatusArgs;
     use crate::cli::test::TestArgs;
     use crate::cli::visualize::VisualizeArgs;
 
     #[derive(Subcommand)]
     pub enum Subcommands {
+        Bench(Bench),
         Clean(Clean),
+        Export(Export),
         Index(Index),
         Init(Init),
+        #[clap(alias = "db-stats")]
+        Inspect(Inspect),
         #[cfg(feature = "lsp")]
         Lsp(Lsp),
         Match(Match),
         Parse(Parse),
         Query(Query),
+        #[cfg(feature = "server")]
+        Serve(Serve),
         Status(Status),
         Test(Test),
         Visualize(Visualize),
     }
 
     impl Subcommands {
-        pub fn run(self, default_db_path: PathBuf) -> anyhow::Result<()> {
+        pub fn run(mut self, default_db_path: PathBuf) -> anyhow::Result<()> {
+            let config = ProjectConfig::discover()?;
+            let default_db_path = config.database.clone().unwrap_or(default_db_path);
+            self.apply_config(&config);
             match self {
+                Self::Bench(cmd) => cmd.run(),
                 Self::Clean(cmd) => cmd.run(default_db_path),
+                Self::Export(cmd) => cmd.run(default_db_path),
                 Self::Index(cmd) => cmd.run(default_db_path),
                 Self::Init(cmd) => cmd.run(),
+                Self::Inspect(cmd) => cmd.run(default_db_path),
                 #[cfg(feature = "lsp")]
                 Self::Lsp(cmd) => cmd.run(default_db_path),
                 Self::Match(cmd) => cmd.run(),
                 Self::Parse(cmd) => cmd.run(),
                 Self::Query(cmd) => cmd.run(default_db_path),
+                #[cfg(feature = "server")]
+                Self::Serve(cmd) => cmd.run(default_db_path),
                 Self::Status(cmd) => cmd.run(default_db_path),
                 Self::Test(cmd) => cmd.run(),
                 Self::Visualize(cmd) => cmd.run(default_db_path),
             }
         }
+
+        /// Use the values from the project configuration for arguments that were not given.
+        fn apply_config(&mut self, config: &ProjectConfig) {
+            match self {
+                Self::Bench(cmd) => {
+                    cmd.load_args.apply_config(&config.loader);
+                    cmd.bench_args.apply_config(config);
+                }
+                Self::Index(cmd) => {
+                    cmd.load_args.apply_config(&config.loader);
+                    cmd.index_args.apply_config(config);
+                }
+                #[cfg(feature = "lsp")]
+                Self::Lsp(cmd) => {
+                    cmd.load_args.apply_config(&config.loader);
+                    cmd.lsp_args.apply_config(config);
+                }
+                Self::Match(cmd) => cmd.load_args.apply_config(&config.loader),
+                Self::Parse(cmd) => cmd.load_args.apply_config(&config.loader),
+                Self::Test(cmd) => cmd.load_args.apply_config(&config.loader),
+                _ => {}
+            }
+        }
+    }
+
+    /// Benchmark indexing and querying a corpus.
+    #[derive(clap::Parser)]
+    pub struct Bench {
+        #[clap(flatten)]
+        load_args: PathLoaderArgs,
+        #[clap(flatten)]
+        bench_args: BenchArgs,
+    }
+
+    impl Bench {
+        pub fn run(self) -> anyhow::Result<()> {
+            let loader = self.load_args.get()?;
+            self.bench_args.run(loader)
+        }
     }
 
     /// Clean the indexing database.
@@ -140,6 +211,22 @@ pub mod path_loading {
         }
     }
 
+    /// Export the database to other code intelligence formats.
+    #[derive(clap::Parser)]
+    pub struct Export {
+        #[clap(flatten)]
+        db_args: DatabaseArgs,
+        #[clap(flatten)]
+        export_args: ExportArgs,
+    }
+
+    impl Export {
+        pub fn run(self, default_db_path: PathBuf) -> anyhow::Result<()> {
+            let db_path = self.db_args.get_or(default_db_path);
+            self.export_args.run(&db_path)
+        }
+    }
+
     /// Index source files into the database.
     #[derive(clap::Parser)]
     pub struct Index {
@@ -172,6 +259,22 @@ pub mod path_loading {
         }
     }
 
+    /// Inspect the database, showing the largest files, errors, and symbols with most root paths.
+    #[derive(clap::Parser)]
+    pub struct Inspect {
+        #[clap(flatten)]
+        db_args: DatabaseArgs,
+        #[clap(flatten)]
+        inspect_args: InspectArgs,
+    }
+
+    impl Inspect {
+        pub fn run(self, default_db_path: PathBuf) -> anyhow::Result<()> {
+            let db_path = self.db_args.get_or(default_db_path);
+            self.inspect_args.run(&db_path)
+        }
+    }
+
     /// Run an LSP server.
     #[cfg(feature = "lsp")]
     #[derive(clap::Parser)]
@@ -241,6 +344,24 @@ pub mod path_loading {
         }
     }
 
+    /// Run a local query server.
+    #[cfg(feature = "server")]
+    #[derive(clap::Parser)]
+    pub struct Serve {
+        #[clap(flatten)]
+        db_args: DatabaseArgs,
+        #[clap(flatten)]
+        serve_args: ServeArgs,
+    }
+
+    #[cfg(feature = "server")]
+    impl Serve {
+        pub fn run(self, default_db_path: PathBuf) -> anyhow::Result<()> {
+            let db_path = self.db_args.get_or(default_db_path);
+            self.serve_args.run(&db_path)
+        }
+    }
+
     /// Show indexing status for source files.
     #[derive(clap::Parser)]
     pub struct Status {
@@ -295,16 +416,22 @@ pub mod provided_languages {
 
     use clap::Subcommand;
 
+    use crate::cli::bench::BenchArgs;
     use crate::cli::clean::CleanArgs;
+    use crate::cli::config::ProjectConfig;
     use crate::cli::database::DatabaseArgs;
+    use crate::cli::export::ExportArgs;
     use crate::cli::index::IndexArgs;
     use crate::cli::init::InitArgs;
+    use crate::cli::inspect::InspectArgs;
     use crate::cli::load::LanguageConfigurationsLoaderArgs;
     #[cfg(feature = "lsp")]
     use crate::cli::lsp::LspArgs;
     use crate::cli::parse::ParseArgs;
     use crate::cli::query::QueryArgs;
     use crate::cli::r#match::MatchArgs;
+    #[cfg(feature = "server")]
+    use crate::cli::serve::ServeArgs;
     use crate::cli::status::StatusArgs;
     use crate::cli::test::TestArgs;
     use crate::cli::visualize::VisualizeArgs;
@@ -312,14 +439,20 @@ pub mod provided_languages {
 
     #[derive(Subcommand)]
     pub enum Subcommands {
+        Bench(Bench),
         Clean(Clean),
+        Export(Export),
         Index(Index),
         Init(Init),
+        #[clap(alias = "db-stats")]
+        Inspect(Inspect),
         #[cfg(feature = "lsp")]
         Lsp(Lsp),
         Match(Match),
         Parse(Parse),
         Query(Query),
+        #[cfg(feature = "server")]
+        Serve(Serve),
         Status(Status),
         Test(Test),
         Visualize(Visualize),
@@ -327,24 +460,71 @@ pub mod provided_languages {
 
     impl Subcommands {
         pub fn run(
-            self,
+            mut self,
             default_db_path: PathBuf,
             configurations: Vec<LanguageConfiguration>,
         ) -> anyhow::Result<()> {
+            let config = ProjectConfig::discover()?;
+            let default_db_path = config.database.clone().unwrap_or(default_db_path);
+            self.apply_config(&config);
             match self {
+                Self::Bench(cmd) => cmd.run(configurations),
                 Self::Clean(cmd) => cmd.run(default_db_path),
+                Self::Export(cmd) => cmd.run(default_db_path),
                 Self::Index(cmd) => cmd.run(default_db_path, configurations),
                 Self::Init(cmd) => cmd.run(),
+                Self::Inspect(cmd) => cmd.run(default_db_path),
                 #[cfg(feature = "lsp")]
                 Self::Lsp(cmd) => cmd.run(default_db_path, configurations),
                 Self::Match(cmd) => cmd.run(configurations),
                 Self::Parse(cmd) => cmd.run(configurations),
                 Self::Query(cmd) => cmd.run(default_db_path),
+                #[cfg(feature = "server")]
+                Self::Serve(cmd) => cmd.run(default_db_path),
                 Self::Status(cmd) => cmd.run(default_db_path),
                 Self::Test(cmd) => cmd.run(configurations),
                 Self::Visualize(cmd) => cmd.run(default_db_path),
             }
         }
+
+        /// Use the values from the project configuration for arguments that were not given.
+        fn apply_config(&mut self, config: &ProjectConfig) {
+            match self {
+                Self::Bench(cmd) => {
+                    cmd.load_args.apply_config(&config.loader);
+                    cmd.bench_args.apply_config(config);
+                }
+                Self::Index(cmd) => {
+                    cmd.load_args.apply_config(&config.loader);
+                    cmd.index_args.apply_config(config);
+                }
+                #[cfg(feature = "lsp")]
+                Self::Lsp(cmd) => {
+                    cmd.load_args.apply_config(&config.loader);
+                    cmd.lsp_args.apply_config(config);
+                }
+                Self::Match(cmd) => cmd.load_args.apply_config(&config.loader),
+                Self::Parse(cmd) => cmd.load_args.apply_config(&config.loader),
+                Self::Test(cmd) => cmd.load_args.apply_config(&config.loader),
+                _ => {}
+            }
+        }
+    }
+
+    /// Benchmark indexing and querying a corpus.
+    #[derive(clap::Parser)]
+    pub struct Bench {
+        #[clap(flatten)]
+        load_args: LanguageConfigurationsLoaderArgs,
+        #[clap(flatten)]
+        bench_args: BenchArgs,
+    }
+
+    impl Bench {
+        pub fn run(self, configurations: Vec<LanguageConfiguration>) -> anyhow::Result<()> {
+            let loader = self.load_args.get(configurations)?;
+            self.bench_args.run(loader)
+        }
     }
 
     /// Clean the indexing database.
@@ -363,6 +543,22 @@ pub mod provided_languages {
         }
     }
 
+    /// Export the database to other code intelligence formats.
+    #[derive(clap::Parser)]
+    pub struct Export {
+        #[clap(flatten)]
+        db_args: DatabaseArgs,
+        #[clap(flatten)]
+        export_args: ExportArgs,
+    }
+
+    impl Export {
+        pub fn run(self, default_db_path: PathBuf) -> anyhow::Result<()> {
+            let db_path = self.db_args.get_or(default_db_path);
+            self.export_args.run(&db_path)
+        }
+    }
+
     /// Index source files into the database.
     #[derive(clap::Parser)]
     pub struct Index {
@@ -399,6 +595,22 @@ pub mod provided_languages {
         }
     }
 
+    /// Inspect the database, showing the largest files, errors, and symbols with most root paths.
+    #[derive(clap::Parser)]
+    pub struct Inspect {
+        #[clap(flatten)]
+        db_args: DatabaseArgs,
+        #[clap(flatten)]
+        inspect_args: InspectArgs,
+    }
+
+    impl Inspect {
+        pub fn run(self, default_db_path: PathBuf) -> anyhow::Result<()> {
+            let db_path = self.db_args.get_or(default_db_path);
+            self.inspect_args.run(&db_path)
+        }
+    }
+
     /// Run an LSP server.
     #[cfg(feature = "lsp")]
     #[derive(clap::Parser)]
@@ -472,6 +684,24 @@ pub mod provided_languages {
         }
     }
 
+    /// Run a local query server.
+    #[cfg(feature = "server")]
+    #[derive(clap::Parser)]
+    pub struct Serve {
+        #[clap(flatten)]
+        db_args: DatabaseArgs,
+        #[clap(flatten)]
+        serve_args: ServeArgs,
+    }
+
+    #[cfg(feature = "server")]
+    impl Serve {
+        pub fn run(self, default_db_path: PathBuf) -> anyhow::Result<()> {
+            let db_path = self.db_args.get_or(default_db_path);
+            self.serve_args.run(&db_path)
+        }
+    }
+
     /// Show indexing status for source files.
     #[derive(clap::Parser)]
     pub struct Status {
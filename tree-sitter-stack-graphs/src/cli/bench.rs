@@ -0,0 +1,334 @@
+// -*- coding: utf-8 -*-
+// ------------------------------------------------------------------------------------------------
+// Copyright © 2023, stack-graphs authors.
+// Licensed under either of Apache License, Version 2.0, or MIT license, at your option.
+// Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
+// ------------------------------------------------------------------------------------------------
+
+use anyhow::Context;
+use clap::Args;
+use clap::ValueHint;
+use serde::Deserialize;
+use serde::Serialize;
+use stack_graphs::stats::FrequencyDistribution;
+use stack_graphs::stitching::Stats as StitchingStats;
+use stack_graphs::storage::FileStatus;
+use stack_graphs::storage::SQLiteReader;
+use stack_graphs::storage::SQLiteWriter;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Instant;
+
+use crate::cli::config::ProjectConfig;
+use crate::cli::index::Indexer;
+use crate::cli::query::Querier;
+use crate::cli::util::print_database_stats;
+use crate::cli::util::print_stitching_stats;
+use crate::cli::util::reporter::ConsoleReporter;
+use crate::cli::util::reporter::Level;
+use crate::cli::util::ExistingPathBufValueParser;
+use crate::cli::util::FileFilterArgs;
+use crate::cli::util::SourcePosition;
+use crate::loader::Loader;
+use crate::NoCancellation;
+
+/// Benchmark indexing and querying
+#[derive(Args)]
+pub struct BenchArgs {
+    /// Source file or directory paths to index.
+    #[clap(
+        value_name = "SOURCE_PATH",
+        required = true,
+        value_hint = ValueHint::AnyPath,
+        value_parser = ExistingPathBufValueParser,
+    )]
+    pub source_paths: Vec<PathBuf>,
+
+    #[clap(flatten)]
+    pub file_filter: FileFilterArgs,
+
+    /// Query a sample of this many references, evenly spread over all references, instead of
+    /// querying all references.
+    #[clap(long, value_name = "COUNT")]
+    pub sample: Option<usize>,
+
+    /// Clear all loaded data before every query, so that queries do not benefit from data loaded
+    /// by earlier queries.
+    #[clap(long)]
+    pub cold: bool,
+
+    /// Compare the results against a baseline, saved earlier with --save-baseline.
+    #[clap(
+        long,
+        value_name = "BASELINE_PATH",
+        value_hint = ValueHint::FilePath,
+    )]
+    pub baseline: Option<PathBuf>,
+
+    /// Save the results as a baseline, for later comparison.
+    #[clap(
+        long,
+        value_name = "BASELINE_PATH",
+        value_hint = ValueHint::FilePath,
+    )]
+    pub save_baseline: Option<PathBuf>,
+
+    /// Global variables per language name, set from the project configuration.
+    #[clap(skip)]
+    pub language_globals: HashMap<String, HashMap<String, String>>,
+}
+
+impl BenchArgs {
+    pub fn new(source_paths: Vec<PathBuf>) -> Self {
+        Self {
+            source_paths,
+            file_filter: FileFilterArgs::new(),
+            sample: None,
+            cold: false,
+            baseline: None,
+            save_baseline: None,
+            language_globals: HashMap::new(),
+        }
+    }
+
+    /// Use the values from the configuration for arguments that were not given.
+    pub fn apply_config(&mut self, config: &ProjectConfig) {
+        self.file_filter.apply_config(&config.files);
+        self.language_globals = config.language_globals();
+    }
+
+    pub fn run(self, mut loader: Loader) -> anyhow::Result<()> {
+        // read the baseline first, so that we do not run the benchmark if it cannot be read
+        let baseline = self
+            .baseline
+            .as_ref()
+            .map(|path| BenchResults::load(path))
+            .transpose()?;
+
+        let source_paths = self
+            .source_paths
+            .iter()
+            .map(|p| p.canonicalize())
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        // the database is kept in memory, so that the benchmark is not affected by earlier runs
+        let mut db = SQLiteWriter::open_in_memory()?;
+        let reporter = ConsoleReporter {
+            skipped_level: Level::None,
+            succeeded_level: Level::None,
+            failed_level: Level::Summary,
+            canceled_level: Level::Summary,
+        };
+        let indexing_start = Instant::now();
+        {
+            let mut indexer = Indexer::new(&mut db, &mut loader, &reporter);
+            indexer.file_filter = self.file_filter.get()?;
+            indexer.language_globals = self.language_globals.clone();
+            indexer.index_all(&source_paths, None::<&Path>, &NoCancellation)?;
+        }
+        let indexing_time = indexing_start.elapsed();
+
+        let mut db = db.into_reader();
+        let (indexed_files, failed_files, references) = collect_references(&mut db)?;
+        let references = sample(references, self.sample);
+        db.clear();
+
+        let mut latencies = FrequencyDistribution::default();
+        let mut stitching_stats = StitchingStats::default();
+        let query_reporter = ConsoleReporter::none();
+        for reference in &references {
+            if self.cold {
+                db.clear();
+            }
+            let mut querier = Querier::new(&mut db, &query_reporter);
+            querier.set_collect_stats(true);
+            let query_start = Instant::now();
+            querier.definitions(reference.clone(), &NoCancellation)?;
+            latencies.record(query_start.elapsed().as_micros() as u64);
+            stitching_stats += querier.into_stats();
+        }
+
+        let results = BenchResults {
+            indexed_files,
+            failed_files,
+            indexing_time_ms: indexing_time.as_millis() as u64,
+            queries: references.len(),
+            query_latency_us: Percentiles::from(&latencies),
+        };
+        results.print();
+        if let Some(baseline) = &baseline {
+            println!();
+            results.print_comparison(baseline);
+        }
+        println!();
+        print_stitching_stats(stitching_stats);
+        println!();
+        print_database_stats(db.stats());
+
+        if let Some(path) = &self.save_baseline {
+            results.save(path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the number of indexed and failed files, and the positions of all references in the
+/// indexed files.
+fn collect_references(
+    db: &mut SQLiteReader,
+) -> anyhow::Result<(usize, usize, Vec<SourcePosition>)> {
+    let mut indexed_paths = Vec::new();
+    let mut failed_files = 0usize;
+    {
+        let mut files = db.list_all()?;
+        for entry in files.try_iter()? {
+            let entry = entry?;
+            match entry.status {
+                FileStatus::Indexed => indexed_paths.push(entry.path),
+                FileStatus::Error(_) => failed_files += 1,
+                FileStatus::Missing => {}
+            }
+        }
+    }
+
+    let mut references = Vec::new();
+    for path in &indexed_paths {
+        let file = db.load_graph_for_file(&path.to_string_lossy())?;
+        let (graph, _, _) = db.get();
+        for node in graph.nodes_for_file(file) {
+            if !graph[node].is_reference() {
+                continue;
+            }
+            if let Some(source_info) = graph.source_info(node) {
+                references.push(SourcePosition {
+                    path: path.clone(),
+                    line: source_info.span.start.line,
+                    column: source_info.span.start.column.grapheme_offset,
+                });
+            }
+        }
+    }
+    Ok((indexed_paths.len(), failed_files, references))
+}
+
+/// Returns a sample of the given size, evenly spread over the values. The sample is deterministic,
+/// so that benchmark runs over the same corpus query the same references.
+fn sample<T>(values: Vec<T>, size: Option<usize>) -> Vec<T> {
+    let size = match size {
+        Some(size) if size < values.len() => size,
+        _ => return values,
+    };
+    let step = values.len() as f64 / size as f64;
+    let mut next = 0f64;
+    values
+        .into_iter()
+        .enumerate()
+        .filter(|(idx, _)| {
+            if (*idx as f64) < next {
+                return false;
+            }
+            next += step;
+            true
+        })
+        .map(|(_, value)| value)
+        .take(size)
+        .collect()
+}
+
+/// The results of a benchmark run, which can be saved as a baseline.
+#[derive(Serialize, Deserialize)]
+struct BenchResults {
+    indexed_files: usize,
+    failed_files: usize,
+    indexing_time_ms: u64,
+    queries: usize,
+    query_latency_us: Percentiles,
+}
+
+impl BenchResults {
+    fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Cannot read baseline {}", path.display()))?;
+        let results = serde_json::from_str(&content)
+            .with_context(|| format!("Invalid baseline {}", path.display()))?;
+        Ok(results)
+    }
+
+    fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, content)
+            .with_context(|| format!("Cannot write baseline {}", path.display()))?;
+        Ok(())
+    }
+
+    fn rows(&self) -> Vec<(&'static str, u64)> {
+        vec![
+            ("indexed files", self.indexed_files as u64),
+            ("failed files", self.failed_files as u64),
+            ("indexing time (ms)", self.indexing_time_ms),
+            ("queries", self.queries as u64),
+            ("query latency min (us)", self.query_latency_us.min),
+            ("query latency p50 (us)", self.query_latency_us.p50),
+            ("query latency p90 (us)", self.query_latency_us.p90),
+            ("query latency p99 (us)", self.query_latency_us.p99),
+            ("query latency max (us)", self.query_latency_us.max),
+        ]
+    }
+
+    fn print(&self) {
+        println!("| {:^29} | {:^9} |", "benchmark", "value");
+        println!("|-------------------------------|-----------|");
+        for (title, value) in self.rows() {
+            println!("| {:>29} | {:>9} |", title, value);
+        }
+    }
+
+    fn print_comparison(&self, baseline: &Self) {
+        println!(
+            "| {:^29} | {:^9} | {:^9} | {:^9} |",
+            "benchmark", "baseline", "current", "change",
+        );
+        println!("|-------------------------------|-----------|-----------|-----------|");
+        for ((title, value), (_, baseline_value)) in self.rows().into_iter().zip(baseline.rows()) {
+            let change = if baseline_value == 0 {
+                "-".to_string()
+            } else {
+                let change = (value as f64 - baseline_value as f64) / baseline_value as f64;
+                format!("{:+.1}%", change * 100.0)
+            };
+            println!(
+                "| {:>29} | {:>9} | {:>9} | {:>9} |",
+                title, baseline_value, value, change
+            );
+        }
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct Percentiles {
+    min: u64,
+    p50: u64,
+    p90: u64,
+    p99: u64,
+    max: u64,
+}
+
+impl From<&FrequencyDistribution<u64>> for Percentiles {
+    fn from(hist: &FrequencyDistribution<u64>) -> Self {
+        let qs = hist.quantiles(100);
+        if qs.is_empty() {
+            return Self::default();
+        }
+        Self {
+            min: *qs[0],
+            p50: *qs[50],
+            p90: *qs[90],
+            p99: *qs[99],
+            max: *qs[100],
+        }
+    }
+}
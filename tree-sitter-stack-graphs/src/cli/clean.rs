@@ -8,10 +8,14 @@
 use clap::ArgGroup;
 use clap::Args;
 use clap::ValueHint;
+use serde_json::json;
 use stack_graphs::storage::SQLiteWriter;
 use std::path::Path;
 use std::path::PathBuf;
 
+use crate::cli::util::reporter::JsonReporter;
+use crate::cli::util::OutputFormat;
+
 #[derive(Args)]
 #[clap(group(
     ArgGroup::new("paths")
@@ -36,6 +40,11 @@ pub struct CleanArgs {
 
     #[clap(long, short = 'v')]
     pub verbose: bool,
+
+    /// Output format. JSON output contains a single object, with the `database` path, and either
+    /// `deleted` or the number of `removed_files`. It is always written, even without `--verbose`.
+    #[clap(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
 }
 
 impl CleanArgs {
@@ -48,11 +57,16 @@ impl CleanArgs {
     }
 
     fn delete(&self, db_path: &Path) -> anyhow::Result<()> {
-        if !db_path.exists() {
-            return Ok(());
+        let exists = db_path.exists();
+        if exists {
+            std::fs::remove_file(db_path)?;
         }
-        std::fs::remove_file(db_path)?;
-        if self.verbose {
+        if self.format == OutputFormat::Json {
+            JsonReporter::new().print(json!({
+                "database": db_path.to_string_lossy(),
+                "deleted": exists,
+            }))?;
+        } else if exists && self.verbose {
             println!("deleted database {}", db_path.display());
         }
         Ok(())
@@ -70,7 +84,12 @@ impl CleanArgs {
             }
             count
         };
-        if self.verbose {
+        if self.format == OutputFormat::Json {
+            JsonReporter::new().print(json!({
+                "database": db_path.to_string_lossy(),
+                "removed_files": count,
+            }))?;
+        } else if self.verbose {
             println!("removed data for {} files", count);
         }
         Ok(())
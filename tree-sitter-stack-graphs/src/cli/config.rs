@@ -0,0 +1,157 @@
+// -*- coding: utf-8 -*-
+// ------------------------------------------------------------------------------------------------
+// Copyright © 2023, stack-graphs authors.
+// Licensed under either of Apache License, Version 2.0, or MIT license, at your option.
+// Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
+// ------------------------------------------------------------------------------------------------
+
+//! Defines the project configuration file, which sets defaults for CLI arguments.
+//!
+//! The configuration file is called `stack-graphs.toml`, and is found by looking in the current
+//! directory and its ancestors. Relative paths in the file are relative to the directory that
+//! contains it. Arguments given on the command line override the values from the file.
+//!
+//! ``` toml
+//! database = "stack-graphs.sqlite"
+//!
+//! [loader]
+//! grammar = ["../tree-sitter-python"]
+//! tsg = "src/stack-graphs.tsg"
+//!
+//! [files]
+//! exclude = ["**/node_modules", "**/build"]
+//!
+//! [index]
+//! max-file-time = 10
+//!
+//! [lsp]
+//! max-query-time = 500
+//!
+//! [languages.python.globals]
+//! PROJECT_NAME = "my-project"
+//! ```
+//!
+//! Globals are added to the globals of every file in a language whose scope, scope without the
+//! `source.` prefix, or one of whose file types, is the language name.
+
+use anyhow::Context;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// The file name of the project configuration file.
+pub const CONFIG_FILE_NAME: &str = "stack-graphs.toml";
+
+/// The project configuration.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ProjectConfig {
+    /// Path of the indexing database.
+    pub database: Option<PathBuf>,
+    pub loader: LoaderConfig,
+    pub files: FilesConfig,
+    pub index: IndexConfig,
+    pub lsp: LspConfig,
+    /// Per-language settings, keyed by language name.
+    pub languages: HashMap<String, LanguageConfig>,
+}
+
+/// Defaults for [`PathLoaderArgs`][crate::cli::load::PathLoaderArgs].
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct LoaderConfig {
+    pub tsg: Option<PathBuf>,
+    pub builtins: Option<PathBuf>,
+    pub grammar: Vec<PathBuf>,
+    pub scope: Option<String>,
+    pub plugin: Vec<PathBuf>,
+}
+
+/// Defaults for [`FileFilterArgs`][crate::cli::util::FileFilterArgs], used when indexing.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct FilesConfig {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub no_ignore: bool,
+}
+
+/// Defaults for [`IndexArgs`][crate::cli::index::IndexArgs].
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct IndexConfig {
+    /// Maximum runtime per file in seconds.
+    pub max_file_time: Option<u64>,
+    pub tolerate_parse_errors: bool,
+}
+
+/// Defaults for `LspArgs`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct LspConfig {
+    /// Maximum index runtime per workspace folder in seconds.
+    pub max_folder_index_time: Option<u64>,
+    /// Maximum index runtime per file in seconds.
+    pub max_file_index_time: Option<u64>,
+    /// Maximum query runtime in milliseconds.
+    pub max_query_time: Option<u64>,
+    pub tolerate_parse_errors: bool,
+}
+
+/// Per-language settings.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct LanguageConfig {
+    /// Global variables for stack graph construction.
+    pub globals: HashMap<String, String>,
+}
+
+impl ProjectConfig {
+    /// Find and load the configuration file for the current directory. Returns the default
+    /// configuration if there is no configuration file.
+    pub fn discover() -> anyhow::Result<Self> {
+        let current_dir = std::env::current_dir()?;
+        match Self::find(&current_dir) {
+            Some(path) => Self::load(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Find the configuration file in the given directory or its ancestors.
+    pub fn find(dir: &Path) -> Option<PathBuf> {
+        dir.ancestors()
+            .map(|dir| dir.join(CONFIG_FILE_NAME))
+            .find(|path| path.is_file())
+    }
+
+    /// Load the configuration file at the given path. Relative paths in the configuration are
+    /// resolved against the directory containing the file.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Cannot read {}", path.display()))?;
+        let mut config: Self = toml::from_str(&content)
+            .with_context(|| format!("Invalid configuration in {}", path.display()))?;
+        if let Some(base) = path.parent() {
+            config.resolve_paths(base);
+        }
+        Ok(config)
+    }
+
+    fn resolve_paths(&mut self, base: &Path) {
+        let resolve = |path: &mut PathBuf| *path = base.join(&*path);
+        self.database.iter_mut().for_each(resolve);
+        self.loader.tsg.iter_mut().for_each(resolve);
+        self.loader.builtins.iter_mut().for_each(resolve);
+        self.loader.grammar.iter_mut().for_each(resolve);
+        self.loader.plugin.iter_mut().for_each(resolve);
+    }
+
+    /// Returns the global variables of all languages, keyed by language name.
+    pub fn language_globals(&self) -> HashMap<String, HashMap<String, String>> {
+        self.languages
+            .iter()
+            .map(|(name, language)| (name.clone(), language.globals.clone()))
+            .collect()
+    }
+}
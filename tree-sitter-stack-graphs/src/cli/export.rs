@@ -0,0 +1,258 @@
+// -*- coding: utf-8 -*-
+// ------------------------------------------------------------------------------------------------
+// Copyright © 2023, stack-graphs authors.
+// Licensed under either of Apache License, Version 2.0, or MIT license, at your option.
+// Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
+// ------------------------------------------------------------------------------------------------
+
+use anyhow::anyhow;
+use clap::Args;
+use clap::ValueEnum;
+use clap::ValueHint;
+use stack_graphs::arena::Handle;
+use stack_graphs::graph::File;
+use stack_graphs::graph::Node;
+use stack_graphs::graph::StackGraph;
+use stack_graphs::partial::PartialPath;
+use stack_graphs::stitching::ForwardPartialPathStitcher;
+use stack_graphs::stitching::StitcherConfig;
+use stack_graphs::storage::FileStatus;
+use stack_graphs::storage::SQLiteReader;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+use url::Url;
+
+use crate::CancellationFlag;
+use crate::NoCancellation;
+
+mod lsif;
+mod scip;
+
+/// Export database
+#[derive(Args)]
+#[clap(after_help = r#"FORMATS:
+    lsif    An LSIF dump, as line-delimited JSON. Ranges use UTF-16 offsets,
+            as required by LSP. Definitions in files that are not exported
+            are omitted.
+
+    scip    A SCIP index, as consumed by Sourcegraph and other SCIP tools.
+            Symbols are derived from the fully qualified names of definitions,
+            which are split into descriptors at the separator given by
+            --qualified-name-separator. Definitions without a fully qualified
+            name get a symbol based on their file and position.
+"#)]
+pub struct ExportArgs {
+    /// Source file or directory paths to export. If no paths are given, all indexed files in
+    /// the database are exported.
+    #[clap(
+        value_name = "SOURCE_PATH",
+        value_hint = ValueHint::AnyPath,
+    )]
+    pub source_paths: Vec<PathBuf>,
+
+    /// Export format.
+    #[clap(long, value_enum)]
+    pub format: ExportFormat,
+
+    /// Output file. Defaults to a file in the current directory, named after the format.
+    #[clap(
+        long,
+        short = 'o',
+        value_name = "OUTPUT_PATH",
+        value_hint = ValueHint::AnyPath,
+    )]
+    pub output: Option<PathBuf>,
+
+    /// Project root, which document paths are relative to. Defaults to the current directory.
+    #[clap(
+        long,
+        value_name = "PROJECT_ROOT",
+        value_hint = ValueHint::DirPath,
+    )]
+    pub project_root: Option<PathBuf>,
+
+    /// Separator between the components of fully qualified names, used to derive SCIP symbols.
+    #[clap(
+        long,
+        value_name = "SEPARATOR",
+        default_value = ".",
+        value_parser = clap::builder::NonEmptyStringValueParser::new(),
+    )]
+    pub qualified_name_separator: String,
+}
+
+/// Supported export formats
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    Lsif,
+    Scip,
+}
+
+impl ExportFormat {
+    fn default_output(&self) -> PathBuf {
+        match self {
+            Self::Lsif => PathBuf::from("dump.lsif"),
+            Self::Scip => PathBuf::from("index.scip"),
+        }
+    }
+}
+
+impl ExportArgs {
+    pub fn run(self, db_path: &Path) -> anyhow::Result<()> {
+        let cancellation_flag = &NoCancellation;
+        let project_root = match &self.project_root {
+            Some(project_root) => project_root.canonicalize()?,
+            None => std::env::current_dir()?.canonicalize()?,
+        };
+        let output = self
+            .output
+            .clone()
+            .unwrap_or_else(|| self.format.default_output());
+
+        let mut db = SQLiteReader::open(&db_path)?;
+        let files = self.resolve_files(&mut db, cancellation_flag)?;
+        let (graph, _, _) = db.get();
+        let graph: &StackGraph = graph;
+
+        if let Some(dir) = output.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        match self.format {
+            ExportFormat::Lsif => lsif::write(graph, &files, &project_root, &output)?,
+            ExportFormat::Scip => scip::write(
+                graph,
+                &files,
+                &project_root,
+                &self.qualified_name_separator,
+                &output,
+            )?,
+        }
+        println!("Exported {} files to {}", files.len(), output.display());
+        Ok(())
+    }
+
+    /// Load the graphs of all indexed files selected by the source paths, and resolve all
+    /// references in them.
+    fn resolve_files(
+        &self,
+        db: &mut SQLiteReader,
+        cancellation_flag: &dyn CancellationFlag,
+    ) -> anyhow::Result<Vec<ExportedFile>> {
+        let mut paths = Vec::new();
+        if self.source_paths.is_empty() {
+            let mut files = db.list_all()?;
+            for entry in files.try_iter()? {
+                let entry = entry?;
+                if let FileStatus::Indexed = entry.status {
+                    paths.push(entry.path);
+                }
+            }
+        } else {
+            for source_path in &self.source_paths {
+                let source_path = source_path.canonicalize()?;
+                let mut files = db.list_file_or_directory(&source_path)?;
+                for entry in files.try_iter()? {
+                    let entry = entry?;
+                    if let FileStatus::Indexed = entry.status {
+                        paths.push(entry.path);
+                    }
+                }
+            }
+        }
+        paths.sort();
+        paths.dedup();
+
+        let mut files = Vec::with_capacity(paths.len());
+        for path in paths {
+            let file = db.load_graph_for_file(&path.to_string_lossy())?;
+            files.push(ExportedFile {
+                path,
+                file,
+                definitions: Vec::new(),
+                references: Vec::new(),
+            });
+        }
+
+        let stitcher_config = StitcherConfig::default()
+            // always detect similar paths, we don't know the language configurations for the data in the database
+            .with_detect_similar_paths(true);
+        for exported_file in &mut files {
+            cancellation_flag.check("resolving references")?;
+            let (graph, _, _) = db.get();
+            let mut references = Vec::new();
+            for node in graph.nodes_for_file(exported_file.file) {
+                if graph.source_info(node).is_none() {
+                    continue;
+                }
+                if graph[node].is_definition() {
+                    exported_file.definitions.push(node);
+                }
+                if graph[node].is_reference() {
+                    references.push(node);
+                }
+            }
+
+            let mut reference_paths = BTreeMap::<Handle<Node>, Vec<PartialPath>>::new();
+            ForwardPartialPathStitcher::find_all_complete_partial_paths(
+                &mut *db,
+                references.iter().cloned(),
+                stitcher_config,
+                &cancellation_flag,
+                |_, _, p| {
+                    reference_paths
+                        .entry(p.start_node)
+                        .or_default()
+                        .push(p.clone());
+                },
+            )?;
+
+            let (_, partials, _) = db.get();
+            for reference in references {
+                let paths = reference_paths.remove(&reference).unwrap_or_default();
+                let mut definitions = Vec::new();
+                for path in &paths {
+                    if paths.iter().all(|other| !other.shadows(partials, path))
+                        && !definitions.contains(&path.end_node)
+                    {
+                        definitions.push(path.end_node);
+                    }
+                }
+                exported_file.references.push(ExportedReference {
+                    node: reference,
+                    definitions,
+                });
+            }
+        }
+
+        Ok(files)
+    }
+}
+
+/// An exported file, with all its definitions and resolved references.
+struct ExportedFile {
+    path: PathBuf,
+    file: Handle<File>,
+    definitions: Vec<Handle<Node>>,
+    references: Vec<ExportedReference>,
+}
+
+/// A reference and the definitions it resolves to. Definitions may be in other files than the
+/// reference, including files that are not exported.
+struct ExportedReference {
+    node: Handle<Node>,
+    definitions: Vec<Handle<Node>>,
+}
+
+/// Returns the path of the given file relative to the project root, or the path unchanged if it
+/// is not inside the project root.
+fn relative_path<'a>(path: &'a Path, project_root: &Path) -> &'a Path {
+    path.strip_prefix(project_root).unwrap_or(path)
+}
+
+/// Returns the file URI of the given path. Paths must be absolute.
+fn uri(path: &Path) -> anyhow::Result<String> {
+    let uri = Url::from_file_path(path)
+        .map_err(|_| anyhow!("Cannot convert {} to a URI", path.display()))?;
+    Ok(uri.to_string())
+}
@@ -0,0 +1,243 @@
+// -*- coding: utf-8 -*-
+// ------------------------------------------------------------------------------------------------
+// Copyright © 2023, stack-graphs authors.
+// Licensed under either of Apache License, Version 2.0, or MIT license, at your option.
+// Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
+// ------------------------------------------------------------------------------------------------
+
+//! Export to the [LSIF](https://microsoft.github.io/language-server-protocol/specifications/lsif/0.6.0/specification/)
+//! dump format.
+//!
+//! Every definition gets a result set with a definition and a reference result. References that
+//! resolve to a single exported definition share the result set of that definition, while
+//! references with multiple definitions get their own result set and definition result.
+//! Definitions in files that are not exported are omitted.
+
+use serde_json::json;
+use serde_json::Value;
+use stack_graphs::arena::Handle;
+use stack_graphs::graph::Node;
+use stack_graphs::graph::StackGraph;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+
+use super::uri;
+use super::ExportedFile;
+
+static LSIF_VERSION: &'static str = "0.6.0";
+
+/// Write an LSIF dump for the given files.
+pub(super) fn write(
+    graph: &StackGraph,
+    files: &[ExportedFile],
+    project_root: &Path,
+    output: &Path,
+) -> anyhow::Result<()> {
+    let mut w = Emitter::new(std::io::BufWriter::new(std::fs::File::create(output)?));
+    w.vertex(
+        "metaData",
+        json!({
+            "version": LSIF_VERSION,
+            "projectRoot": uri(project_root)?,
+            "positionEncoding": "utf-16",
+            "toolInfo": {
+                "name": "stack-graphs",
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+        }),
+    )?;
+
+    // documents and ranges
+    let mut definition_ranges = HashMap::<Handle<Node>, Range>::new();
+    let mut reference_ranges = Vec::new();
+    for file in files {
+        let document = w.vertex(
+            "document",
+            json!({
+                "uri": uri(&file.path)?,
+                "languageId": "",
+            }),
+        )?;
+        let mut ranges = Vec::new();
+        for definition in &file.definitions {
+            let id = w.vertex("range", range(graph, *definition))?;
+            definition_ranges.insert(*definition, Range { id, document });
+            ranges.push(id);
+        }
+        for reference in &file.references {
+            let id = w.vertex("range", range(graph, reference.node))?;
+            reference_ranges.push((Range { id, document }, &reference.definitions));
+            ranges.push(id);
+        }
+        if !ranges.is_empty() {
+            w.edge_many("contains", document, &ranges, json!({}))?;
+        }
+    }
+
+    // the references of every exported definition
+    let mut references_by_definition = HashMap::<Handle<Node>, Vec<Range>>::new();
+    for (reference, definitions) in &reference_ranges {
+        for definition in definitions.iter() {
+            if definition_ranges.contains_key(definition) {
+                references_by_definition
+                    .entry(*definition)
+                    .or_default()
+                    .push(*reference);
+            }
+        }
+    }
+
+    // result sets for definitions
+    let mut result_sets = HashMap::<Handle<Node>, usize>::new();
+    for file in files {
+        for definition in &file.definitions {
+            let definition_range = definition_ranges[definition];
+            let result_set = w.vertex("resultSet", json!({}))?;
+            w.edge("next", definition_range.id, result_set)?;
+            result_sets.insert(*definition, result_set);
+
+            let definition_result = w.vertex("definitionResult", json!({}))?;
+            w.edge("textDocument/definition", result_set, definition_result)?;
+            w.items(definition_result, &[definition_range], None)?;
+
+            let reference_result = w.vertex("referenceResult", json!({}))?;
+            w.edge("textDocument/references", result_set, reference_result)?;
+            w.items(reference_result, &[definition_range], Some("definitions"))?;
+            if let Some(references) = references_by_definition.get(definition) {
+                w.items(reference_result, references, Some("references"))?;
+            }
+        }
+    }
+
+    // link references to the result sets of their definitions
+    for (reference, definitions) in &reference_ranges {
+        let definitions = definitions
+            .iter()
+            .filter(|d| definition_ranges.contains_key(*d))
+            .collect::<Vec<_>>();
+        match definitions.len() {
+            0 => {}
+            1 => {
+                w.edge("next", reference.id, result_sets[definitions[0]])?;
+            }
+            _ => {
+                let result_set = w.vertex("resultSet", json!({}))?;
+                w.edge("next", reference.id, result_set)?;
+                let definition_result = w.vertex("definitionResult", json!({}))?;
+                w.edge("textDocument/definition", result_set, definition_result)?;
+                let ranges = definitions
+                    .into_iter()
+                    .map(|d| definition_ranges[d])
+                    .collect::<Vec<_>>();
+                w.items(definition_result, &ranges, None)?;
+            }
+        }
+    }
+
+    w.flush()?;
+    Ok(())
+}
+
+/// A range vertex, and the document vertex that contains it.
+#[derive(Clone, Copy)]
+struct Range {
+    id: usize,
+    document: usize,
+}
+
+/// Returns the LSIF range of the given node. Ranges use 0-based lines and UTF-16 offsets, as
+/// required by LSP.
+fn range(graph: &StackGraph, node: Handle<Node>) -> Value {
+    let span = &graph
+        .source_info(node)
+        .expect("exported nodes have source info")
+        .span;
+    json!({
+        "start": {
+            "line": span.start.line,
+            "character": span.start.column.utf16_offset,
+        },
+        "end": {
+            "line": span.end.line,
+            "character": span.end.column.utf16_offset,
+        },
+    })
+}
+
+/// Writes LSIF elements as line-delimited JSON, and assigns element ids.
+struct Emitter<W: Write> {
+    w: W,
+    next_id: usize,
+}
+
+impl<W: Write> Emitter<W> {
+    fn new(w: W) -> Self {
+        Self { w, next_id: 1 }
+    }
+
+    /// Write a vertex with the given label and properties, and return its id.
+    fn vertex(&mut self, label: &str, properties: Value) -> anyhow::Result<usize> {
+        self.element("vertex", label, properties)
+    }
+
+    /// Write an edge with a single in vertex.
+    fn edge(&mut self, label: &str, out_v: usize, in_v: usize) -> anyhow::Result<usize> {
+        self.element("edge", label, json!({ "outV": out_v, "inV": in_v }))
+    }
+
+    /// Write an edge with multiple in vertices, and additional properties.
+    fn edge_many(
+        &mut self,
+        label: &str,
+        out_v: usize,
+        in_vs: &[usize],
+        mut properties: Value,
+    ) -> anyhow::Result<usize> {
+        properties["outV"] = json!(out_v);
+        properties["inVs"] = json!(in_vs);
+        self.element("edge", label, properties)
+    }
+
+    /// Write item edges from the given result to the given ranges. One edge is written per
+    /// document, because item edges must specify the document that contains the ranges.
+    fn items(
+        &mut self,
+        result: usize,
+        ranges: &[Range],
+        property: Option<&str>,
+    ) -> anyhow::Result<()> {
+        let mut ranges_by_document = BTreeMap::<usize, Vec<usize>>::new();
+        for range in ranges {
+            ranges_by_document
+                .entry(range.document)
+                .or_default()
+                .push(range.id);
+        }
+        for (document, ranges) in ranges_by_document {
+            let mut properties = json!({ "document": document });
+            if let Some(property) = property {
+                properties["property"] = json!(property);
+            }
+            self.edge_many("item", result, &ranges, properties)?;
+        }
+        Ok(())
+    }
+
+    fn element(&mut self, kind: &str, label: &str, mut properties: Value) -> anyhow::Result<usize> {
+        let id = self.next_id;
+        self.next_id += 1;
+        properties["id"] = json!(id);
+        properties["type"] = json!(kind);
+        properties["label"] = json!(label);
+        serde_json::to_writer(&mut self.w, &properties)?;
+        writeln!(self.w)?;
+        Ok(id)
+    }
+
+    fn flush(&mut self) -> anyhow::Result<()> {
+        self.w.flush()?;
+        Ok(())
+    }
+}
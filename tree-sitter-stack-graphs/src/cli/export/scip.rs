@@ -0,0 +1,192 @@
+// -*- coding: utf-8 -*-
+// ------------------------------------------------------------------------------------------------
+// Copyright © 2023, stack-graphs authors.
+// Licensed under either of Apache License, Version 2.0, or MIT license, at your option.
+// Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
+// ------------------------------------------------------------------------------------------------
+
+//! Export to the [SCIP](https://github.com/sourcegraph/scip) index format.
+
+use ::scip::types::Document;
+use ::scip::types::Index;
+use ::scip::types::Metadata;
+use ::scip::types::Occurrence;
+use ::scip::types::SymbolInformation;
+use ::scip::types::SymbolRole;
+use ::scip::types::TextEncoding;
+use ::scip::types::ToolInfo;
+use protobuf::Message;
+use protobuf::MessageField;
+use stack_graphs::arena::Handle;
+use stack_graphs::graph::Node;
+use stack_graphs::graph::StackGraph;
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::relative_path;
+use super::uri;
+use super::ExportedFile;
+
+static SCHEME: &'static str = "stack-graphs";
+
+/// Write a SCIP index for the given files.
+pub(super) fn write(
+    graph: &StackGraph,
+    files: &[ExportedFile],
+    project_root: &Path,
+    qualified_name_separator: &str,
+    output: &Path,
+) -> anyhow::Result<()> {
+    let mut symbols = Symbols::new(graph, project_root, qualified_name_separator);
+    let mut index = Index::new();
+    index.metadata = MessageField::some(Metadata {
+        tool_info: MessageField::some(ToolInfo {
+            name: SCHEME.to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            ..Default::default()
+        }),
+        project_root: uri(project_root)?,
+        text_document_encoding: TextEncoding::UTF8.into(),
+        ..Default::default()
+    });
+    for file in files {
+        let mut document = Document {
+            relative_path: relative_path(&file.path, project_root)
+                .to_string_lossy()
+                .to_string(),
+            ..Default::default()
+        };
+        for definition in &file.definitions {
+            let symbol = symbols.get(*definition);
+            document.occurrences.push(Occurrence {
+                range: range(graph, *definition),
+                symbol: symbol.clone(),
+                symbol_roles: SymbolRole::Definition as i32,
+                ..Default::default()
+            });
+            document.symbols.push(SymbolInformation {
+                symbol,
+                ..Default::default()
+            });
+        }
+        for reference in &file.references {
+            for definition in &reference.definitions {
+                document.occurrences.push(Occurrence {
+                    range: range(graph, reference.node),
+                    symbol: symbols.get(*definition),
+                    ..Default::default()
+                });
+            }
+        }
+        index.documents.push(document);
+    }
+    std::fs::write(output, index.write_to_bytes()?)?;
+    Ok(())
+}
+
+/// Returns the SCIP range of the given node. Ranges use 0-based lines and UTF-8 offsets, and
+/// consist of three elements if the range is on a single line.
+fn range(graph: &StackGraph, node: Handle<Node>) -> Vec<i32> {
+    let span = &graph
+        .source_info(node)
+        .expect("exported nodes have source info")
+        .span;
+    if span.start.line == span.end.line {
+        vec![
+            span.start.line as i32,
+            span.start.column.utf8_offset as i32,
+            span.end.column.utf8_offset as i32,
+        ]
+    } else {
+        vec![
+            span.start.line as i32,
+            span.start.column.utf8_offset as i32,
+            span.end.line as i32,
+            span.end.column.utf8_offset as i32,
+        ]
+    }
+}
+
+/// Computes and caches SCIP symbols for definitions.
+struct Symbols<'a> {
+    graph: &'a StackGraph,
+    project_root: &'a Path,
+    qualified_name_separator: &'a str,
+    symbols: HashMap<Handle<Node>, String>,
+}
+
+impl<'a> Symbols<'a> {
+    fn new(
+        graph: &'a StackGraph,
+        project_root: &'a Path,
+        qualified_name_separator: &'a str,
+    ) -> Self {
+        Self {
+            graph,
+            project_root,
+            qualified_name_separator,
+            symbols: HashMap::new(),
+        }
+    }
+
+    /// Returns the symbol for the given definition. Symbols are derived from the fully qualified
+    /// name if the definition has one, split at the qualified name separator. Otherwise, a symbol
+    /// based on the file and the node is used, which is unique but not stable across indexing runs.
+    fn get(&mut self, definition: Handle<Node>) -> String {
+        if let Some(symbol) = self.symbols.get(&definition) {
+            return symbol.clone();
+        }
+        let graph = self.graph;
+        let fully_qualified_name = graph
+            .source_info(definition)
+            .and_then(|si| si.fully_qualified_name.into_option())
+            .map(|fqn| &graph[fqn]);
+        let descriptors = match fully_qualified_name {
+            Some(fully_qualified_name) => {
+                let mut components = fully_qualified_name
+                    .split(self.qualified_name_separator)
+                    .collect::<Vec<_>>();
+                let name = components.pop().unwrap_or_default();
+                components
+                    .into_iter()
+                    .map(|c| format!("{}/", escape(c)))
+                    .chain(std::iter::once(format!("{}.", escape(name))))
+                    .collect::<String>()
+            }
+            None => {
+                let node = &graph[definition];
+                let path = node
+                    .id()
+                    .file()
+                    .map(|f| graph[f].name().to_string())
+                    .unwrap_or_default();
+                let path = relative_path(Path::new(&path), self.project_root)
+                    .to_string_lossy()
+                    .to_string();
+                let name = node.symbol().map(|s| &graph[s]).unwrap_or_default();
+                format!(
+                    "{}/{}.",
+                    escape(&path),
+                    escape(&format!("{}@{}", name, node.id().local_id()))
+                )
+            }
+        };
+        let symbol = format!("{} . . . {}", SCHEME, descriptors);
+        self.symbols.insert(definition, symbol.clone());
+        symbol
+    }
+}
+
+/// Escape a descriptor name, if it contains characters that are not allowed in simple
+/// identifiers.
+fn escape(name: &str) -> String {
+    if !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '+' || c == '-' || c == '$')
+    {
+        name.to_string()
+    } else {
+        format!("`{}`", name.replace('`', "``"))
+    }
+}
@@ -5,8 +5,11 @@
 // Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
 // ------------------------------------------------------------------------------------------------
 
+use anyhow::anyhow;
 use clap::Args;
 use clap::ValueHint;
+use notify::RecursiveMode;
+use notify::Watcher;
 use stack_graphs::arena::Handle;
 use stack_graphs::graph::File;
 use stack_graphs::graph::StackGraph;
@@ -17,15 +20,24 @@ use stack_graphs::stitching::Stats as StitchingStats;
 use stack_graphs::stitching::StitcherConfig;
 use stack_graphs::storage::FileStatus;
 use stack_graphs::storage::SQLiteWriter;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use std::collections::HashMap;
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::RecvError;
+use std::sync::mpsc::RecvTimeoutError;
 use std::time::Duration;
+use std::time::Instant;
 use thiserror::Error;
+use tree_sitter_graph::parse_error::TreeWithParseErrorVec;
 use tree_sitter_graph::Variables;
 
+use crate::cli::config::ProjectConfig;
 use crate::cli::util::duration_from_seconds_str;
-use crate::cli::util::iter_files_and_directories;
+use crate::cli::util::git;
+use crate::cli::util::iter_filtered_files_and_directories;
 use crate::cli::util::print_indexing_stats;
 use crate::cli::util::reporter::ConsoleReporter;
 use crate::cli::util::reporter::Level;
@@ -35,13 +47,22 @@ use crate::cli::util::wait_for_input;
 use crate::cli::util::BuildErrorWithSource;
 use crate::cli::util::CLIFileReporter;
 use crate::cli::util::ExistingPathBufValueParser;
+use crate::cli::util::FileFilter;
+use crate::cli::util::FileFilterArgs;
+use crate::cli::util::OutputFormat;
+use crate::coverage::StanzaCoverage;
 use crate::loader::FileLanguageConfigurations;
 use crate::loader::FileReader;
+use crate::loader::LanguageConfiguration;
 use crate::loader::Loader;
 use crate::BuildError;
+use crate::BuildOutput;
+use crate::BuildTimings;
 use crate::CancelAfterDuration;
 use crate::CancellationFlag;
+use crate::Injection;
 use crate::NoCancellation;
+use crate::StackGraphLanguage;
 
 #[derive(Args)]
 pub struct IndexArgs {
@@ -54,6 +75,9 @@ pub struct IndexArgs {
     )]
     pub source_paths: Vec<PathBuf>,
 
+    #[clap(flatten)]
+    pub file_filter: FileFilterArgs,
+
     /// Continue indexing from the given file.
     #[clap(
         long,
@@ -85,15 +109,49 @@ pub struct IndexArgs {
     #[clap(long)]
     pub stats: bool,
 
+    /// Output format of the file statuses.
+    #[clap(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+
     /// Wait for user input before starting analysis. Useful for profiling.
     #[clap(long)]
     pub wait_at_start: bool,
+
+    /// Index files with parse errors, skipping the parts of the file that could not be parsed.
+    #[clap(long, overrides_with = "no_tolerate_parse_errors")]
+    pub tolerate_parse_errors: bool,
+
+    /// Fail to index files with parse errors, even if the project configuration tolerates them.
+    #[clap(long, overrides_with = "tolerate_parse_errors")]
+    pub no_tolerate_parse_errors: bool,
+
+    /// Keep watching the source paths after indexing, and update the index when files are
+    /// created, modified, renamed, or deleted.
+    #[clap(long, conflicts_with = "stats")]
+    pub watch: bool,
+
+    /// Only index files that were added, modified, renamed, or deleted since the given git
+    /// revision, including uncommitted changes. Without a revision, the revision recorded by the
+    /// previous run is used, and all files are indexed if there is none. The indexed revision is
+    /// recorded in the database whenever the sources are in a git checkout.
+    #[clap(
+        long,
+        value_name = "REVISION",
+        num_args = 0..=1,
+        conflicts_with = "continue_from",
+    )]
+    pub since: Option<Option<String>>,
+
+    /// Global variables per language name, set from the project configuration.
+    #[clap(skip)]
+    pub language_globals: HashMap<String, HashMap<String, String>>,
 }
 
 impl IndexArgs {
     pub fn new(source_paths: Vec<PathBuf>) -> Self {
         Self {
             source_paths,
+            file_filter: FileFilterArgs::new(),
             force: false,
             continue_from: None,
             verbose: false,
@@ -101,36 +159,87 @@ impl IndexArgs {
             max_file_time: None,
             wait_at_start: false,
             stats: false,
+            format: OutputFormat::Text,
+            tolerate_parse_errors: false,
+            no_tolerate_parse_errors: false,
+            watch: false,
+            since: None,
+            language_globals: HashMap::new(),
+        }
+    }
+
+    /// Use the values from the configuration for arguments that were not given.
+    pub fn apply_config(&mut self, config: &ProjectConfig) {
+        self.file_filter.apply_config(&config.files);
+        if self.max_file_time.is_none() {
+            self.max_file_time = config.index.max_file_time.map(Duration::from_secs);
         }
+        if !self.no_tolerate_parse_errors {
+            self.tolerate_parse_errors |= config.index.tolerate_parse_errors;
+        }
+        self.language_globals = config.language_globals();
     }
 
     pub fn run(self, db_path: &Path, mut loader: Loader) -> anyhow::Result<()> {
+        if self.stats && self.format == OutputFormat::Json {
+            return Err(anyhow!("Statistics are not supported in JSON output"));
+        }
         if self.wait_at_start {
             wait_for_input()?;
         }
         let mut db = SQLiteWriter::open(&db_path)?;
+        let source_paths = self
+            .source_paths
+            .iter()
+            .map(|p| p.canonicalize())
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let git_dir = git_dir_for(&source_paths);
+        let since = match &self.since {
+            Some(Some(since)) => Some(since.clone()),
+            Some(None) => db.metadata_value(INDEXED_REVISION_KEY)?,
+            None => None,
+        };
+        // without --since, the sources do not have to be in a git checkout
+        let head = if self.since.is_some() {
+            Some(git::head_revision(git_dir)?)
+        } else {
+            git::head_revision(git_dir).ok()
+        };
         let reporter = self.get_reporter();
-        let mut indexer = Indexer::new(&mut db, &mut loader, &reporter);
+        loader.set_error_tolerant(self.tolerate_parse_errors);
+        let mut indexer = Indexer::new(&mut db, &mut loader, &*reporter);
         indexer.force = self.force;
         indexer.max_file_time = self.max_file_time;
+        indexer.file_filter = self.file_filter.get()?;
+        indexer.language_globals = self.language_globals;
         indexer.set_collect_stats(self.stats);
 
-        let source_paths = self
-            .source_paths
-            .into_iter()
-            .map(|p| p.canonicalize())
-            .collect::<std::result::Result<Vec<_>, _>>()?;
-        indexer.index_all(source_paths, self.continue_from, &NoCancellation)?;
+        match &since {
+            Some(since) => {
+                let changes = git::changes_since(git_dir, since)?;
+                indexer.index_changes(&source_paths, changes)?;
+            }
+            None => indexer.index_all(&source_paths, self.continue_from, &NoCancellation)?,
+        }
+        // the revision is recorded before watching, so that it is not lost if watching is stopped
+        if let Some(head) = &head {
+            indexer.db.set_metadata_value(INDEXED_REVISION_KEY, head)?;
+        }
+
+        if self.watch {
+            watch_and_index(&mut indexer, &source_paths)?;
+        }
 
+        let stats = indexer.into_stats();
         if self.stats {
             println!();
-            print_indexing_stats(indexer.into_stats());
+            print_indexing_stats(stats);
         }
         Ok(())
     }
 
-    fn get_reporter(&self) -> ConsoleReporter {
-        return ConsoleReporter {
+    fn get_reporter(&self) -> Box<dyn Reporter> {
+        self.format.reporter(ConsoleReporter {
             skipped_level: if self.verbose {
                 Level::Summary
             } else {
@@ -151,7 +260,7 @@ impl IndexArgs {
             } else {
                 Level::Details
             },
-        };
+        })
     }
 }
 
@@ -164,6 +273,11 @@ pub struct Indexer<'a> {
     pub force: bool,
     /// Maximum time per file.
     pub max_file_time: Option<Duration>,
+    /// Filter for the files in source directories.
+    pub file_filter: FileFilter,
+    /// Global variables per language name. Globals are added for every language that
+    /// [matches the name][LanguageConfiguration::matches_name].
+    pub language_globals: HashMap<String, HashMap<String, String>>,
 }
 
 impl<'a> Indexer<'a> {
@@ -178,6 +292,8 @@ impl<'a> Indexer<'a> {
             reporter,
             force: false,
             max_file_time: None,
+            file_filter: FileFilter::all(),
+            language_globals: HashMap::new(),
             stats: None,
         }
     }
@@ -201,7 +317,9 @@ impl<'a> Indexer<'a> {
         IP: IntoIterator<Item = P>,
         Q: AsRef<Path>,
     {
-        for (source_root, source_path, strict) in iter_files_and_directories(source_paths) {
+        for (source_root, source_path, strict) in
+            iter_filtered_files_and_directories(source_paths, self.file_filter.clone())
+        {
             let mut file_status = CLIFileReporter::new(self.reporter, &source_path);
             cancellation_flag.check("indexing all files")?;
             self.index_file(
@@ -236,6 +354,76 @@ impl<'a> Indexer<'a> {
         Ok(())
     }
 
+    /// Remove the file, or all files in the directory, from the database. Nothing is reported if
+    /// the database contained no such files.
+    pub fn remove(&mut self, source_path: &Path) -> Result<()> {
+        if self.db.clean_file_or_directory(source_path)? > 0 {
+            let mut file_status = CLIFileReporter::new(self.reporter, source_path);
+            file_status.success("removed", None);
+        }
+        Ok(())
+    }
+
+    /// Update the index for the given changes. Removed files and the old paths of renamed files
+    /// are cleaned from the database, and changed files are indexed if they are in the source
+    /// paths.
+    pub fn index_changes(
+        &mut self,
+        source_paths: &[PathBuf],
+        changes: git::Changes,
+    ) -> anyhow::Result<()> {
+        for removed_path in changes.removed {
+            if source_root_for(source_paths, &removed_path).is_some() {
+                self.remove(&removed_path)?;
+            }
+        }
+        for changed_path in changes.changed {
+            let source_root = match source_root_for(source_paths, &changed_path) {
+                Some(source_root) => source_root,
+                None => continue,
+            };
+            if changed_path.is_file() {
+                index_changed_file(self, &source_root, &changed_path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Update the index for the file system events received from a watcher of the source paths,
+    /// until the sender is disconnected. Changes are collected until no change has been seen for
+    /// [`WATCH_DEBOUNCE_TIME`][], so that bursts of changes, such as a branch checkout, are handled
+    /// at once. Files that fail to index are reported, and do not stop the watch.
+    pub fn index_watched_changes(
+        &mut self,
+        source_paths: &[PathBuf],
+        events: &Receiver<notify::Result<notify::Event>>,
+    ) -> anyhow::Result<()> {
+        while let Some(changed_paths) = receive_changed_paths(events)? {
+            for changed_path in changed_paths {
+                let source_root = match source_root_for(source_paths, &changed_path) {
+                    Some(source_root) => source_root,
+                    None => continue,
+                };
+                // errors are reported by the indexer, and the watch continues with other files
+                if changed_path.is_file() {
+                    let _ = index_changed_file(self, &source_root, &changed_path);
+                } else if changed_path.is_dir() {
+                    // a directory was moved into the source paths, and is not reported file by file
+                    let file_filter = self.file_filter.clone();
+                    for source_path in
+                        file_filter.iter_selected_files_in(&source_root, &changed_path)
+                    {
+                        let _ = index_selected_file(self, &source_root, &source_path);
+                    }
+                } else if let Err(err) = self.remove(&changed_path) {
+                    CLIFileReporter::new(self.reporter, &changed_path)
+                        .failure("failed to remove", Some(&err));
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Analyze file and add error context to any failures that are returned.
     fn index_file<P>(
         &mut self,
@@ -303,9 +491,7 @@ impl<'a> Indexer<'a> {
             }
             Err(e) => return Err(IndexError::LoadError(e)),
         };
-        let stitcher_config = StitcherConfig::default()
-            .with_detect_similar_paths(!lcs.no_similar_paths_in_file())
-            .with_collect_stats(self.stats.is_some());
+        let mut no_similar_paths_in_file = lcs.no_similar_paths_in_file();
 
         let source = file_reader.get(source_path)?;
         let tag = sha1(source);
@@ -343,6 +529,8 @@ impl<'a> Indexer<'a> {
             .add_file(&source_path.to_string_lossy())
             .expect("file not present in empty graph");
 
+        let mut parse_error_details = Vec::new();
+        let mut build_timings = BuildTimings::default();
         let result = Self::build_stack_graph(
             &mut graph,
             file,
@@ -350,32 +538,85 @@ impl<'a> Indexer<'a> {
             source_path,
             &source,
             lcs,
+            &self.language_globals,
+            &mut parse_error_details,
+            &mut build_timings,
+            self.stats.as_mut(),
             &cancellation_flag,
         );
-        if let Err(err) = result {
-            match err.inner {
-                BuildError::Cancelled(_) => {
-                    file_status.warning("parsing timed out", None);
-                    self.db
-                        .store_error_for_file(source_path, &tag, "parsing timed out")?;
+        let injections = match result {
+            Ok(injections) => injections,
+            Err(err) => {
+                return Self::handle_build_error(self.db, source_path, &tag, err, file_status)
+            }
+        };
+        // Injected regions are built with the language named by the injection. Regions in
+        // languages that are not supported, or nested deeper than MAX_INJECTION_DEPTH, are skipped.
+        let mut injections = injections
+            .into_iter()
+            .map(|injection| (injection, 1))
+            .collect::<Vec<_>>();
+        while let Some((injection, depth)) = injections.pop() {
+            let lc = match self
+                .loader
+                .load_for_injection(&injection.language, &cancellation_flag)
+            {
+                Ok(Some(lc)) => lc,
+                Ok(None) => continue,
+                Err(crate::loader::LoadError::Cancelled(_)) => {
+                    file_status.warning("language loading timed out", None);
                     return Ok(());
                 }
-                BuildError::ParseErrors { .. } => {
-                    file_status.failure("parsing failed", Some(&err.display_pretty()));
-                    self.db.store_error_for_file(
-                        source_path,
-                        &tag,
-                        &format!("parsing failed: {}", err.inner),
-                    )?;
-                    return Ok(());
+                Err(e) => return Err(IndexError::LoadError(e)),
+            };
+            no_similar_paths_in_file &= lc.no_similar_paths_in_file;
+            let mut globals = Variables::new();
+            Self::add_language_globals(&self.language_globals, lc, &mut globals);
+            match lc.sgl.build_injection_into(
+                &mut graph,
+                file,
+                &source,
+                &injection,
+                &globals,
+                &cancellation_flag,
+            ) {
+                Ok(output) => {
+                    build_timings += output.timings;
+                    if let Some(stats) = &mut self.stats {
+                        // matching the tree that was just executed cannot fail
+                        let _ = stats.record_stanza_matches(&lc.sgl, &source, &output);
+                    }
+                    if let Some(parse_errors) = output.parse_errors {
+                        parse_error_details.push(Self::parse_error_details(
+                            parse_errors,
+                            source_path,
+                            &source,
+                            &lc.sgl,
+                        ));
+                    }
+                    if depth < MAX_INJECTION_DEPTH {
+                        injections.extend(
+                            output
+                                .injections
+                                .into_iter()
+                                .map(|injection| (injection, depth + 1)),
+                        );
+                    }
                 }
-                _ => {
-                    file_status.failure("failed to build stack graph", Some(&err.display_pretty()));
-                    return Err(IndexError::StackGraph);
+                Err(inner) => {
+                    let err = BuildErrorWithSource {
+                        inner,
+                        source_path: source_path.to_path_buf(),
+                        source_str: &source,
+                        tsg_path: lc.sgl.tsg_path().to_path_buf(),
+                        tsg_str: &lc.sgl.tsg_source(),
+                    };
+                    return Self::handle_build_error(self.db, source_path, &tag, err, file_status);
                 }
             }
-        };
+        }
         if let Some(stats) = &mut self.stats {
+            stats.timing_stats.record_build(&build_timings);
             stats.total_graph_nodes.record(graph.iter_nodes().count());
             let mut total_edges = 0;
             for n in graph.iter_nodes() {
@@ -390,8 +631,12 @@ impl<'a> Indexer<'a> {
             stats.total_graph_edges.record(total_edges);
         }
 
+        let stitcher_config = StitcherConfig::default()
+            .with_detect_similar_paths(!no_similar_paths_in_file)
+            .with_collect_stats(self.stats.is_some());
         let mut partials = PartialPaths::new();
         let mut paths = Vec::new();
+        let path_computation_start = Instant::now();
         match ForwardPartialPathStitcher::find_minimal_partial_path_set_in_file(
             &graph,
             &mut partials,
@@ -404,6 +649,9 @@ impl<'a> Indexer<'a> {
         ) {
             Ok(stitching_stats) => {
                 if let Some(stats) = &mut self.stats {
+                    stats
+                        .timing_stats
+                        .record_path_computation(path_computation_start.elapsed());
                     stats.stitching_stats += stitching_stats;
                 }
             }
@@ -421,7 +669,14 @@ impl<'a> Indexer<'a> {
         self.db
             .store_result_for_file(&graph, file, &tag, &mut partials, &paths)?;
 
-        file_status.success(success_status, None);
+        if parse_error_details.is_empty() {
+            file_status.success(success_status, None);
+        } else {
+            file_status.warning(
+                "indexed with parse errors",
+                Some(&parse_error_details.join("\n")),
+            );
+        }
 
         Ok(())
     }
@@ -433,13 +688,26 @@ impl<'a> Indexer<'a> {
         source_path: &Path,
         source: &'b str,
         lcs: FileLanguageConfigurations<'b>,
+        language_globals: &HashMap<String, HashMap<String, String>>,
+        parse_error_details: &mut Vec<String>,
+        timings: &mut BuildTimings,
+        stats: Option<&mut IndexingStats>,
         cancellation_flag: &dyn CancellationFlag,
-    ) -> std::result::Result<(), BuildErrorWithSource<'b>> {
+    ) -> std::result::Result<Vec<Injection>, BuildErrorWithSource<'b>> {
         let relative_source_path = source_path.strip_prefix(source_root).unwrap();
+        let mut injections = Vec::new();
         if let Some(lc) = lcs.primary {
-            let globals = Variables::new();
-            lc.sgl
-                .build_stack_graph_into(graph, file, source, &globals, cancellation_flag)
+            let mut globals = Variables::new();
+            Self::add_language_globals(language_globals, lc, &mut globals);
+            let output = lc
+                .sgl
+                .build_stack_graph_with_output_into(
+                    graph,
+                    file,
+                    source,
+                    &globals,
+                    cancellation_flag,
+                )
                 .map_err(|inner| BuildErrorWithSource {
                     inner,
                     source_path: source_path.to_path_buf(),
@@ -447,6 +715,20 @@ impl<'a> Indexer<'a> {
                     tsg_path: lc.sgl.tsg_path().to_path_buf(),
                     tsg_str: &lc.sgl.tsg_source(),
                 })?;
+            if let Some(parse_errors) = output.parse_errors {
+                parse_error_details.push(Self::parse_error_details(
+                    parse_errors,
+                    source_path,
+                    source,
+                    &lc.sgl,
+                ));
+            }
+            *timings += output.timings;
+            if let Some(stats) = stats {
+                // matching the tree that was just executed cannot fail
+                let _ = stats.record_stanza_matches(&lc.sgl, source, &output);
+            }
+            injections = output.injections;
         }
         for (_, fa) in lcs.secondary {
             fa.build_stack_graph_into(
@@ -466,7 +748,72 @@ impl<'a> Indexer<'a> {
                 tsg_str: "",
             })?;
         }
-        Ok(())
+        Ok(injections)
+    }
+
+    /// Adds the globals of all language names that match the language.
+    fn add_language_globals(
+        language_globals: &HashMap<String, HashMap<String, String>>,
+        lc: &LanguageConfiguration,
+        globals: &mut Variables,
+    ) {
+        for (language_name, values) in language_globals {
+            if !lc.matches_name(language_name) {
+                continue;
+            }
+            for (name, value) in values {
+                // variables that are already set, by another matching name, are kept
+                let _ = globals.add(name.as_str().into(), value.as_str().into());
+            }
+        }
+    }
+
+    /// Formats the parse errors that were tolerated while building the stack graph of a file.
+    fn parse_error_details(
+        parse_errors: TreeWithParseErrorVec,
+        source_path: &Path,
+        source: &str,
+        sgl: &StackGraphLanguage,
+    ) -> String {
+        let err = BuildErrorWithSource {
+            inner: BuildError::ParseErrors(parse_errors),
+            source_path: source_path.to_path_buf(),
+            source_str: source,
+            tsg_path: sgl.tsg_path().to_path_buf(),
+            tsg_str: &sgl.tsg_source(),
+        };
+        err.display_pretty().to_string()
+    }
+
+    /// Reports a failure to build the stack graph of a file. Parse errors and timeouts are stored
+    /// in the database, any other error aborts indexing.
+    fn handle_build_error(
+        db: &mut SQLiteWriter,
+        source_path: &Path,
+        tag: &str,
+        err: BuildErrorWithSource<'_>,
+        file_status: &mut CLIFileReporter<'_>,
+    ) -> Result<()> {
+        match err.inner {
+            BuildError::Cancelled(_) => {
+                file_status.warning("parsing timed out", None);
+                db.store_error_for_file(source_path, tag, "parsing timed out")?;
+                Ok(())
+            }
+            BuildError::ParseErrors { .. } => {
+                file_status.failure("parsing failed", Some(&err.display_pretty()));
+                db.store_error_for_file(
+                    source_path,
+                    tag,
+                    &format!("parsing failed: {}", err.inner),
+                )?;
+                Ok(())
+            }
+            _ => {
+                file_status.failure("failed to build stack graph", Some(&err.display_pretty()));
+                Err(IndexError::StackGraph)
+            }
+        }
     }
 
     /// Determines if a path should be skipped because we have not seen the
@@ -490,6 +837,116 @@ impl<'a> Indexer<'a> {
     }
 }
 
+/// The maximum nesting depth of injected regions. Deeper regions are skipped, so that a language
+/// that injects itself cannot be built forever.
+const MAX_INJECTION_DEPTH: usize = 8;
+
+/// The database metadata key under which the git revision of the last indexing run is stored.
+const INDEXED_REVISION_KEY: &str = "indexed-revision";
+
+/// Returns the directory to run git in, which is the first source path, or its parent directory if
+/// it is a file.
+fn git_dir_for(source_paths: &[PathBuf]) -> &Path {
+    let source_path = &source_paths[0];
+    if source_path.is_file() {
+        source_path.parent().unwrap_or(source_path)
+    } else {
+        source_path
+    }
+}
+
+/// The time to wait for more changes after a change is seen, before the index is updated.
+const WATCH_DEBOUNCE_TIME: Duration = Duration::from_millis(500);
+
+/// Watch the source paths, and update the index whenever files change. Only returns if an error
+/// occurs.
+fn watch_and_index(indexer: &mut Indexer, source_paths: &[PathBuf]) -> anyhow::Result<()> {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    for source_path in source_paths {
+        let mode = if source_path.is_dir() {
+            RecursiveMode::Recursive
+        } else {
+            RecursiveMode::NonRecursive
+        };
+        watcher.watch(source_path, mode)?;
+    }
+    indexer.index_watched_changes(source_paths, &receiver)
+}
+
+/// Receive the next burst of changes, and return the changed paths. Returns `None` if the sender
+/// was disconnected before any change was received. Changes that were received before the sender
+/// was disconnected are returned, so that they are still applied.
+fn receive_changed_paths(
+    events: &Receiver<notify::Result<notify::Event>>,
+) -> anyhow::Result<Option<BTreeSet<PathBuf>>> {
+    let mut changed_paths = BTreeSet::new();
+    let mut event = match events.recv() {
+        Ok(event) => event,
+        Err(RecvError) => return Ok(None),
+    };
+    loop {
+        let event = event?;
+        if !event.kind.is_access() {
+            changed_paths.extend(event.paths);
+        }
+        event = match events.recv_timeout(WATCH_DEBOUNCE_TIME) {
+            Ok(event) => event,
+            Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => break,
+        };
+    }
+    Ok(Some(changed_paths))
+}
+
+/// Index a changed file if it is selected by the indexer's file filter. Unlike
+/// [`Indexer::index`][], files in unsupported languages are skipped silently, as they are when
+/// indexing a directory.
+fn index_changed_file(indexer: &mut Indexer, source_root: &Path, source_path: &Path) -> Result<()> {
+    if !indexer.file_filter.is_selected(source_root, source_path) {
+        return Ok(());
+    }
+    index_selected_file(indexer, source_root, source_path)
+}
+
+/// Index a file that is known to be selected by the indexer's file filter. Any error is reported
+/// for the file, as well as returned.
+fn index_selected_file(
+    indexer: &mut Indexer,
+    source_root: &Path,
+    source_path: &Path,
+) -> Result<()> {
+    let mut file_status = CLIFileReporter::new(indexer.reporter, source_path);
+    if let Err(err) = indexer.index_file(
+        source_root,
+        source_path,
+        false,
+        &mut None::<&Path>,
+        &NoCancellation,
+        &mut file_status,
+    ) {
+        file_status.failure_if_unreported("error", Some(&err));
+        return Err(err);
+    }
+    file_status.assert_reported();
+    Ok(())
+}
+
+/// Returns the source root of a path, which is the innermost source directory containing it, or
+/// the parent directory if the path is a source file itself.
+fn source_root_for(source_paths: &[PathBuf], path: &Path) -> Option<PathBuf> {
+    source_paths
+        .iter()
+        .filter(|source_path| path.starts_with(source_path))
+        .max_by_key(|source_path| source_path.components().count())
+        .map(|source_path| {
+            if source_path == path {
+                path.parent().unwrap_or(path).to_path_buf()
+            } else {
+                source_path.clone()
+            }
+        })
+}
+
 #[derive(Debug, Error)]
 pub enum IndexError {
     #[error("cancelled at {0}")]
@@ -522,6 +979,71 @@ pub struct IndexingStats {
     pub node_out_degrees: FrequencyDistribution<usize>,
     // The root node's out-degree.
     pub root_out_degree: usize,
+    // The time spent in the indexing phases.
+    pub timing_stats: TimingStats,
     // The stitching statistics.
     pub stitching_stats: StitchingStats,
+    // The matches of the stanzas per TSG file, including the time spent finding them.
+    pub stanza_stats: BTreeMap<PathBuf, StanzaCoverage>,
+}
+
+impl IndexingStats {
+    /// Records the matches of the stanzas of the language in a build of the source.
+    pub fn record_stanza_matches(
+        &mut self,
+        sgl: &StackGraphLanguage,
+        source: &str,
+        output: &BuildOutput,
+    ) -> std::result::Result<(), BuildError> {
+        self.stanza_stats
+            .entry(sgl.tsg_path().to_path_buf())
+            .or_insert_with(|| StanzaCoverage::new(sgl))
+            .add_matches(sgl, source, output)
+    }
+
+    /// Adds the stanza matches of the given statistics to these statistics.
+    pub fn absorb_stanza_stats(&mut self, other: BTreeMap<PathBuf, StanzaCoverage>) {
+        for (tsg_path, coverage) in other {
+            match self.stanza_stats.get_mut(&tsg_path) {
+                Some(total_coverage) => total_coverage.absorb(coverage),
+                None => {
+                    self.stanza_stats.insert(tsg_path, coverage);
+                }
+            }
+        }
+    }
+}
+
+/// Per-file timings of the indexing phases, in microseconds.
+#[derive(Clone, Debug, Default)]
+pub struct TimingStats {
+    // The distribution of the time spent parsing.
+    pub parsing: FrequencyDistribution<u64>,
+    // The distribution of the time spent executing the TSG rules.
+    pub execution: FrequencyDistribution<u64>,
+    // The distribution of the time spent loading the TSG graph into the stack graph.
+    pub loading: FrequencyDistribution<u64>,
+    // The distribution of the time spent computing partial paths.
+    pub path_computation: FrequencyDistribution<u64>,
+}
+
+impl TimingStats {
+    pub fn record_build(&mut self, timings: &BuildTimings) {
+        self.parsing.record(timings.parsing.as_micros() as u64);
+        self.execution.record(timings.execution.as_micros() as u64);
+        self.loading.record(timings.loading.as_micros() as u64);
+    }
+
+    pub fn record_path_computation(&mut self, time: Duration) {
+        self.path_computation.record(time.as_micros() as u64);
+    }
+}
+
+impl std::ops::AddAssign<Self> for TimingStats {
+    fn add_assign(&mut self, rhs: Self) {
+        self.parsing += rhs.parsing;
+        self.execution += rhs.execution;
+        self.loading += rhs.loading;
+        self.path_computation += rhs.path_computation;
+    }
 }
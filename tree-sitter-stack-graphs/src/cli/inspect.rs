@@ -0,0 +1,205 @@
+// -*- coding: utf-8 -*-
+// ------------------------------------------------------------------------------------------------
+// Copyright © 2023, stack-graphs authors.
+// Licensed under either of Apache License, Version 2.0, or MIT license, at your option.
+// Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
+// ------------------------------------------------------------------------------------------------
+
+use clap::Args;
+use clap::ValueEnum;
+use serde_json::json;
+use serde_json::Value;
+use stack_graphs::storage::FileStatus;
+use stack_graphs::storage::FileSummary;
+use stack_graphs::storage::SQLiteReader;
+use stack_graphs::storage::SymbolStackSummary;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::cli::util::print_file_summary_stats;
+use crate::cli::util::reporter::JsonReporter;
+use crate::cli::util::OutputFormat;
+
+/// Inspect the contents of the database, to find files and symbols that make queries slow.
+#[derive(Args)]
+pub struct InspectArgs {
+    /// Number of files, error messages, and symbol stacks to show.
+    #[clap(long, short = 'n', value_name = "COUNT", default_value_t = 10)]
+    pub limit: usize,
+
+    /// Order in which the largest files are shown.
+    #[clap(long, value_enum, default_value_t = FileOrder::TotalSize)]
+    pub order_by: FileOrder,
+
+    /// Output format. JSON output contains a single object, with `files`, `errors`, and
+    /// `symbol_stacks` fields. It contains all files and error messages, but only the symbol
+    /// stacks with the most root paths.
+    #[clap(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+}
+
+/// Order of files
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum FileOrder {
+    /// Total size of the graph and partial paths.
+    TotalSize,
+    /// Number of graph nodes.
+    GraphNodes,
+    /// Number of node partial paths.
+    NodePaths,
+    /// Number of root partial paths.
+    RootPaths,
+}
+
+impl FileOrder {
+    fn key(&self, summary: &FileSummary) -> usize {
+        match self {
+            Self::TotalSize => total_size(summary),
+            Self::GraphNodes => summary.graph_node_count,
+            Self::NodePaths => summary.node_path_count,
+            Self::RootPaths => summary.root_path_count,
+        }
+    }
+}
+
+impl InspectArgs {
+    pub fn new() -> Self {
+        Self {
+            limit: 10,
+            order_by: FileOrder::TotalSize,
+            format: OutputFormat::Text,
+        }
+    }
+
+    pub fn run(self, db_path: &Path) -> anyhow::Result<()> {
+        let mut db = SQLiteReader::open(&db_path)?;
+        let mut files = db.file_summaries()?;
+        let symbol_stacks = db.symbol_stacks_by_root_path_count(self.limit)?;
+        // files are ordered by path, which is kept for files with equal keys
+        files.sort_by_key(|file| Reverse(self.order_by.key(file)));
+        let errors = group_errors(&files);
+        match self.format {
+            OutputFormat::Text => self.print_text(&files, &errors, &symbol_stacks),
+            OutputFormat::Json => self.print_json(&files, &errors, &symbol_stacks)?,
+        }
+        Ok(())
+    }
+
+    fn print_text(
+        &self,
+        files: &[FileSummary],
+        errors: &[(&str, Vec<&Path>)],
+        symbol_stacks: &[SymbolStackSummary],
+    ) {
+        print_file_summary_stats(files);
+
+        println!();
+        println!(
+            "| {:^9} | {:^9} | {:^9} | {:^10} | {:^10} | largest files",
+            "size", "nodes", "edges", "node paths", "root paths",
+        );
+        println!("|-----------|-----------|-----------|------------|------------|--------------");
+        for file in files
+            .iter()
+            .filter(|f| !matches!(f.status, FileStatus::Error(_)))
+            .take(self.limit)
+        {
+            println!(
+                "| {:>9} | {:>9} | {:>9} | {:>10} | {:>10} | {}",
+                total_size(file),
+                file.graph_node_count,
+                file.graph_edge_count,
+                file.node_path_count,
+                file.root_path_count,
+                file.path.display(),
+            );
+        }
+
+        if !errors.is_empty() {
+            println!();
+            println!("| {:^9} | errors", "files");
+            println!("|-----------|--------------");
+            for (message, paths) in errors.iter().take(self.limit) {
+                println!("| {:>9} | {}", paths.len(), first_line(message));
+            }
+        }
+
+        println!();
+        println!("| {:^10} | symbol stacks", "root paths");
+        println!("|------------|--------------");
+        for symbol_stack in symbol_stacks {
+            println!("| {:>10} | {}", symbol_stack.root_path_count, symbol_stack);
+        }
+    }
+
+    fn print_json(
+        &self,
+        files: &[FileSummary],
+        errors: &[(&str, Vec<&Path>)],
+        symbol_stacks: &[SymbolStackSummary],
+    ) -> std::io::Result<()> {
+        let files = files
+            .iter()
+            .map(|file| {
+                json!({
+                    "path": file.path.to_string_lossy(),
+                    "failed": matches!(file.status, FileStatus::Error(_)),
+                    "graph_size": file.graph_size,
+                    "graph_nodes": file.graph_node_count,
+                    "graph_edges": file.graph_edge_count,
+                    "node_paths": file.node_path_count,
+                    "node_paths_size": file.node_paths_size,
+                    "root_paths": file.root_path_count,
+                    "root_paths_size": file.root_paths_size,
+                })
+            })
+            .collect::<Vec<_>>();
+        let errors = errors
+            .iter()
+            .map(|(message, paths)| {
+                json!({
+                    "message": message,
+                    "paths": paths.iter().map(|p| p.to_string_lossy()).collect::<Vec<_>>(),
+                })
+            })
+            .collect::<Vec<_>>();
+        let symbol_stacks = symbol_stacks
+            .iter()
+            .map(|symbol_stack| {
+                json!({
+                    "symbols": symbol_stack.symbols,
+                    "has_variable": symbol_stack.has_variable,
+                    "root_paths": symbol_stack.root_path_count,
+                })
+            })
+            .collect::<Vec<_>>();
+        JsonReporter::new().print(json!({
+            "files": Value::Array(files),
+            "errors": Value::Array(errors),
+            "symbol_stacks": Value::Array(symbol_stacks),
+        }))
+    }
+}
+
+fn total_size(summary: &FileSummary) -> usize {
+    summary.graph_size + summary.node_paths_size + summary.root_paths_size
+}
+
+/// Group failed files by error message, ordered by descending number of files.
+fn group_errors(files: &[FileSummary]) -> Vec<(&str, Vec<&Path>)> {
+    let mut errors = HashMap::<&str, Vec<&Path>>::new();
+    for file in files {
+        if let FileStatus::Error(message) = &file.status {
+            errors.entry(message.as_str()).or_default().push(&file.path);
+        }
+    }
+    let mut errors = errors.into_iter().collect::<Vec<_>>();
+    errors.sort_by(|(m1, p1), (m2, p2)| p2.len().cmp(&p1.len()).then(m1.cmp(m2)));
+    errors
+}
+
+/// Error messages can contain details on following lines, which are left out of the table.
+fn first_line(message: &str) -> &str {
+    message.lines().next().unwrap_or_default()
+}
@@ -9,12 +9,14 @@ use clap::Args;
 use std::path::PathBuf;
 use tree_sitter_config::Config as TsConfig;
 
+use crate::cli::config::LoaderConfig;
 use crate::loader::LanguageConfiguration;
 use crate::loader::LoadError;
 use crate::loader::LoadPath;
 use crate::loader::Loader;
 use crate::loader::DEFAULT_BUILTINS_PATHS;
 use crate::loader::DEFAULT_TSG_PATHS;
+use crate::NoCancellation;
 
 #[derive(Args)]
 pub struct PathLoaderArgs {
@@ -37,6 +39,15 @@ pub struct PathLoaderArgs {
     /// See https://tree-sitter.github.io/tree-sitter/syntax-highlighting#basics for details.
     #[clap(long, value_name = "SCOPE")]
     pub scope: Option<String>,
+
+    /// The path of a language plugin to load languages from, instead of looking for grammars.
+    /// Can be specified multiple times.
+    #[clap(
+        long,
+        value_name = "PLUGIN_PATH",
+        conflicts_with_all = ["tsg", "builtins", "grammar"],
+    )]
+    pub plugin: Vec<PathBuf>,
 }
 
 impl PathLoaderArgs {
@@ -46,10 +57,42 @@ impl PathLoaderArgs {
             builtins: None,
             grammar: Vec::new(),
             scope: None,
+            plugin: Vec::new(),
+        }
+    }
+
+    /// Use the values from the configuration for arguments that were not given. Plugins from the
+    /// configuration are only used if no TSG, builtins, or grammar paths were given, and vice
+    /// versa.
+    pub fn apply_config(&mut self, config: &LoaderConfig) {
+        if self.plugin.is_empty() {
+            if self.tsg.is_none() {
+                self.tsg = config.tsg.clone();
+            }
+            if self.builtins.is_none() {
+                self.builtins = config.builtins.clone();
+            }
+            if self.grammar.is_empty() {
+                self.grammar = config.grammar.clone();
+            }
+        }
+        if self.tsg.is_none()
+            && self.builtins.is_none()
+            && self.grammar.is_empty()
+            && self.plugin.is_empty()
+        {
+            self.plugin = config.plugin.clone();
+        }
+        if self.scope.is_none() {
+            self.scope = config.scope.clone();
         }
     }
 
     pub fn get(&self) -> Result<Loader, LoadError<'static>> {
+        if !self.plugin.is_empty() {
+            return Loader::from_plugins(self.plugin.clone(), self.scope.clone(), &NoCancellation);
+        }
+
         let tsg_paths = match &self.tsg {
             Some(tsg_path) => vec![LoadPath::Regular(tsg_path.clone())],
             None => DEFAULT_TSG_PATHS.clone(),
@@ -95,6 +138,13 @@ impl LanguageConfigurationsLoaderArgs {
         Self { scope: None }
     }
 
+    /// Use the scope from the configuration if no scope was given.
+    pub fn apply_config(&mut self, config: &LoaderConfig) {
+        if self.scope.is_none() {
+            self.scope = config.scope.clone();
+        }
+    }
+
     pub fn get(
         &self,
         configurations: Vec<LanguageConfiguration>,
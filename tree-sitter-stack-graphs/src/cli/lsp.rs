@@ -12,6 +12,7 @@ use crossbeam_channel::Sender;
 use stack_graphs::storage::SQLiteReader;
 use stack_graphs::storage::SQLiteWriter;
 use stack_graphs::storage::StorageError;
+use std::collections::HashMap;
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -28,12 +29,15 @@ use tower_lsp::LanguageServer;
 use tower_lsp::LspService;
 use tower_lsp::Server;
 
+use crate::cli::config::ProjectConfig;
 use crate::cli::index::Indexer;
 use crate::cli::query::Querier;
 use crate::cli::query::QueryError;
 use crate::cli::util::duration_from_milliseconds_str;
 use crate::cli::util::duration_from_seconds_str;
 use crate::cli::util::reporter::Reporter;
+use crate::cli::util::FileFilter;
+use crate::cli::util::FileFilterArgs;
 use crate::cli::util::SourcePosition;
 use crate::cli::util::SourceSpan;
 use crate::loader::Loader;
@@ -66,15 +70,51 @@ pub struct LspArgs {
         value_parser = duration_from_milliseconds_str,
     )]
     pub max_query_time: Option<Duration>,
+
+    #[clap(flatten)]
+    pub file_filter: FileFilterArgs,
+
+    /// Index files with parse errors, skipping the parts of the file that could not be parsed.
+    #[clap(long, overrides_with = "no_tolerate_parse_errors")]
+    pub tolerate_parse_errors: bool,
+
+    /// Fail to index files with parse errors, even if the project configuration tolerates them.
+    #[clap(long, overrides_with = "tolerate_parse_errors")]
+    pub no_tolerate_parse_errors: bool,
+
+    /// Global variables per language name, set from the project configuration.
+    #[clap(skip)]
+    pub language_globals: HashMap<String, HashMap<String, String>>,
 }
 
 impl LspArgs {
-    pub fn run(self, db_path: PathBuf, loader: Loader) -> anyhow::Result<()> {
+    /// Use the values from the configuration for arguments that were not given.
+    pub fn apply_config(&mut self, config: &ProjectConfig) {
+        self.file_filter.apply_config(&config.files);
+        if self.max_folder_index_time.is_none() {
+            self.max_folder_index_time = config.lsp.max_folder_index_time.map(Duration::from_secs);
+        }
+        if self.max_file_index_time.is_none() {
+            self.max_file_index_time = config.lsp.max_file_index_time.map(Duration::from_secs);
+        }
+        if self.max_query_time.is_none() {
+            self.max_query_time = config.lsp.max_query_time.map(Duration::from_millis);
+        }
+        if !self.no_tolerate_parse_errors {
+            self.tolerate_parse_errors |= config.lsp.tolerate_parse_errors;
+        }
+        self.language_globals = config.language_globals();
+    }
+
+    pub fn run(self, db_path: PathBuf, mut loader: Loader) -> anyhow::Result<()> {
+        loader.set_error_tolerant(self.tolerate_parse_errors);
+        let file_filter = self.file_filter.get()?;
         let rt = tokio::runtime::Runtime::new()?;
         rt.block_on(async {
             let (service, socket) = LspService::new(|client| Backend {
                 _client: client.clone(),
                 db_path,
+                file_filter,
                 args: self,
                 loader: Arc::new(std::sync::Mutex::new(loader)),
                 jobs: Arc::new(tokio::sync::Mutex::new(None)),
@@ -108,6 +148,10 @@ impl std::fmt::Display for LspArgs {
         if let Some(max_query_time) = self.max_query_time {
             write!(f, " --max-query-time {}", max_query_time.as_millis())?;
         }
+        if self.tolerate_parse_errors {
+            write!(f, " --tolerate-parse-errors")?;
+        }
+        write!(f, "{}", self.file_filter)?;
         Ok(())
     }
 }
@@ -117,6 +161,7 @@ struct Backend {
     _client: Client,
     db_path: PathBuf,
     loader: Arc<std::sync::Mutex<Loader>>,
+    file_filter: FileFilter,
     args: LspArgs,
     jobs: Arc<tokio::sync::Mutex<Option<(Sender<Job>, AtomicCancellationFlag)>>>,
     logger: BackendLogger,
@@ -215,6 +260,8 @@ impl Backend {
         let cancellation_flag = cancellation_flag | folder_cancellation_flag.as_ref();
         let mut indexer = Indexer::new(&mut db, &mut loader, &reporter);
         indexer.max_file_time = self.args.max_file_index_time;
+        indexer.file_filter = self.file_filter.clone();
+        indexer.language_globals = self.args.language_globals.clone();
         let result = indexer.index_all(vec![path], None::<&Path>, &cancellation_flag);
 
         handle.block_on(capture!([logger = &self.logger, path], async move {
@@ -5,13 +5,21 @@
 // Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
 // ------------------------------------------------------------------------------------------------
 
+use std::collections::BTreeMap;
+use std::collections::HashSet;
 use std::path::Path;
 use std::path::PathBuf;
 
+use anyhow::anyhow;
 use clap::Args;
 use clap::Parser;
 use clap::Subcommand;
 use clap::ValueHint;
+use serde_json::json;
+use serde_json::Value;
+use stack_graphs::arena::Handle;
+use stack_graphs::graph::Node;
+use stack_graphs::partial::PartialPath;
 use stack_graphs::stitching::ForwardPartialPathStitcher;
 use stack_graphs::stitching::Stats as StitchingStats;
 use stack_graphs::stitching::StitcherConfig;
@@ -23,9 +31,11 @@ use tree_sitter_graph::parse_error::Excerpt;
 use crate::cli::util::print_database_stats;
 use crate::cli::util::print_stitching_stats;
 use crate::cli::util::reporter::ConsoleReporter;
+use crate::cli::util::reporter::JsonReporter;
 use crate::cli::util::reporter::Reporter;
 use crate::cli::util::sha1;
 use crate::cli::util::wait_for_input;
+use crate::cli::util::OutputFormat;
 use crate::cli::util::SourcePosition;
 use crate::cli::util::SourceSpan;
 use crate::loader::FileReader;
@@ -41,17 +51,25 @@ pub struct QueryArgs {
     #[clap(long)]
     pub stats: bool,
 
+    /// Output format. JSON output contains the file statuses, and one object per result, with
+    /// `source` and `targets` spans.
+    #[clap(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+
     #[clap(subcommand)]
     target: Target,
 }
 
 impl QueryArgs {
     pub fn run(self, db_path: &Path) -> anyhow::Result<()> {
+        if self.stats && self.format == OutputFormat::Json {
+            return Err(anyhow!("Statistics are not supported in JSON output"));
+        }
         if self.wait_at_start {
             wait_for_input()?;
         }
         let mut db = SQLiteReader::open(&db_path)?;
-        let stitching_stats = self.target.run(&mut db, self.stats)?;
+        let stitching_stats = self.target.run(&mut db, self.stats, self.format)?;
         if self.stats {
             println!();
             print_stitching_stats(stitching_stats);
@@ -68,12 +86,20 @@ pub enum Target {
 }
 
 impl Target {
-    fn run(self, db: &mut SQLiteReader, collect_stats: bool) -> anyhow::Result<StitchingStats> {
-        let reporter = ConsoleReporter::details();
-        let mut querier = Querier::new(db, &reporter);
+    fn run(
+        self,
+        db: &mut SQLiteReader,
+        collect_stats: bool,
+        format: OutputFormat,
+    ) -> anyhow::Result<StitchingStats> {
+        let reporter = format.reporter(ConsoleReporter::details());
+        let mut querier = Querier::new(db, &*reporter);
         querier.set_collect_stats(collect_stats);
         match self {
-            Self::Definition(cmd) => cmd.run(&mut querier)?,
+            Self::Definition(cmd) => match format {
+                OutputFormat::Text => cmd.run(&mut querier)?,
+                OutputFormat::Json => cmd.run_json(&mut querier)?,
+            },
         }
         Ok(querier.into_stats())
     }
@@ -92,6 +118,20 @@ pub struct Definition {
 }
 
 impl Definition {
+    /// Run the queries, and output the results as JSON objects, one per line.
+    pub fn run_json(self, querier: &mut Querier) -> anyhow::Result<()> {
+        let cancellation_flag = NoCancellation;
+        let json_reporter = JsonReporter::new();
+        for mut reference in self.references {
+            reference.canonicalize()?;
+            let results = querier.definitions(reference, &cancellation_flag)?;
+            for result in results {
+                json_reporter.print(result.to_json())?;
+            }
+        }
+        Ok(())
+    }
+
     pub fn run(self, querier: &mut Querier) -> anyhow::Result<()> {
         let cancellation_flag = NoCancellation;
         let mut file_reader = FileReader::new();
@@ -180,18 +220,8 @@ impl<'a> Querier<'a> {
     ) -> Result<Vec<QueryResult>> {
         let log_path = PathBuf::from(reference.to_string());
 
-        let mut file_reader = FileReader::new();
-        let tag = file_reader.get(&reference.path).ok().map(sha1);
-        match self
-            .db
-            .status_for_file(&reference.path.to_string_lossy(), tag.as_ref())?
-        {
-            FileStatus::Indexed => {}
-            _ => {
-                self.reporter.started(&log_path);
-                self.reporter.failed(&log_path, "file not indexed", None);
-                return Ok(Default::default());
-            }
+        if !self.is_indexed(&reference, &log_path)? {
+            return Ok(Default::default());
         }
 
         self.reporter.started(&log_path);
@@ -290,6 +320,179 @@ impl<'a> Querier<'a> {
         Ok(result)
     }
 
+    /// Find the references to the definitions at the given position. All indexed files are
+    /// searched for references, which requires loading all their graphs.
+    pub fn references(
+        &mut self,
+        definition: SourcePosition,
+        cancellation_flag: &dyn CancellationFlag,
+    ) -> Result<Vec<QueryResult>> {
+        let log_path = PathBuf::from(definition.to_string());
+
+        if !self.is_indexed(&definition, &log_path)? {
+            return Ok(Default::default());
+        }
+
+        self.reporter.started(&log_path);
+
+        self.db
+            .load_graph_for_file(&definition.path.to_string_lossy())?;
+        let (graph, _, _) = self.db.get();
+
+        let definitions = definition.iter_definitions(graph).collect::<Vec<_>>();
+        if definitions.is_empty() {
+            self.reporter
+                .cancelled(&log_path, "no definitions at location", None);
+            return Ok(Default::default());
+        }
+        let symbols = definitions
+            .iter()
+            .filter_map(|(node, _)| graph[*node].symbol())
+            .collect::<HashSet<_>>();
+
+        if let Err(err) = self.load_all_indexed_files(cancellation_flag) {
+            self.reporter
+                .failed(&log_path, "loading files failed", None);
+            return Err(err);
+        }
+        let (graph, _, _) = self.db.get();
+
+        // only references with the same symbol as one of the definitions can resolve to them
+        let candidates = graph
+            .iter_nodes()
+            .filter(|node| {
+                graph[*node].is_reference()
+                    && graph[*node]
+                        .symbol()
+                        .map_or(false, |s| symbols.contains(&s))
+                    && graph.source_info(*node).is_some()
+            })
+            .collect::<Vec<_>>();
+
+        let mut reference_paths = BTreeMap::<Handle<Node>, Vec<PartialPath>>::new();
+        let stitcher_config = StitcherConfig::default()
+            // always detect similar paths, we don't know the language configurations for the data in the database
+            .with_detect_similar_paths(true)
+            .with_collect_stats(self.stats.is_some());
+        let ref_result = ForwardPartialPathStitcher::find_all_complete_partial_paths(
+            self.db,
+            candidates.iter().cloned(),
+            stitcher_config,
+            &cancellation_flag,
+            |_g, _ps, p| {
+                reference_paths
+                    .entry(p.start_node)
+                    .or_default()
+                    .push(p.clone());
+            },
+        );
+        match ref_result {
+            Ok(ref_stats) => {
+                if let Some(stats) = &mut self.stats {
+                    *stats += ref_stats
+                }
+            }
+            Err(err) => {
+                self.reporter.failed(&log_path, "query timed out", None);
+                return Err(err.into());
+            }
+        }
+
+        let (graph, partials, _) = self.db.get();
+        let mut references = BTreeMap::<Handle<Node>, Vec<SourceSpan>>::new();
+        for (reference, paths) in &reference_paths {
+            if let Err(err) = cancellation_flag.check("shadowing") {
+                self.reporter.failed(&log_path, "query timed out", None);
+                return Err(err.into());
+            }
+            for path in paths {
+                if !definitions.iter().any(|(node, _)| *node == path.end_node)
+                    || paths.iter().any(|other| other.shadows(partials, path))
+                {
+                    continue;
+                }
+                let span = match graph.source_info(*reference) {
+                    Some(p) => p.span.clone(),
+                    None => continue,
+                };
+                let file_path = match graph[*reference].id().file() {
+                    Some(f) => PathBuf::from(graph[f].name()),
+                    None => continue,
+                };
+                references
+                    .entry(path.end_node)
+                    .or_default()
+                    .push(SourceSpan {
+                        path: file_path,
+                        span,
+                    });
+            }
+        }
+
+        let result = definitions
+            .into_iter()
+            .map(|(node, span)| QueryResult {
+                source: SourceSpan {
+                    path: definition.path.clone(),
+                    span,
+                },
+                targets: references.remove(&node).unwrap_or_default(),
+            })
+            .collect::<Vec<_>>();
+
+        let count: usize = result.iter().map(|r| r.targets.len()).sum();
+        self.reporter.succeeded(
+            &log_path,
+            &format!(
+                "found {} references for {} definitions",
+                count,
+                result.len()
+            ),
+            None,
+        );
+
+        Ok(result)
+    }
+
+    /// Checks that the file of the position is indexed and up-to-date, and reports a failure if
+    /// it is not.
+    fn is_indexed(&mut self, position: &SourcePosition, log_path: &Path) -> Result<bool> {
+        let mut file_reader = FileReader::new();
+        let tag = file_reader.get(&position.path).ok().map(sha1);
+        match self
+            .db
+            .status_for_file(&position.path.to_string_lossy(), tag.as_ref())?
+        {
+            FileStatus::Indexed => Ok(true),
+            _ => {
+                self.reporter.started(log_path);
+                self.reporter.failed(log_path, "file not indexed", None);
+                Ok(false)
+            }
+        }
+    }
+
+    /// Load the graphs of all indexed files. Files that are already loaded are not loaded again.
+    pub fn load_all_indexed_files(
+        &mut self,
+        cancellation_flag: &dyn CancellationFlag,
+    ) -> Result<()> {
+        let mut paths = Vec::new();
+        let mut files = self.db.list_all()?;
+        for entry in files.try_iter()? {
+            let entry = entry?;
+            if let FileStatus::Indexed = entry.status {
+                paths.push(entry.path);
+            }
+        }
+        drop(files);
+        for path in paths {
+            cancellation_flag.check("loading files")?;
+            self.db.load_graph_for_file(&path.to_string_lossy())?;
+        }
+        Ok(())
+    }
+
     pub fn into_stats(self) -> StitchingStats {
         self.stats.unwrap_or_default()
     }
@@ -322,4 +525,13 @@ pub struct QueryResult {
     pub targets: Vec<SourceSpan>,
 }
 
+impl QueryResult {
+    pub(crate) fn to_json(&self) -> Value {
+        json!({
+            "source": self.source.to_json(),
+            "targets": self.targets.iter().map(SourceSpan::to_json).collect::<Vec<_>>(),
+        })
+    }
+}
+
 type Result<T> = std::result::Result<T, QueryError>;
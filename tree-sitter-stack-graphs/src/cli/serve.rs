@@ -0,0 +1,430 @@
+// -*- coding: utf-8 -*-
+// ------------------------------------------------------------------------------------------------
+// Copyright © 2023, stack-graphs authors.
+// Licensed under either of Apache License, Version 2.0, or MIT license, at your option.
+// Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
+// ------------------------------------------------------------------------------------------------
+
+//! Defines a local query server, which answers queries against a database without reopening it
+//! for every query.
+//!
+//! The server speaks JSON-RPC 2.0, either over HTTP on localhost, where every request is the body
+//! of a `POST` request, or over standard input and output, with one message per line. Requests
+//! are handled concurrently, each worker keeping its own database reader open. Queries that need
+//! the graphs of all indexed files, `references` and `symbols`, share a single reader between the
+//! workers, so that those graphs are only loaded once. Graphs loaded by a reader are kept for
+//! later requests, so the server should be restarted after the database is updated.
+//!
+//! All lines and columns are 1-based, and columns count graphemes. The supported methods are:
+//!
+//! - `definition`, with params `{"path", "line", "column"}`, returns the definitions of the
+//!   references at the position, as a list of `{"source", "targets"}` results.
+//! - `references`, with params `{"path", "line", "column"}`, returns the references to the
+//!   definitions at the position, in all indexed files, as a list of `{"source", "targets"}`
+//!   results.
+//! - `file-status`, with params `{"path"}`, returns the status of the file, or of all files in the
+//!   directory, as a list of `{"path", "tag", "status", "error"}` entries.
+//! - `symbols`, with params `{"name"}` and an optional `"path"`, returns all definitions with the
+//!   given name, optionally restricted to the file or directory, as a list of
+//!   `{"symbol", "span", "syntax_type", "fully_qualified_name", "documentation"}` entries.
+//!
+//! Spans are returned as `{"path", "start": {"line", "column"}, "end": {"line", "column"}}`.
+
+use anyhow::anyhow;
+use clap::Args;
+use serde::Deserialize;
+use serde_json::json;
+use serde_json::Value;
+use stack_graphs::storage::SQLiteReader;
+use stack_graphs::storage::StorageError;
+use std::io::BufRead;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use thiserror::Error;
+use tiny_http::Header;
+use tiny_http::Method;
+use tiny_http::Response as HttpResponse;
+use tiny_http::Server;
+
+use crate::cli::query::Querier;
+use crate::cli::query::QueryError;
+use crate::cli::query::QueryResult;
+use crate::cli::status::file_entry_to_json;
+use crate::cli::util::duration_from_milliseconds_str;
+use crate::cli::util::reporter::ConsoleReporter;
+use crate::cli::util::SourcePosition;
+use crate::cli::util::SourceSpan;
+use crate::CancelAfterDuration;
+use crate::CancellationFlag;
+
+#[derive(Args, Clone)]
+pub struct ServeArgs {
+    /// Serve JSON-RPC over HTTP on the given localhost port, instead of over standard input and
+    /// output.
+    #[clap(long, value_name = "PORT")]
+    pub port: Option<u16>,
+
+    /// Number of requests to handle at the same time. Defaults to the number of available cores.
+    #[clap(long, short = 'j', value_name = "JOBS")]
+    pub jobs: Option<usize>,
+
+    /// Maximum query runtime in milliseconds.
+    #[clap(
+        long,
+        value_name = "MILLISECONDS",
+        value_parser = duration_from_milliseconds_str,
+    )]
+    pub max_query_time: Option<Duration>,
+}
+
+impl ServeArgs {
+    pub fn new() -> Self {
+        Self {
+            port: None,
+            jobs: None,
+            max_query_time: None,
+        }
+    }
+
+    pub fn run(self, db_path: &Path) -> anyhow::Result<()> {
+        let jobs = self
+            .jobs
+            .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |jobs| jobs.get()));
+        let workers = Worker::open_all(db_path, jobs, self.max_query_time)?;
+        match self.port {
+            Some(port) => serve_http(port, workers),
+            None => serve_stdio(workers),
+        }
+    }
+}
+
+/// Serve requests sent as the body of HTTP `POST` requests. Only returns if an error occurs.
+fn serve_http(port: u16, workers: Vec<Worker>) -> anyhow::Result<()> {
+    let server = Server::http(("127.0.0.1", port))
+        .map_err(|e| anyhow!("Cannot listen on port {}: {}", port, e))?;
+    eprintln!("Listening on http://{}", server.server_addr());
+    std::thread::scope(|s| {
+        let handles = workers
+            .into_iter()
+            .map(|mut worker| {
+                let server = &server;
+                s.spawn(move || -> anyhow::Result<()> {
+                    loop {
+                        let request = server.recv()?;
+                        worker.handle_http(request);
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            handle.join().expect("worker panicked")?;
+        }
+        Ok(())
+    })
+}
+
+/// Serve requests read from standard input, one per line, and write responses to standard output,
+/// one per line. Responses are written in the order they are finished, which may differ from the
+/// order of the requests. Returns when standard input is closed and all requests are handled.
+fn serve_stdio(workers: Vec<Worker>) -> anyhow::Result<()> {
+    let (sender, receiver) = channel::<String>();
+    let receiver = Mutex::new(receiver);
+    let stdout = std::io::stdout();
+    std::thread::scope(|s| {
+        let handles = workers
+            .into_iter()
+            .map(|mut worker| {
+                let receiver = &receiver;
+                let stdout = &stdout;
+                s.spawn(move || -> anyhow::Result<()> {
+                    loop {
+                        let message = match receiver.lock().unwrap().recv() {
+                            Ok(message) => message,
+                            Err(_) => return Ok(()),
+                        };
+                        if let Some(response) = worker.handle_message(&message) {
+                            let mut stdout = stdout.lock();
+                            serde_json::to_writer(&mut stdout, &response)?;
+                            writeln!(stdout)?;
+                            stdout.flush()?;
+                        }
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for line in std::io::stdin().lock().lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            sender.send(line)?;
+        }
+        drop(sender);
+        for handle in handles {
+            handle.join().expect("worker panicked")?;
+        }
+        Ok(())
+    })
+}
+
+/// A worker handles one request at a time, using its own database reader for queries on single
+/// files, and a reader shared with the other workers for queries on all indexed files.
+pub struct Worker {
+    db: SQLiteReader,
+    shared_db: Arc<Mutex<SQLiteReader>>,
+    max_query_time: Option<Duration>,
+    reporter: ConsoleReporter,
+}
+
+impl Worker {
+    /// Opens the given number of workers for the database, but at least one. All readers are
+    /// opened up front, so that an unusable database is reported immediately.
+    pub fn open_all(
+        db_path: &Path,
+        jobs: usize,
+        max_query_time: Option<Duration>,
+    ) -> anyhow::Result<Vec<Self>> {
+        let shared_db = Arc::new(Mutex::new(SQLiteReader::open(db_path)?));
+        (0..jobs.max(1))
+            .map(|_| {
+                Ok(Self {
+                    db: SQLiteReader::open(db_path)?,
+                    shared_db: shared_db.clone(),
+                    max_query_time,
+                    reporter: ConsoleReporter::none(),
+                })
+            })
+            .collect()
+    }
+
+    fn handle_http(&mut self, mut request: tiny_http::Request) {
+        let response = if request.method() != &Method::Post {
+            HttpResponse::from_string("expected a POST request").with_status_code(405)
+        } else {
+            let mut message = String::new();
+            let response = match request.as_reader().read_to_string(&mut message) {
+                Ok(_) => self.handle_message(&message),
+                Err(err) => Some(error_response(
+                    Value::Null,
+                    &ServerError::Parse(err.to_string()),
+                )),
+            };
+            match response {
+                Some(response) => HttpResponse::from_string(response.to_string()).with_header(
+                    "Content-Type: application/json"
+                        .parse::<Header>()
+                        .expect("valid header"),
+                ),
+                None => HttpResponse::from_string("").with_status_code(204),
+            }
+        };
+        // the client may have gone away, there is nobody left to report errors to
+        let _ = request.respond(response);
+    }
+
+    /// Handle a JSON-RPC message, and return the response. Notifications, which are requests
+    /// without an id, are handled but get no response.
+    pub fn handle_message(&mut self, message: &str) -> Option<Value> {
+        let request = match serde_json::from_str::<Request>(message) {
+            Ok(request) => request,
+            Err(err) => {
+                return Some(error_response(
+                    Value::Null,
+                    &ServerError::Parse(err.to_string()),
+                ))
+            }
+        };
+        let result = self.handle_request(&request.method, request.params);
+        let id = request.id?;
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(err) => error_response(id, &err),
+        })
+    }
+
+    fn handle_request(&mut self, method: &str, params: Value) -> Result<Value, ServerError> {
+        let cancellation_flag = CancelAfterDuration::from_option(self.max_query_time);
+        match method {
+            "definition" => {
+                let position = parse_params::<PositionParams>(params)?.into_source_position()?;
+                let mut querier = Querier::new(&mut self.db, &self.reporter);
+                let results = querier.definitions(position, cancellation_flag.as_ref())?;
+                Ok(results_to_json(&results))
+            }
+            "references" => {
+                let position = parse_params::<PositionParams>(params)?.into_source_position()?;
+                let mut shared_db = self.shared_db.lock().unwrap();
+                let mut querier = Querier::new(&mut shared_db, &self.reporter);
+                let results = querier.references(position, cancellation_flag.as_ref())?;
+                Ok(results_to_json(&results))
+            }
+            "file-status" => {
+                let params = parse_params::<PathParams>(params)?;
+                self.file_status(&canonicalize(&params.path)?)
+            }
+            "symbols" => {
+                let params = parse_params::<SymbolsParams>(params)?;
+                let path = params.path.as_deref().map(canonicalize).transpose()?;
+                self.symbols(&params.name, path.as_deref(), cancellation_flag.as_ref())
+            }
+            _ => Err(ServerError::MethodNotFound(method.to_string())),
+        }
+    }
+
+    fn file_status(&mut self, path: &Path) -> Result<Value, ServerError> {
+        let mut files = self.db.list_file_or_directory(path)?;
+        let mut entries = Vec::new();
+        for entry in files.try_iter()? {
+            entries.push(file_entry_to_json(&entry?));
+        }
+        Ok(Value::Array(entries))
+    }
+
+    fn symbols(
+        &mut self,
+        name: &str,
+        path: Option<&Path>,
+        cancellation_flag: &dyn CancellationFlag,
+    ) -> Result<Value, ServerError> {
+        let mut shared_db = self.shared_db.lock().unwrap();
+        Querier::new(&mut shared_db, &self.reporter).load_all_indexed_files(cancellation_flag)?;
+        let (graph, _, _) = shared_db.get();
+        let mut definitions = Vec::new();
+        for node in graph.iter_nodes() {
+            if !graph[node].is_definition() {
+                continue;
+            }
+            match graph[node].symbol() {
+                Some(symbol) if &graph[symbol] == name => {}
+                _ => continue,
+            }
+            let file_path = match graph[node].id().file() {
+                Some(file) => PathBuf::from(graph[file].name()),
+                None => continue,
+            };
+            if path.map_or(false, |path| !file_path.starts_with(path)) {
+                continue;
+            }
+            let source_info = match graph.source_info(node) {
+                Some(source_info) => source_info,
+                None => continue,
+            };
+            let span = SourceSpan {
+                path: file_path,
+                span: source_info.span.clone(),
+            };
+            let syntax_type = source_info.syntax_type.into_option().map(|s| &graph[s]);
+            let fully_qualified_name = source_info
+                .fully_qualified_name
+                .into_option()
+                .map(|s| &graph[s]);
+            let documentation = source_info.documentation.into_option().map(|s| &graph[s]);
+            definitions.push(json!({
+                "symbol": name,
+                "span": span.to_json(),
+                "syntax_type": syntax_type,
+                "fully_qualified_name": fully_qualified_name,
+                "documentation": documentation,
+            }));
+        }
+        Ok(Value::Array(definitions))
+    }
+}
+
+fn results_to_json(results: &[QueryResult]) -> Value {
+    Value::Array(results.iter().map(QueryResult::to_json).collect())
+}
+
+fn error_response(id: Value, err: &ServerError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": {
+            "code": err.code(),
+            "message": err.to_string(),
+        },
+    })
+}
+
+fn parse_params<T: serde::de::DeserializeOwned>(params: Value) -> Result<T, ServerError> {
+    serde_json::from_value(params).map_err(|e| ServerError::InvalidParams(e.to_string()))
+}
+
+fn canonicalize(path: &Path) -> Result<PathBuf, ServerError> {
+    path.canonicalize()
+        .map_err(|e| ServerError::InvalidParams(format!("{}: {}", path.display(), e)))
+}
+
+#[derive(Deserialize)]
+struct Request {
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Deserialize)]
+struct PositionParams {
+    path: PathBuf,
+    line: usize,
+    column: usize,
+}
+
+impl PositionParams {
+    fn into_source_position(self) -> Result<SourcePosition, ServerError> {
+        if self.line == 0 || self.column == 0 {
+            return Err(ServerError::InvalidParams(
+                "lines and columns are 1-based".to_string(),
+            ));
+        }
+        Ok(SourcePosition {
+            path: canonicalize(&self.path)?,
+            line: self.line - 1,
+            column: self.column - 1,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct PathParams {
+    path: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct SymbolsParams {
+    name: String,
+    path: Option<PathBuf>,
+}
+
+#[derive(Debug, Error)]
+enum ServerError {
+    #[error("parse error: {0}")]
+    Parse(String),
+    #[error("invalid params: {0}")]
+    InvalidParams(String),
+    #[error("method not found: {0}")]
+    MethodNotFound(String),
+    #[error(transparent)]
+    Query(#[from] QueryError),
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+}
+
+impl ServerError {
+    /// Returns the JSON-RPC error code for this error.
+    fn code(&self) -> i64 {
+        match self {
+            Self::Parse(_) => -32700,
+            Self::InvalidParams(_) => -32602,
+            Self::MethodNotFound(_) => -32601,
+            Self::Query(QueryError::Cancelled(_)) => -32001,
+            Self::Query(_) | Self::Storage(_) => -32000,
+        }
+    }
+}
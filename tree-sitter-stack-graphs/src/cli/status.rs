@@ -8,6 +8,8 @@
 use clap::ArgGroup;
 use clap::Args;
 use clap::ValueHint;
+use serde_json::json;
+use serde_json::Value;
 use stack_graphs::storage::FileEntry;
 use stack_graphs::storage::FileStatus;
 use stack_graphs::storage::SQLiteReader;
@@ -15,7 +17,9 @@ use std::path::Path;
 use std::path::PathBuf;
 
 use crate::cli::util::reporter::ConsoleReporter;
+use crate::cli::util::reporter::JsonReporter;
 use crate::cli::util::reporter::Reporter;
+use crate::cli::util::OutputFormat;
 
 #[derive(Args)]
 #[clap(group(
@@ -37,6 +41,11 @@ pub struct StatusArgs {
 
     #[clap(long, short = 'v')]
     pub verbose: bool,
+
+    /// Output format. JSON output contains one object per file, with `path`, `tag`, `status`,
+    /// and `error` fields.
+    #[clap(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
 }
 
 impl StatusArgs {
@@ -71,6 +80,13 @@ impl StatusArgs {
         entries: &mut impl Iterator<Item = stack_graphs::storage::Result<FileEntry>>,
         reporter: &dyn Reporter,
     ) -> anyhow::Result<()> {
+        if self.format == OutputFormat::Json {
+            let json_reporter = JsonReporter::new();
+            for entry in entries {
+                json_reporter.print(file_entry_to_json(&entry?))?;
+            }
+            return Ok(());
+        }
         for entry in entries {
             let entry = entry?;
             reporter.started(&entry.path);
@@ -89,3 +105,19 @@ impl StatusArgs {
         Ok(())
     }
 }
+
+/// Returns a JSON representation of a file entry, with `path`, `tag`, `status`, and `error`
+/// fields. The status is one of `missing`, `indexed`, or `error`.
+pub(crate) fn file_entry_to_json(entry: &FileEntry) -> Value {
+    let (status, error) = match &entry.status {
+        FileStatus::Missing => ("missing", None),
+        FileStatus::Indexed => ("indexed", None),
+        FileStatus::Error(error) => ("error", Some(error)),
+    };
+    json!({
+        "path": entry.path.to_string_lossy(),
+        "tag": entry.tag,
+        "status": status,
+        "error": error,
+    })
+}
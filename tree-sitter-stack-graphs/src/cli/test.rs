@@ -19,27 +19,50 @@ use stack_graphs::stitching::Database;
 use stack_graphs::stitching::DatabaseCandidates;
 use stack_graphs::stitching::ForwardPartialPathStitcher;
 use stack_graphs::stitching::StitcherConfig;
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::io::Write;
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
 use std::time::Duration;
+use std::time::Instant;
 use tree_sitter_graph::Variables;
 
+use crate::cli::index::IndexingStats;
 use crate::cli::util::duration_from_seconds_str;
 use crate::cli::util::iter_files_and_directories;
+use crate::cli::util::print_stanza_stats;
+use crate::cli::util::print_stitching_stats;
+use crate::cli::util::print_timing_stats;
+use crate::cli::util::reporter::BufferedReporter;
 use crate::cli::util::reporter::ConsoleReporter;
 use crate::cli::util::reporter::Level;
+use crate::cli::util::reporter::Reporter;
 use crate::cli::util::CLIFileReporter;
 use crate::cli::util::ExistingPathBufValueParser;
 use crate::cli::util::PathSpec;
+use crate::coverage::StanzaCoverage;
 use crate::loader::ContentProvider;
 use crate::loader::FileReader;
 use crate::loader::LanguageConfiguration;
 use crate::loader::Loader;
+use crate::test::ResolutionSnapshot;
 use crate::test::Test;
 use crate::test::TestResult;
 use crate::CancelAfterDuration;
 use crate::CancellationFlag;
 
+use self::report::ReportSpec;
+use self::report::TestFileReport;
+use self::report::TestFileStatus;
+use self::report::TestReport;
+
+pub mod report;
+
 #[derive(Args)]
 #[clap(after_help = r#"PATH SPECIFICATIONS:
     Output filenames can be specified using placeholders based on the input file.
@@ -58,6 +81,23 @@ use crate::CancellationFlag;
     Note that on Windows the path specification must be valid Unicode, but all valid
     paths (including ones that are not valid Unicode) are accepted as arguments, and
     placeholders are correctly subtituted for all paths.
+
+REPORTS:
+    Machine-readable reports of the test results can be written using --report,
+    which takes an argument of the form FORMAT=PATH. Supported formats are json
+    and junit. The flag can be given multiple times to write multiple reports.
+
+STANZA COVERAGE:
+    With --coverage, the test command records which stanzas of the TSG files matched
+    the test files, and how many stack graph nodes each stanza created. The coverage
+    is written as text, or as an LCOV tracefile using --coverage=lcov. Coverage is
+    written to the console, unless a file is given with --coverage-output.
+
+RESOLUTION SNAPSHOTS:
+    If a test file has a snapshot file next to it, named after the test file with
+    an additional .snapshot extension, the resolution of every reference in the test
+    is compared against the snapshot, in addition to the assertions in the test file.
+    Use --update-snapshots to create or update snapshot files.
 "#)]
 pub struct TestArgs {
     /// Test file or directory paths. Files or files inside directories ending in .skip are excluded.
@@ -139,6 +179,38 @@ pub struct TestArgs {
         value_parser = duration_from_seconds_str,
     )]
     pub max_test_time: Option<Duration>,
+
+    /// Record the resolution of every reference in a snapshot file next to the test file,
+    /// instead of comparing against existing snapshot files.
+    #[clap(long)]
+    pub update_snapshots: bool,
+
+    /// Write a machine-readable report of the test results.
+    #[clap(long, value_name = "FORMAT=PATH")]
+    pub report: Vec<ReportSpec>,
+
+    /// Record and show which TSG stanzas are exercised by the tests.
+    #[clap(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "text"
+    )]
+    pub coverage: Option<CoverageFormat>,
+
+    /// Write stanza coverage to the given file instead of the console.
+    #[clap(long, value_name = "PATH", requires = "coverage")]
+    pub coverage_output: Option<PathBuf>,
+
+    /// Show timing and stitching statistics of the tests.
+    #[clap(long)]
+    pub stats: bool,
+
+    /// Number of test files to run in parallel. Defaults to the number of available cores.
+    #[clap(long, short = 'j', value_name = "JOBS")]
+    pub jobs: Option<usize>,
 }
 
 /// Flag to control output
@@ -148,6 +220,13 @@ pub enum OutputMode {
     OnFailure,
 }
 
+/// Format of stanza coverage output
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum CoverageFormat {
+    Text,
+    Lcov,
+}
+
 impl OutputMode {
     fn test(&self, failure: bool) -> bool {
         match self {
@@ -170,25 +249,187 @@ impl TestArgs {
             output_mode: OutputMode::OnFailure,
             no_builtins: false,
             max_test_time: None,
+            update_snapshots: false,
+            report: Vec::new(),
+            coverage: None,
+            coverage_output: None,
+            stats: false,
+            jobs: None,
         }
     }
 
-    pub fn run(self, mut loader: Loader) -> anyhow::Result<()> {
+    pub fn run(self, loader: Loader) -> anyhow::Result<()> {
         let reporter = self.get_reporter();
+        self.run_with_reporter(loader, &reporter)
+    }
+
+    /// Run the tests, and report the status of every test file to the given reporter. Statuses
+    /// are reported in the order of the test files, even if tests are run in parallel.
+    pub fn run_with_reporter(
+        self,
+        mut loader: Loader,
+        reporter: &dyn Reporter,
+    ) -> anyhow::Result<()> {
+        let test_files = iter_files_and_directories(self.test_paths.clone())
+            .map(|(test_root, test_path, _)| (test_root, test_path))
+            .collect::<Vec<_>>();
+
+        // Languages are loaded before running any tests, because the loader cannot be shared
+        // between threads. Loading stops at the first error, which is reported when that test
+        // file is run, just as if tests were run one after another.
+        let mut language_indices = Vec::with_capacity(test_files.len());
+        for (_, test_path) in &test_files {
+            let language_index = self.load_language_for_test(test_path, &mut loader);
+            let is_err = language_index.is_err();
+            language_indices.push(language_index);
+            if is_err {
+                break;
+            }
+        }
+        let languages = loader
+            .iter_loaded_language_configurations()
+            .collect::<Vec<_>>();
+        let tests = test_files
+            .iter()
+            .zip(language_indices)
+            .map(|((test_root, test_path), language_index)| {
+                let lc = language_index.map(|i| i.map(|i| languages[i]));
+                (test_root.as_path(), test_path.as_path(), lc)
+            })
+            .collect::<Vec<_>>();
+
         let mut total_result = TestResult::new();
-        for (test_root, test_path, _) in iter_files_and_directories(self.test_paths.clone()) {
-            let mut file_status = CLIFileReporter::new(&reporter, &test_path);
-            let test_result =
-                self.run_test(&test_root, &test_path, &mut loader, &mut file_status)?;
-            file_status.assert_reported();
-            total_result.absorb(test_result);
+        let mut report = TestReport::new();
+        let mut coverage = BTreeMap::<PathBuf, StanzaCoverage>::new();
+        let mut stats = IndexingStats::default();
+        let mut error = None;
+        let mut add_outcome =
+            |mut file_report: TestFileReport, test_result: anyhow::Result<TestResult>| -> bool {
+                if let Some(file_coverage) = file_report.coverage.take() {
+                    match coverage.get_mut(file_coverage.tsg_path()) {
+                        Some(total_coverage) => total_coverage.absorb(file_coverage),
+                        None => {
+                            coverage.insert(file_coverage.tsg_path().to_path_buf(), file_coverage);
+                        }
+                    }
+                }
+                if let Some(file_stats) = file_report.stats.take() {
+                    stats.timing_stats += file_stats.timing_stats;
+                    stats.stitching_stats += file_stats.stitching_stats;
+                    stats.absorb_stanza_stats(file_stats.stanza_stats);
+                }
+                report.add(file_report);
+                match test_result {
+                    Ok(test_result) => {
+                        total_result.absorb(test_result);
+                        true
+                    }
+                    Err(err) => {
+                        error = Some(err);
+                        false
+                    }
+                }
+            };
+
+        let jobs = self
+            .jobs
+            .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |jobs| jobs.get()));
+        if jobs <= 1 || tests.len() <= 1 {
+            for (test_root, test_path, lc) in tests {
+                let (file_report, test_result) =
+                    self.run_test_file(reporter, test_root, test_path, lc);
+                if !add_outcome(file_report, test_result) {
+                    break;
+                }
+            }
+        } else {
+            self.run_test_files_in_parallel(jobs, reporter, tests, add_outcome);
+        }
+        if self.stats {
+            println!();
+            print_timing_stats(stats.timing_stats);
+            println!();
+            print_stanza_stats(stats.stanza_stats.values());
+            println!();
+            print_stitching_stats(stats.stitching_stats);
+        }
+
+        self.write_reports(&report)?;
+        if let Some(err) = error {
+            return Err(err);
         }
+        self.write_coverage(coverage.values())?;
         if total_result.failure_count() > 0 {
             return Err(anyhow!(total_result.to_string()));
         }
         Ok(())
     }
 
+    /// Run test files on the given number of threads. Test outcomes are reported in the order
+    /// of the given tests, and no more outcomes are reported once `add_outcome` returns false.
+    fn run_test_files_in_parallel<'a>(
+        &self,
+        jobs: usize,
+        reporter: &dyn Reporter,
+        tests: Vec<(
+            &'a Path,
+            &'a Path,
+            anyhow::Result<Option<&'a LanguageConfiguration>>,
+        )>,
+        mut add_outcome: impl FnMut(TestFileReport, anyhow::Result<TestResult>) -> bool,
+    ) {
+        let test_count = tests.len();
+        let tests = tests.into_iter().map(Mutex::new).collect::<Vec<_>>();
+        let next_test = AtomicUsize::new(0);
+        let stopped = AtomicBool::new(false);
+        std::thread::scope(|scope| {
+            let (sender, receiver) = std::sync::mpsc::channel();
+            for _ in 0..jobs.min(test_count) {
+                let sender = sender.clone();
+                let (tests, next_test, stopped) = (&tests, &next_test, &stopped);
+                scope.spawn(move || loop {
+                    let index = next_test.fetch_add(1, Ordering::SeqCst);
+                    if index >= test_count || stopped.load(Ordering::SeqCst) {
+                        return;
+                    }
+                    let (test_root, test_path, lc) = {
+                        let mut test = tests[index].lock().unwrap();
+                        let lc = std::mem::replace(&mut test.2, Ok(None));
+                        (test.0, test.1, lc)
+                    };
+                    let file_reporter = BufferedReporter::new();
+                    let (file_report, test_result) =
+                        self.run_test_file(&file_reporter, test_root, test_path, lc);
+                    if sender
+                        .send((index, file_reporter, file_report, test_result))
+                        .is_err()
+                    {
+                        return;
+                    }
+                });
+            }
+            drop(sender);
+
+            // Outcomes arrive in completion order, and are buffered until all preceding
+            // outcomes have been reported.
+            let mut pending = BTreeMap::new();
+            let mut next_outcome = 0;
+            for (index, file_reporter, file_report, test_result) in receiver {
+                pending.insert(index, (file_reporter, file_report, test_result));
+                while let Some((file_reporter, file_report, test_result)) =
+                    pending.remove(&next_outcome)
+                {
+                    next_outcome += 1;
+                    file_reporter.replay(reporter);
+                    if !add_outcome(file_report, test_result) {
+                        stopped.store(true, Ordering::SeqCst);
+                        return;
+                    }
+                }
+            }
+        });
+    }
+
     fn get_reporter(&self) -> ConsoleReporter {
         return ConsoleReporter {
             skipped_level: if self.show_skipped {
@@ -210,19 +451,104 @@ impl TestArgs {
         };
     }
 
+    fn write_reports(&self, report: &TestReport) -> anyhow::Result<()> {
+        for spec in &self.report {
+            spec.write(report)?;
+        }
+        Ok(())
+    }
+
+    fn write_coverage<'c>(
+        &self,
+        coverage: impl Iterator<Item = &'c StanzaCoverage>,
+    ) -> anyhow::Result<()> {
+        let format = match self.coverage {
+            Some(format) => format,
+            None => return Ok(()),
+        };
+        let mut output: Box<dyn std::io::Write> = match &self.coverage_output {
+            Some(path) => {
+                if let Some(dir) = path.parent() {
+                    std::fs::create_dir_all(dir)?;
+                }
+                Box::new(std::io::BufWriter::new(std::fs::File::create(path)?))
+            }
+            None => Box::new(std::io::stdout()),
+        };
+        for coverage in coverage {
+            match format {
+                CoverageFormat::Text => coverage.write_text(&mut output)?,
+                CoverageFormat::Lcov => coverage.write_lcov(&mut output)?,
+            }
+        }
+        output.flush()?;
+        Ok(())
+    }
+
+    /// Load the language for the given test file. Returns the index of the language configuration
+    /// in [`Loader::iter_loaded_language_configurations`], or `None` if the test file is not
+    /// supported by any language.
+    fn load_language_for_test(
+        &self,
+        test_path: &Path,
+        loader: &mut Loader,
+    ) -> anyhow::Result<Option<usize>> {
+        let cancellation_flag = CancelAfterDuration::from_option(self.max_test_time);
+        let load_path = Self::load_path(test_path);
+        let mut file_reader = MappingFileReader::new(&load_path, test_path);
+        Ok(loader.load_index_for_file(&load_path, &mut file_reader, cancellation_flag.as_ref())?)
+    }
+
+    /// If the file is skipped (ending in .skip) we construct the non-skipped path to see if we would support it.
+    fn load_path(test_path: &Path) -> PathBuf {
+        if test_path.extension().map_or(false, |e| e == "skip") {
+            test_path.with_extension("")
+        } else {
+            test_path.to_path_buf()
+        }
+    }
+
+    /// Run test file, reporting its status to the given reporter.
+    fn run_test_file(
+        &self,
+        reporter: &dyn Reporter,
+        test_root: &Path,
+        test_path: &Path,
+        lc: anyhow::Result<Option<&LanguageConfiguration>>,
+    ) -> (TestFileReport, anyhow::Result<TestResult>) {
+        let mut file_status = CLIFileReporter::new(reporter, test_path);
+        let mut file_report = TestFileReport::new(test_path);
+        let start_time = Instant::now();
+        let test_result = match lc {
+            Ok(Some(lc)) => {
+                self.run_test(test_root, test_path, lc, &mut file_status, &mut file_report)
+            }
+            Ok(None) => Ok(TestResult::new()),
+            Err(err) => {
+                file_report.status = TestFileStatus::Error(format!("{:#}", err));
+                Err(err)
+            }
+        };
+        file_report.duration = start_time.elapsed();
+        file_status.assert_reported();
+        (file_report, test_result)
+    }
+
     /// Run test file. Takes care of the output when an error is returned.
     fn run_test(
         &self,
         test_root: &Path,
         test_path: &Path,
-        loader: &mut Loader,
+        lc: &LanguageConfiguration,
         file_status: &mut CLIFileReporter,
+        file_report: &mut TestFileReport,
     ) -> anyhow::Result<TestResult> {
-        match self.run_test_inner(test_root, test_path, loader, file_status) {
-            ok @ Ok(_) => ok,
-            err @ Err(_) => {
+        match self.run_test_inner(test_root, test_path, lc, file_status, file_report) {
+            Ok(test_result) => Ok(test_result),
+            Err(err) => {
                 file_status.failure_if_processing("error", None);
-                err
+                file_report.status = TestFileStatus::Error(format!("{:#}", err));
+                Err(err)
             }
         }
     }
@@ -231,26 +557,12 @@ impl TestArgs {
         &self,
         test_root: &Path,
         test_path: &Path,
-        loader: &mut Loader,
+        lc: &LanguageConfiguration,
         file_status: &mut CLIFileReporter,
+        file_report: &mut TestFileReport,
     ) -> anyhow::Result<TestResult> {
         let cancellation_flag = CancelAfterDuration::from_option(self.max_test_time);
 
-        // If the file is skipped (ending in .skip) we construct the non-skipped path to see if we would support it.
-        let load_path = if test_path.extension().map_or(false, |e| e == "skip") {
-            test_path.with_extension("")
-        } else {
-            test_path.to_path_buf()
-        };
-        let mut file_reader = MappingFileReader::new(&load_path, test_path);
-        let lc = match loader
-            .load_for_file(&load_path, &mut file_reader, cancellation_flag.as_ref())?
-            .primary
-        {
-            Some(lc) => lc,
-            None => return Ok(TestResult::new()),
-        };
-
         if test_path.components().any(|c| match c {
             std::path::Component::Normal(name) => (name.as_ref() as &Path)
                 .extension()
@@ -258,18 +570,23 @@ impl TestArgs {
             _ => false,
         }) {
             file_status.skipped("skipped", None);
+            file_report.status = TestFileStatus::Skipped;
             return Ok(TestResult::new());
         }
 
         file_status.processing();
 
+        let mut file_reader = FileReader::new();
         let source = file_reader.get(test_path)?;
         let default_fragment_path = test_path.strip_prefix(test_root).unwrap();
         let mut test = Test::from_source(test_path, source, default_fragment_path)?;
+        file_report.fragments = test.fragments.iter().map(|f| f.path.clone()).collect();
         if !self.no_builtins {
             self.load_builtins_into(&lc, &mut test.graph)?;
         }
         let mut globals = Variables::new();
+        let mut coverage = self.coverage.map(|_| StanzaCoverage::new(&lc.sgl));
+        let mut stats = self.stats.then(IndexingStats::default);
         for test_fragment in &test.fragments {
             let result = if let Some(fa) = test_fragment
                 .path
@@ -292,13 +609,22 @@ impl TestArgs {
             )? {
                 globals.clear();
                 test_fragment.add_globals_to(&mut globals);
-                lc.sgl.build_stack_graph_into(
+                let result = lc.sgl.build_stack_graph_with_output_into(
                     &mut test.graph,
                     test_fragment.file,
                     &test_fragment.source,
                     &globals,
                     cancellation_flag.as_ref(),
-                )
+                );
+                if let (Some(stats), Ok(output)) = (&mut stats, &result) {
+                    stats.timing_stats.record_build(&output.timings);
+                    stats.record_stanza_matches(&lc.sgl, &test_fragment.source, output)?;
+                }
+                if let (Some(coverage), Ok(output)) = (&mut coverage, &result) {
+                    coverage.add_matches(&lc.sgl, &test_fragment.source, output)?;
+                    coverage.add_nodes(&test.graph, test_fragment.file);
+                }
+                result.map(|_| ())
             } else {
                 return Err(anyhow!(
                     "Test fragment {} not supported by language of test file {}",
@@ -325,28 +651,60 @@ impl TestArgs {
                 Ok(_) => {}
             }
         }
+        file_report.coverage = coverage;
         let stitcher_config =
             StitcherConfig::default().with_detect_similar_paths(!lc.no_similar_paths_in_file);
         let mut partials = PartialPaths::new();
         let mut db = Database::new();
+        let path_stitcher_config = stitcher_config.with_collect_stats(stats.is_some());
         for file in test.graph.iter_files() {
-            ForwardPartialPathStitcher::find_minimal_partial_path_set_in_file(
-                &test.graph,
-                &mut partials,
-                file,
-                stitcher_config,
-                &cancellation_flag.as_ref(),
-                |g, ps, p| {
-                    db.add_partial_path(g, ps, p.clone());
-                },
-            )?;
+            let path_computation_start = Instant::now();
+            let stitching_stats =
+                ForwardPartialPathStitcher::find_minimal_partial_path_set_in_file(
+                    &test.graph,
+                    &mut partials,
+                    file,
+                    path_stitcher_config,
+                    &cancellation_flag.as_ref(),
+                    |g, ps, p| {
+                        db.add_partial_path(g, ps, p.clone());
+                    },
+                )?;
+            if let Some(stats) = &mut stats {
+                stats
+                    .timing_stats
+                    .record_path_computation(path_computation_start.elapsed());
+                stats.stitching_stats += stitching_stats;
+            }
         }
-        let result = test.run(
+        file_report.stats = stats;
+        let mut result = test.run(
             &mut partials,
             &mut db,
             stitcher_config,
             cancellation_flag.as_ref(),
         )?;
+        let snapshot_path = Self::snapshot_path(test_path);
+        if self.update_snapshots {
+            let snapshot = test.resolution_snapshot(
+                &mut partials,
+                &mut db,
+                stitcher_config,
+                cancellation_flag.as_ref(),
+            )?;
+            std::fs::write(&snapshot_path, snapshot.to_string())?;
+        } else if snapshot_path.exists() {
+            let expected =
+                std::fs::read_to_string(&snapshot_path)?.parse::<ResolutionSnapshot>()?;
+            let actual = test.resolution_snapshot(
+                &mut partials,
+                &mut db,
+                stitcher_config,
+                cancellation_flag.as_ref(),
+            )?;
+            result.absorb(test.check_snapshot(&expected, &actual));
+        }
+        file_report.status = TestFileStatus::Ran(result.clone());
         let success = result.failure_count() == 0;
         let outputs = if self.output_mode.test(!success) {
             let files = test.fragments.iter().map(|f| f.file).collect::<Vec<_>>();
@@ -387,6 +745,13 @@ impl TestArgs {
         Ok(result)
     }
 
+    /// Returns the path of the resolution snapshot for the given test file.
+    fn snapshot_path(test_path: &Path) -> PathBuf {
+        let mut path = OsString::from(test_path.as_os_str());
+        path.push(".snapshot");
+        PathBuf::from(path)
+    }
+
     fn load_builtins_into(
         &self,
         lc: &LanguageConfiguration,
@@ -0,0 +1,330 @@
+// -*- coding: utf-8 -*-
+// ------------------------------------------------------------------------------------------------
+// Copyright © 2023, stack-graphs authors.
+// Licensed under either of Apache License, Version 2.0, or MIT license, at your option.
+// Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
+// ------------------------------------------------------------------------------------------------
+
+//! Machine-readable reports of test runs.
+
+use anyhow::anyhow;
+use serde_json::json;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::cli::index::IndexingStats;
+use crate::coverage::StanzaCoverage;
+use crate::test::TestResult;
+
+/// Format of a test report.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportFormat {
+    Json,
+    Junit,
+}
+
+/// A report specification of the form `FORMAT=PATH`.
+#[derive(Clone, Debug)]
+pub struct ReportSpec {
+    pub format: ReportFormat,
+    pub path: PathBuf,
+}
+
+impl std::str::FromStr for ReportSpec {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (format, path) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Expected report in format FORMAT=PATH, got {}", s))?;
+        let format = match format {
+            "json" => ReportFormat::Json,
+            "junit" => ReportFormat::Junit,
+            _ => {
+                return Err(anyhow!(
+                    "Unsupported report format {}, expected json or junit",
+                    format
+                ))
+            }
+        };
+        if path.is_empty() {
+            return Err(anyhow!("Missing path in expected format FORMAT=PATH"));
+        }
+        Ok(Self {
+            format,
+            path: PathBuf::from(path),
+        })
+    }
+}
+
+impl ReportSpec {
+    /// Write the report to the path of this specification.
+    pub fn write(&self, report: &TestReport) -> anyhow::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut file = std::io::BufWriter::new(std::fs::File::create(&self.path)?);
+        match self.format {
+            ReportFormat::Json => report.write_json(&mut file)?,
+            ReportFormat::Junit => report.write_junit(&mut file)?,
+        }
+        file.flush()?;
+        Ok(())
+    }
+}
+
+/// Status of a test file.
+#[derive(Clone, Debug)]
+pub enum TestFileStatus {
+    /// The file is not supported by any of the languages, and is not part of the report.
+    Unsupported,
+    Skipped,
+    Ran(TestResult),
+    Error(String),
+}
+
+/// Report of running a single test file.
+#[derive(Clone, Debug)]
+pub struct TestFileReport {
+    pub path: PathBuf,
+    pub fragments: Vec<PathBuf>,
+    pub status: TestFileStatus,
+    pub duration: Duration,
+    /// Stanza coverage of the TSG file used for this test file, if coverage was requested.
+    pub coverage: Option<StanzaCoverage>,
+    /// Timing and stitching statistics of this test file, if statistics were requested.
+    pub stats: Option<IndexingStats>,
+}
+
+impl TestFileReport {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            fragments: Vec::new(),
+            status: TestFileStatus::Unsupported,
+            duration: Duration::default(),
+            coverage: None,
+            stats: None,
+        }
+    }
+}
+
+/// Report of a complete test run.
+#[derive(Clone, Debug, Default)]
+pub struct TestReport {
+    files: Vec<TestFileReport>,
+}
+
+impl TestReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, file: TestFileReport) {
+        if let TestFileStatus::Unsupported = file.status {
+            return;
+        }
+        self.files.push(file);
+    }
+
+    /// Write the report as a JSON document.
+    pub fn write_json<W: Write>(&self, w: &mut W) -> anyhow::Result<()> {
+        let files = self
+            .files
+            .iter()
+            .map(|file| {
+                let (status, error, assertions) = match &file.status {
+                    TestFileStatus::Unsupported => unreachable!(),
+                    TestFileStatus::Skipped => ("skipped", None, Vec::new()),
+                    TestFileStatus::Error(error) => ("error", Some(error.clone()), Vec::new()),
+                    TestFileStatus::Ran(result) => {
+                        let assertions = result
+                            .assertion_results_iter()
+                            .map(|a| {
+                                let status = if a.is_success() { "passed" } else { "failed" };
+                                json!({
+                                    "path": a.path.to_string_lossy(),
+                                    "line": a.position.line + 1,
+                                    "column": a.position.column.grapheme_offset + 1,
+                                    "kind": a.kind,
+                                    "status": status,
+                                    "failure": a.failure.as_ref().map(|f| f.to_string()),
+                                })
+                            })
+                            .collect::<Vec<_>>();
+                        let status = if result.failure_count() == 0 {
+                            "passed"
+                        } else {
+                            "failed"
+                        };
+                        (status, None, assertions)
+                    }
+                };
+                json!({
+                    "path": file.path.to_string_lossy(),
+                    "fragments": file
+                        .fragments
+                        .iter()
+                        .map(|f| f.to_string_lossy())
+                        .collect::<Vec<_>>(),
+                    "status": status,
+                    "error": error,
+                    "time": file.duration.as_secs_f64(),
+                    "assertions": assertions,
+                })
+            })
+            .collect::<Vec<_>>();
+        serde_json::to_writer_pretty(&mut *w, &json!({ "files": files }))?;
+        writeln!(w)?;
+        Ok(())
+    }
+
+    /// Write the report in the JUnit XML format. Every test file is reported as a test suite,
+    /// and every assertion as a test case.
+    pub fn write_junit<W: Write>(&self, w: &mut W) -> anyhow::Result<()> {
+        let mut total = Counts::default();
+        for file in &self.files {
+            total.add(&Counts::for_file(file));
+        }
+        writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            w,
+            r#"<testsuites name="stack-graphs" tests="{}" failures="{}" errors="{}" skipped="{}">"#,
+            total.tests, total.failures, total.errors, total.skipped,
+        )?;
+        for file in &self.files {
+            let counts = Counts::for_file(file);
+            let name = escape_xml(&file.path.to_string_lossy());
+            writeln!(
+                w,
+                r#"  <testsuite name="{}" tests="{}" failures="{}" errors="{}" skipped="{}" time="{:.3}">"#,
+                name,
+                counts.tests,
+                counts.failures,
+                counts.errors,
+                counts.skipped,
+                file.duration.as_secs_f64(),
+            )?;
+            if !file.fragments.is_empty() {
+                writeln!(w, "    <properties>")?;
+                for fragment in &file.fragments {
+                    writeln!(
+                        w,
+                        r#"      <property name="fragment" value="{}"/>"#,
+                        escape_xml(&fragment.to_string_lossy())
+                    )?;
+                }
+                writeln!(w, "    </properties>")?;
+            }
+            match &file.status {
+                TestFileStatus::Unsupported => unreachable!(),
+                TestFileStatus::Skipped => {
+                    writeln!(w, r#"    <testcase name="{}" classname="{}">"#, name, name)?;
+                    writeln!(w, "      <skipped/>")?;
+                    writeln!(w, "    </testcase>")?;
+                }
+                TestFileStatus::Error(error) => {
+                    writeln!(w, r#"    <testcase name="{}" classname="{}">"#, name, name)?;
+                    writeln!(
+                        w,
+                        r#"      <error message="{}">{}</error>"#,
+                        escape_xml(error.lines().next().unwrap_or("")),
+                        escape_xml(error)
+                    )?;
+                    writeln!(w, "    </testcase>")?;
+                }
+                TestFileStatus::Ran(result) => {
+                    for assertion in result.assertion_results_iter() {
+                        let case_name = escape_xml(&assertion.to_string());
+                        match &assertion.failure {
+                            None => writeln!(
+                                w,
+                                r#"    <testcase name="{}" classname="{}"/>"#,
+                                case_name, name
+                            )?,
+                            Some(failure) => {
+                                let failure = failure.to_string();
+                                writeln!(
+                                    w,
+                                    r#"    <testcase name="{}" classname="{}">"#,
+                                    case_name, name
+                                )?;
+                                writeln!(
+                                    w,
+                                    r#"      <failure message="{}">{}</failure>"#,
+                                    escape_xml(failure.lines().next().unwrap_or("")),
+                                    escape_xml(&failure)
+                                )?;
+                                writeln!(w, "    </testcase>")?;
+                            }
+                        }
+                    }
+                }
+            }
+            writeln!(w, "  </testsuite>")?;
+        }
+        writeln!(w, "</testsuites>")?;
+        Ok(())
+    }
+}
+
+/// Test case counts, as used by the JUnit format.
+#[derive(Default)]
+struct Counts {
+    tests: usize,
+    failures: usize,
+    errors: usize,
+    skipped: usize,
+}
+
+impl Counts {
+    fn for_file(file: &TestFileReport) -> Self {
+        match &file.status {
+            TestFileStatus::Unsupported => Self::default(),
+            TestFileStatus::Skipped => Self {
+                tests: 1,
+                skipped: 1,
+                ..Self::default()
+            },
+            TestFileStatus::Error(_) => Self {
+                tests: 1,
+                errors: 1,
+                ..Self::default()
+            },
+            TestFileStatus::Ran(result) => Self {
+                tests: result.count(),
+                failures: result.failure_count(),
+                ..Self::default()
+            },
+        }
+    }
+
+    fn add(&mut self, other: &Counts) {
+        self.tests += other.tests;
+        self.failures += other.failures;
+        self.errors += other.errors;
+        self.skipped += other.skipped;
+    }
+}
+
+/// Escape a value for use in XML text and attributes. Characters that are not allowed in XML 1.0,
+/// such as the escape character of terminal color codes, are replaced by U+FFFD, because they
+/// cannot be represented even when escaped.
+fn escape_xml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            '\u{0}'..='\u{1f}' | '\u{fffe}' | '\u{ffff}' => escaped.push('\u{fffd}'),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -12,7 +12,15 @@ use clap::builder::TypedValueParser;
 use clap::error::ContextKind;
 use clap::error::ContextValue;
 use clap::error::ErrorKind;
+use clap::Args;
+use clap::ValueEnum;
+use globset::Glob;
+use globset::GlobSet;
+use globset::GlobSetBuilder;
+use ignore::WalkBuilder;
 use lsp_positions::Span;
+use serde_json::json;
+use serde_json::Value;
 use sha1::Digest;
 use sha1::Sha1;
 use stack_graphs::arena::Handle;
@@ -20,6 +28,8 @@ use stack_graphs::graph::Node;
 use stack_graphs::graph::StackGraph;
 use stack_graphs::stats::FrequencyDistribution;
 use stack_graphs::stitching::Stats as StitchingStats;
+use stack_graphs::storage::FileStatus;
+use stack_graphs::storage::FileSummary;
 use stack_graphs::storage::Stats as StorageStats;
 use std::ffi::OsStr;
 use std::ffi::OsString;
@@ -30,11 +40,16 @@ use std::ops::Range;
 use std::path::Path;
 use std::path::PathBuf;
 use std::time::Duration;
-use walkdir::WalkDir;
 
+use crate::cli::config::FilesConfig;
 use crate::cli::index::IndexingStats;
+use crate::cli::index::TimingStats;
+use crate::cli::util::reporter::ConsoleReporter;
+use crate::cli::util::reporter::JsonReporter;
 use crate::cli::util::reporter::Reporter;
+use crate::coverage::StanzaCoverage;
 
+pub mod git;
 pub mod reporter;
 
 #[derive(Clone)]
@@ -192,13 +207,28 @@ impl SourcePosition {
     pub fn iter_references<'a>(
         &'a self,
         graph: &'a StackGraph,
+    ) -> impl Iterator<Item = (Handle<Node>, Span)> + 'a {
+        self.iter_nodes(graph, Node::is_reference)
+    }
+
+    pub fn iter_definitions<'a>(
+        &'a self,
+        graph: &'a StackGraph,
+    ) -> impl Iterator<Item = (Handle<Node>, Span)> + 'a {
+        self.iter_nodes(graph, Node::is_definition)
+    }
+
+    fn iter_nodes<'a>(
+        &'a self,
+        graph: &'a StackGraph,
+        include: fn(&Node) -> bool,
     ) -> impl Iterator<Item = (Handle<Node>, Span)> + 'a {
         graph
             .get_file(&self.path.to_string_lossy())
             .into_iter()
             .flat_map(move |file| {
                 graph.nodes_for_file(file).filter_map(move |node| {
-                    if !graph[node].is_reference() {
+                    if !include(&graph[node]) {
                         return None;
                     }
                     let source_info = match graph.source_info(node) {
@@ -304,6 +334,22 @@ pub struct SourceSpan {
 }
 
 impl SourceSpan {
+    /// Returns a JSON representation of this span. Lines and columns are 1-based, and columns
+    /// count graphemes.
+    pub(crate) fn to_json(&self) -> Value {
+        json!({
+            "path": self.path.to_string_lossy(),
+            "start": {
+                "line": self.span.start.line + 1,
+                "column": self.span.start.column.grapheme_offset + 1,
+            },
+            "end": {
+                "line": self.span.end.line + 1,
+                "column": self.span.end.column.grapheme_offset + 1,
+            },
+        })
+    }
+
     pub(crate) fn first_line(&self) -> usize {
         self.span.start.line
     }
@@ -326,7 +372,7 @@ pub(crate) fn duration_from_seconds_str(s: &str) -> Result<Duration, anyhow::Err
     Ok(Duration::new(seconds, 0))
 }
 
-#[cfg(feature = "lsp")]
+#[cfg(any(feature = "lsp", feature = "server"))]
 pub(crate) fn duration_from_milliseconds_str(s: &str) -> Result<Duration, anyhow::Error> {
     let milliseconds = s.parse::<u64>()?;
     let seconds = milliseconds / 1000;
@@ -334,9 +380,234 @@ pub(crate) fn duration_from_milliseconds_str(s: &str) -> Result<Duration, anyhow
     Ok(Duration::new(seconds, nano_seconds))
 }
 
+/// Output format of CLI commands.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable text.
+    Text,
+    /// One JSON object per line.
+    Json,
+}
+
+impl OutputFormat {
+    /// Returns a reporter for this format, which is the given console reporter for text output.
+    /// For JSON output, colored output is disabled, so that the reported details do not contain
+    /// terminal escape codes.
+    pub fn reporter(self, console_reporter: ConsoleReporter) -> Box<dyn Reporter> {
+        match self {
+            Self::Text => Box::new(console_reporter),
+            Self::Json => {
+                colored::control::set_override(false);
+                Box::new(JsonReporter::new())
+            }
+        }
+    }
+}
+
+/// CLI arguments for selecting the files in source directories.
+#[derive(Args, Clone)]
+pub struct FileFilterArgs {
+    /// Only select files matching the glob, relative to the source directory.
+    /// Can be specified multiple times.
+    #[clap(long, value_name = "GLOB")]
+    pub include: Vec<String>,
+
+    /// Skip files and directories matching the glob, relative to the source directory.
+    /// Can be specified multiple times.
+    #[clap(long, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+
+    /// Do not skip files ignored by .gitignore and .ignore files.
+    #[clap(long, overrides_with = "ignore")]
+    pub no_ignore: bool,
+
+    /// Skip files ignored by .gitignore and .ignore files, even if the project configuration
+    /// disables ignore files.
+    #[clap(long, overrides_with = "no_ignore")]
+    pub ignore: bool,
+}
+
+impl FileFilterArgs {
+    pub fn new() -> Self {
+        Self {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            no_ignore: false,
+            ignore: false,
+        }
+    }
+
+    /// Use the values from the configuration for arguments that were not given.
+    pub fn apply_config(&mut self, config: &FilesConfig) {
+        if self.include.is_empty() {
+            self.include = config.include.clone();
+        }
+        if self.exclude.is_empty() {
+            self.exclude = config.exclude.clone();
+        }
+        if !self.ignore {
+            self.no_ignore |= config.no_ignore;
+        }
+    }
+
+    pub fn get(&self) -> anyhow::Result<FileFilter> {
+        FileFilter::new(&self.include, &self.exclude, !self.no_ignore)
+    }
+}
+
+impl std::fmt::Display for FileFilterArgs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for include in &self.include {
+            write!(f, " --include {}", include)?;
+        }
+        for exclude in &self.exclude {
+            write!(f, " --exclude {}", exclude)?;
+        }
+        if self.no_ignore {
+            write!(f, " --no-ignore")?;
+        }
+        if self.ignore {
+            write!(f, " --ignore")?;
+        }
+        Ok(())
+    }
+}
+
+/// Selects the files in source directories. Globs are matched against paths relative to the
+/// source directory. Excluded directories are not visited at all.
+#[derive(Clone)]
+pub struct FileFilter {
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+    use_ignore_files: bool,
+}
+
+impl FileFilter {
+    pub fn new(
+        include: &[String],
+        exclude: &[String],
+        use_ignore_files: bool,
+    ) -> anyhow::Result<Self> {
+        let include = if include.is_empty() {
+            None
+        } else {
+            Some(Self::build_glob_set(include)?)
+        };
+        let exclude = Self::build_glob_set(exclude)?;
+        Ok(Self {
+            include,
+            exclude,
+            use_ignore_files,
+        })
+    }
+
+    /// A filter that selects all files.
+    pub fn all() -> Self {
+        Self {
+            include: None,
+            exclude: GlobSet::empty(),
+            use_ignore_files: false,
+        }
+    }
+
+    fn build_glob_set(globs: &[String]) -> anyhow::Result<GlobSet> {
+        let mut builder = GlobSetBuilder::new();
+        for glob in globs {
+            builder.add(Glob::new(glob).map_err(|e| anyhow!("invalid glob {}: {}", glob, e))?);
+        }
+        Ok(builder.build()?)
+    }
+
+    /// Returns whether the path, which must be a file in the source directory, is selected by
+    /// this filter. This uses the same walk as for the source directory, but only visits the
+    /// path and its ancestors, which is cheaper if only a few paths need to be checked.
+    pub fn is_selected(&self, source_root: &Path, path: &Path) -> bool {
+        let relative_path = match path.strip_prefix(source_root) {
+            Ok(relative_path) => relative_path,
+            Err(_) => return false,
+        };
+        if !self.is_included(relative_path) {
+            return false;
+        }
+        self.walk(source_root, Some(path))
+            .filter_map(|e| e.ok())
+            .any(|e| e.path() == path)
+    }
+
+    /// Returns a walk of the source directory, which skips excluded and ignored files and
+    /// directories. Ignore files are applied in the same way as by git, including the global and
+    /// repository excludes, and .gitignore files are only used inside a git repository. If a
+    /// target is given, only the target, its ancestors, and its descendants are visited.
+    fn walk(&self, source_root: &Path, target: Option<&Path>) -> ignore::Walk {
+        let filter = self.clone();
+        let root = source_root.to_path_buf();
+        let target = target.map(Path::to_path_buf);
+        WalkBuilder::new(source_root)
+            .standard_filters(self.use_ignore_files)
+            .hidden(false)
+            .follow_links(true)
+            .sort_by_file_name(|a, b| a.cmp(b))
+            .filter_entry(move |e| {
+                if let Some(target) = &target {
+                    if !target.starts_with(e.path()) && !e.path().starts_with(target) {
+                        return false;
+                    }
+                }
+                e.path()
+                    .strip_prefix(&root)
+                    .map_or(true, |p| !filter.is_excluded(p))
+            })
+            .build()
+    }
+
+    /// Iterates over the files in the directory, which must be in the source directory, that are
+    /// selected by this filter. Globs are matched relative to the source directory, not the given
+    /// directory, so the result is the same as for the files in a walk of the source directory.
+    pub fn iter_selected_files_in<'a>(
+        &'a self,
+        source_root: &'a Path,
+        directory: &Path,
+    ) -> impl Iterator<Item = PathBuf> + 'a {
+        self.walk(source_root, Some(directory))
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().map_or(false, |t| t.is_file()))
+            .filter(move |e| {
+                e.path()
+                    .strip_prefix(source_root)
+                    .map_or(false, |p| self.is_included(p))
+            })
+            .map(|e| e.into_path())
+    }
+
+    fn is_excluded(&self, relative_path: &Path) -> bool {
+        // repository metadata is not ignored by any ignore file, but should never be indexed
+        (self.use_ignore_files && relative_path.file_name() == Some(OsStr::new(".git")))
+            || self.exclude.is_match(relative_path)
+    }
+
+    fn is_included(&self, relative_path: &Path) -> bool {
+        self.include
+            .as_ref()
+            .map_or(true, |include| include.is_match(relative_path))
+    }
+}
+
 pub fn iter_files_and_directories<'a, P, IP>(
     paths: IP,
 ) -> impl Iterator<Item = (PathBuf, PathBuf, bool)> + 'a
+where
+    P: AsRef<Path> + 'a,
+    IP: IntoIterator<Item = P> + 'a,
+{
+    iter_filtered_files_and_directories(paths, FileFilter::all())
+}
+
+/// Iterates over the given files, and the files in the given directories that are selected by
+/// the filter. Files that are given explicitly are never filtered.
+pub fn iter_filtered_files_and_directories<'a, P, IP>(
+    paths: IP,
+    filter: FileFilter,
+) -> impl Iterator<Item = (PathBuf, PathBuf, bool)> + 'a
 where
     P: AsRef<Path> + 'a,
     IP: IntoIterator<Item = P> + 'a,
@@ -344,16 +615,21 @@ where
     paths
         .into_iter()
         .filter_map(
-            |source_path| -> Option<Box<dyn Iterator<Item = (PathBuf, PathBuf, bool)>>> {
+            move |source_path| -> Option<Box<dyn Iterator<Item = (PathBuf, PathBuf, bool)>>> {
                 if source_path.as_ref().is_dir() {
-                    let source_root = source_path;
-                    let paths = WalkDir::new(&source_root)
-                        .follow_links(true)
-                        .sort_by_file_name()
-                        .into_iter()
+                    let source_root = source_path.as_ref().to_path_buf();
+                    let file_filter = filter.clone();
+                    let file_root = source_root.clone();
+                    let paths = filter
+                        .walk(&source_root, None)
                         .filter_map(|e| e.ok())
-                        .filter(|e| e.file_type().is_file())
-                        .map(move |e| (source_root.as_ref().to_path_buf(), e.into_path(), false));
+                        .filter(|e| e.file_type().map_or(false, |t| t.is_file()))
+                        .filter(move |e| {
+                            e.path()
+                                .strip_prefix(&file_root)
+                                .map_or(true, |p| file_filter.is_included(p))
+                        })
+                        .map(move |e| (source_root.clone(), e.into_path(), false));
                     Some(Box::new(paths))
                 } else {
                     let source_root = source_path
@@ -449,6 +725,19 @@ impl<'a> CLIFileReporter<'a> {
         self.failure(status, details);
     }
 
+    /// Reports a failure if no status was reported yet, for example because an error occurred
+    /// before processing started.
+    pub(super) fn failure_if_unreported(
+        &mut self,
+        status: &str,
+        details: Option<&dyn std::fmt::Display>,
+    ) {
+        if self.status_logged {
+            return;
+        }
+        self.failure(status, details);
+    }
+
     pub(super) fn assert_reported(&mut self) {
         if self.path_logged && !self.status_logged {
             panic!("status not reported");
@@ -509,9 +798,51 @@ pub(super) fn print_indexing_stats(stats: IndexingStats) {
     print_quartiles_row("node out degrees", stats.node_out_degrees);
     print_value_row("root out degree", stats.root_out_degree);
     println!();
+    print_timing_stats(stats.timing_stats);
+    println!();
+    print_stanza_stats(stats.stanza_stats.values());
+    println!();
     print_stitching_stats(stats.stitching_stats);
 }
 
+pub(super) fn print_timing_stats(stats: TimingStats) {
+    print_quartiles_header("timing stats (us)");
+    print_quartiles_row("parsing", stats.parsing);
+    print_quartiles_row("TSG execution", stats.execution);
+    print_quartiles_row("stack graph loading", stats.loading);
+    print_quartiles_row("partial path computation", stats.path_computation);
+}
+
+/// The number of stanzas shown in the stanza statistics.
+const SLOWEST_STANZA_COUNT: usize = 10;
+
+/// Prints the stanzas that took the most time to match, slowest first.
+pub(super) fn print_stanza_stats<'a>(stats: impl IntoIterator<Item = &'a StanzaCoverage>) {
+    let mut stanzas = stats
+        .into_iter()
+        .flat_map(|coverage| {
+            coverage
+                .iter_stanzas()
+                .map(move |stanza| (coverage.tsg_path(), stanza))
+        })
+        .filter(|(_, stanza)| stanza.is_hit())
+        .collect::<Vec<_>>();
+    stanzas.sort_by_key(|(_, stanza)| std::cmp::Reverse(stanza.match_time));
+    println!(
+        "| {:^53} | {:^9} | {:^9} |",
+        "slowest stanza queries", "matches", "time (us)",
+    );
+    println!("|-------------------------------------------------------|-----------|-----------|");
+    for (tsg_path, stanza) in stanzas.into_iter().take(SLOWEST_STANZA_COUNT) {
+        println!(
+            "| {:>53} | {:>9} | {:>9} |",
+            format!("{}:{}", tsg_path.display(), stanza.start_line + 1),
+            stanza.matches,
+            stanza.match_time.as_micros(),
+        );
+    }
+}
+
 pub(super) fn print_stitching_stats(stats: StitchingStats) {
     print_quartiles_header("stitching stats");
     print_quartiles_row("initial paths", stats.initial_paths);
@@ -540,7 +871,7 @@ pub(super) fn print_database_stats(stats: StorageStats) {
         "| {:^29} | {:^9} | {:^9} |",
         "database stats", "loads", "cached",
     );
-    println!("|-------------------------------|-----------|-----------|");
+    println!("|-------------------------------------------------------|-----------|-----------|");
     println!(
         "| {:>29} | {:>9} | {:>9} |",
         "files", stats.file_loads, stats.file_cached
@@ -555,6 +886,39 @@ pub(super) fn print_database_stats(stats: StorageStats) {
     );
 }
 
+pub(super) fn print_file_summary_stats(summaries: &[FileSummary]) {
+    let mut graph_sizes = FrequencyDistribution::default();
+    let mut graph_nodes = FrequencyDistribution::default();
+    let mut graph_edges = FrequencyDistribution::default();
+    let mut node_paths = FrequencyDistribution::default();
+    let mut node_paths_sizes = FrequencyDistribution::default();
+    let mut root_paths = FrequencyDistribution::default();
+    let mut root_paths_sizes = FrequencyDistribution::default();
+    let mut failed_files = 0usize;
+    for summary in summaries {
+        if let FileStatus::Error(_) = summary.status {
+            failed_files += 1;
+            continue;
+        }
+        graph_sizes.record(summary.graph_size);
+        graph_nodes.record(summary.graph_node_count);
+        graph_edges.record(summary.graph_edge_count);
+        node_paths.record(summary.node_path_count);
+        node_paths_sizes.record(summary.node_paths_size);
+        root_paths.record(summary.root_path_count);
+        root_paths_sizes.record(summary.root_paths_size);
+    }
+    print_quartiles_header("file stats");
+    print_quartiles_row("graph size (bytes)", graph_sizes);
+    print_quartiles_row("graph nodes", graph_nodes);
+    print_quartiles_row("graph edges", graph_edges);
+    print_quartiles_row("node paths", node_paths);
+    print_quartiles_row("node paths size (bytes)", node_paths_sizes);
+    print_quartiles_row("root paths", root_paths);
+    print_quartiles_row("root paths size (bytes)", root_paths_sizes);
+    print_value_row("failed files", failed_files);
+}
+
 fn print_quartiles_header(title: &str) {
     println!(
         "| {:^29} | {:^9} | {:^9} | {:^9} | {:^9} | {:^9} | {:^9} |",
@@ -0,0 +1,83 @@
+// -*- coding: utf-8 -*-
+// ------------------------------------------------------------------------------------------------
+// Copyright © 2023, stack-graphs authors.
+// Licensed under either of Apache License, Version 2.0, or MIT license, at your option.
+// Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
+// ------------------------------------------------------------------------------------------------
+
+//! Finds changed files in a local git repository, using the `git` command.
+
+use anyhow::anyhow;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// The files that changed since a revision.
+#[derive(Debug, Default)]
+pub struct Changes {
+    /// Files that were added or modified, or are the target of a rename or copy.
+    pub changed: Vec<PathBuf>,
+    /// Files that were deleted, or are the source of a rename.
+    pub removed: Vec<PathBuf>,
+}
+
+/// Returns the commit hash of the HEAD revision of the repository containing the directory.
+pub fn head_revision(dir: &Path) -> anyhow::Result<String> {
+    Ok(git(dir, &["rev-parse", "HEAD"])?.trim().to_string())
+}
+
+/// Returns the files that changed between the given revision and the working tree of the
+/// repository containing the directory, which includes committed as well as uncommitted changes
+/// to tracked files. Untracked files are not included. Paths are absolute.
+pub fn changes_since(dir: &Path, revision: &str) -> anyhow::Result<Changes> {
+    let toplevel = PathBuf::from(git(dir, &["rev-parse", "--show-toplevel"])?.trim());
+    let toplevel = toplevel.canonicalize()?;
+    let output = git(dir, &["diff", "--name-status", "-z", "-M", revision, "--"])?;
+    parse_name_status(&toplevel, &output)
+}
+
+/// Parses the output of `git diff --name-status -z`. Paths are resolved against the given
+/// repository root.
+pub fn parse_name_status(toplevel: &Path, output: &str) -> anyhow::Result<Changes> {
+    let mut changes = Changes::default();
+    let mut fields = output.split('\0').filter(|f| !f.is_empty());
+    while let Some(status) = fields.next() {
+        let mut path = || {
+            fields
+                .next()
+                .map(|p| toplevel.join(p))
+                .ok_or_else(|| anyhow!("Missing path for status {} in git output", status))
+        };
+        match status.chars().next() {
+            Some('A') | Some('M') | Some('T') => changes.changed.push(path()?),
+            Some('D') => changes.removed.push(path()?),
+            Some('R') => {
+                changes.removed.push(path()?);
+                changes.changed.push(path()?);
+            }
+            Some('C') => {
+                path()?;
+                changes.changed.push(path()?);
+            }
+            _ => return Err(anyhow!("Unsupported status {} in git output", status)),
+        }
+    }
+    Ok(changes)
+}
+
+fn git(dir: &Path, args: &[&str]) -> anyhow::Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| anyhow!("Cannot run git: {}", e))?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
@@ -7,8 +7,12 @@
 
 use colored::ColoredString;
 use colored::Colorize;
+use serde_json::json;
+use serde_json::Value;
+use std::cell::RefCell;
 use std::io::Write;
 use std::path::Path;
+use std::path::PathBuf;
 
 /// Trait that supports reporting file processing status.
 ///
@@ -176,3 +180,168 @@ impl Reporter for ConsoleReporter {
         )
     }
 }
+
+/// A reporter that outputs one JSON object per line for every reported status, for consumption by
+/// other programs. Every object has an `event` field, which is one of `skipped`, `started`,
+/// `succeeded`, `failed`, or `cancelled`, and a `path` field. All events except `started` also
+/// have a `summary` field, and a `details` field that is null if there are no details.
+#[derive(Clone, Copy, Debug)]
+pub struct JsonReporter;
+
+impl JsonReporter {
+    /// Creates a new JSON reporter. Details are reported as they are formatted, so colored output
+    /// should be disabled to keep terminal escape codes out of them.
+    pub fn new() -> Self {
+        Self
+    }
+
+    fn print_event(&self, event: &str, path: &Path) {
+        self.print_status(json!({
+            "event": event,
+            "path": path.to_string_lossy(),
+        }));
+    }
+
+    fn print_result(
+        &self,
+        event: &str,
+        path: &Path,
+        summary: &str,
+        details: Option<&dyn std::fmt::Display>,
+    ) {
+        self.print_status(json!({
+            "event": event,
+            "path": path.to_string_lossy(),
+            "summary": summary,
+            "details": details.map(|d| d.to_string()),
+        }));
+    }
+
+    /// Print a status object. Statuses are dropped if the reader of the output has gone away.
+    fn print_status(&self, value: Value) {
+        match self.print(value) {
+            Err(err) if err.kind() != std::io::ErrorKind::BrokenPipe => {
+                panic!("write should succeed: {}", err)
+            }
+            _ => {}
+        }
+    }
+
+    /// Print a JSON object as a single line.
+    pub fn print(&self, value: Value) -> std::io::Result<()> {
+        let mut stdout = std::io::stdout().lock();
+        serde_json::to_writer(&mut stdout, &value)?;
+        writeln!(stdout)?;
+        stdout.flush()
+    }
+}
+
+impl Reporter for JsonReporter {
+    fn skipped(&self, path: &Path, summary: &str, details: Option<&dyn std::fmt::Display>) {
+        self.print_result("skipped", path, summary, details);
+    }
+
+    fn started(&self, path: &Path) {
+        self.print_event("started", path);
+    }
+
+    fn succeeded(&self, path: &Path, summary: &str, details: Option<&dyn std::fmt::Display>) {
+        self.print_result("succeeded", path, summary, details);
+    }
+
+    fn failed(&self, path: &Path, summary: &str, details: Option<&dyn std::fmt::Display>) {
+        self.print_result("failed", path, summary, details);
+    }
+
+    fn cancelled(&self, path: &Path, summary: &str, details: Option<&dyn std::fmt::Display>) {
+        self.print_result("cancelled", path, summary, details);
+    }
+}
+
+/// A reporter that records all reported statuses, so that they can be replayed to another
+/// reporter later. This allows files to be processed concurrently, while their statuses are
+/// still reported in a deterministic order.
+#[derive(Default)]
+pub struct BufferedReporter {
+    events: RefCell<Vec<BufferedEvent>>,
+}
+
+enum BufferedEvent {
+    Skipped(PathBuf, String, Option<String>),
+    Started(PathBuf),
+    Succeeded(PathBuf, String, Option<String>),
+    Failed(PathBuf, String, Option<String>),
+    Cancelled(PathBuf, String, Option<String>),
+}
+
+impl BufferedReporter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Report all recorded statuses to the given reporter, in the order they were recorded.
+    pub fn replay(self, reporter: &dyn Reporter) {
+        fn details(details: &Option<String>) -> Option<&dyn std::fmt::Display> {
+            details.as_ref().map(|d| d as &dyn std::fmt::Display)
+        }
+        for event in self.events.into_inner() {
+            match &event {
+                BufferedEvent::Skipped(path, summary, d) => {
+                    reporter.skipped(path, summary, details(d))
+                }
+                BufferedEvent::Started(path) => reporter.started(path),
+                BufferedEvent::Succeeded(path, summary, d) => {
+                    reporter.succeeded(path, summary, details(d))
+                }
+                BufferedEvent::Failed(path, summary, d) => {
+                    reporter.failed(path, summary, details(d))
+                }
+                BufferedEvent::Cancelled(path, summary, d) => {
+                    reporter.cancelled(path, summary, details(d))
+                }
+            }
+        }
+    }
+
+    fn record(&self, event: BufferedEvent) {
+        self.events.borrow_mut().push(event);
+    }
+}
+
+impl Reporter for BufferedReporter {
+    fn skipped(&self, path: &Path, summary: &str, details: Option<&dyn std::fmt::Display>) {
+        self.record(BufferedEvent::Skipped(
+            path.to_path_buf(),
+            summary.to_string(),
+            details.map(|d| d.to_string()),
+        ));
+    }
+
+    fn started(&self, path: &Path) {
+        self.record(BufferedEvent::Started(path.to_path_buf()));
+    }
+
+    fn succeeded(&self, path: &Path, summary: &str, details: Option<&dyn std::fmt::Display>) {
+        self.record(BufferedEvent::Succeeded(
+            path.to_path_buf(),
+            summary.to_string(),
+            details.map(|d| d.to_string()),
+        ));
+    }
+
+    fn failed(&self, path: &Path, summary: &str, details: Option<&dyn std::fmt::Display>) {
+        self.record(BufferedEvent::Failed(
+            path.to_path_buf(),
+            summary.to_string(),
+            details.map(|d| d.to_string()),
+        ));
+    }
+
+    fn cancelled(&self, path: &Path, summary: &str, details: Option<&dyn std::fmt::Display>) {
+        self.record(BufferedEvent::Cancelled(
+            path.to_path_buf(),
+            summary.to_string(),
+            details.map(|d| d.to_string()),
+        ));
+    }
+}
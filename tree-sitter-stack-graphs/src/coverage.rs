@@ -0,0 +1,239 @@
+// -*- coding: utf-8 -*-
+// ------------------------------------------------------------------------------------------------
+// Copyright © 2023, stack-graphs authors.
+// Licensed under either of Apache License, Version 2.0, or MIT license, at your option.
+// Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
+// ------------------------------------------------------------------------------------------------
+
+//! Defines stanza coverage for TSG files.
+//!
+//! Stanza coverage records, for every stanza in a TSG file, how often its query matched source
+//! files, and how many stack graph nodes were created by its statements. Nodes are attributed to
+//! stanzas using the `tsg_location` debug info that the [`Builder`][crate::Builder] attaches to
+//! every node.
+
+use stack_graphs::arena::Handle;
+use stack_graphs::graph::File;
+use stack_graphs::graph::StackGraph;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::BuildError;
+use crate::BuildOutput;
+use crate::StackGraphLanguage;
+
+static TSG_LOCATION_DEBUG_KEY: &'static str = "tsg_location";
+
+/// Coverage of the stanzas of a single TSG file.
+#[derive(Clone, Debug)]
+pub struct StanzaCoverage {
+    tsg_path: PathBuf,
+    stanzas: Vec<StanzaHits>,
+}
+
+/// Coverage of a single stanza.
+#[derive(Clone, Debug)]
+pub struct StanzaHits {
+    /// The first line of the stanza (0-based).
+    pub start_line: usize,
+    /// The last line of the stanza (0-based).
+    pub end_line: usize,
+    /// The number of times the stanza query matched.
+    pub matches: usize,
+    /// The number of stack graph nodes created by the stanza.
+    pub nodes: usize,
+    /// The time spent finding the matches of the stanza query.
+    pub match_time: Duration,
+}
+
+impl StanzaHits {
+    pub fn is_hit(&self) -> bool {
+        self.matches > 0
+    }
+}
+
+impl StanzaCoverage {
+    /// Creates empty coverage for the stanzas of the given language.
+    pub fn new(sgl: &StackGraphLanguage) -> Self {
+        let stanzas = sgl
+            .tsg
+            .stanzas
+            .iter()
+            .map(|s| StanzaHits {
+                start_line: s.range.start.row,
+                end_line: s.range.end.row,
+                matches: 0,
+                nodes: 0,
+                match_time: Duration::ZERO,
+            })
+            .collect();
+        Self {
+            tsg_path: sgl.tsg_path().to_path_buf(),
+            stanzas,
+        }
+    }
+
+    pub fn tsg_path(&self) -> &Path {
+        &self.tsg_path
+    }
+
+    /// Iterate over the coverage of all stanzas, in the order they appear in the TSG file.
+    pub fn iter_stanzas(&self) -> impl Iterator<Item = &StanzaHits> + '_ {
+        self.stanzas.iter()
+    }
+
+    /// Iterate over all stanzas that never matched.
+    pub fn iter_unhit_stanzas(&self) -> impl Iterator<Item = &StanzaHits> + '_ {
+        self.stanzas.iter().filter(|s| !s.is_hit())
+    }
+
+    /// Records the stanzas that matched in a build of the given source with the given language,
+    /// using the tree that the graph construction rules were executed on. Matches are found one
+    /// at a time, and the time spent finding a match is attributed to the stanza it belongs to.
+    pub fn add_matches(
+        &mut self,
+        sgl: &StackGraphLanguage,
+        source: &str,
+        output: &BuildOutput,
+    ) -> Result<(), BuildError> {
+        let source = output.execution_source(source);
+        let mut match_start = Instant::now();
+        sgl.tsg.try_visit_matches(
+            &output.execution_tree,
+            source,
+            true,
+            |mat| -> Result<(), BuildError> {
+                let match_time = match_start.elapsed();
+                if let Some(stanza) = self.stanza_for_line_mut(mat.query_location().row) {
+                    stanza.matches += 1;
+                    stanza.match_time += match_time;
+                }
+                match_start = Instant::now();
+                Ok(())
+            },
+        )
+    }
+
+    /// Records the stanzas that created the nodes of the given file. The nodes must have been
+    /// created using the stanzas of this coverage.
+    pub fn add_nodes(&mut self, graph: &StackGraph, file: Handle<File>) {
+        for node in graph.nodes_for_file(file) {
+            let location = match graph.node_debug_info(node).and_then(|di| {
+                di.iter()
+                    .find(|e| &graph[e.key] == TSG_LOCATION_DEBUG_KEY)
+                    .map(|e| &graph[e.value])
+            }) {
+                Some(location) => location,
+                None => continue,
+            };
+            // locations are formatted with the 1-based line number first, e.g. `(L, C)`
+            let line = match location
+                .split(|c: char| !c.is_ascii_digit())
+                .find(|s| !s.is_empty())
+                .and_then(|s| s.parse::<usize>().ok())
+            {
+                Some(line) if line > 0 => line - 1,
+                _ => continue,
+            };
+            if let Some(stanza) = self.stanza_for_line_mut(line) {
+                stanza.nodes += 1;
+            }
+        }
+    }
+
+    /// Adds the hits of the given coverage to this coverage. Both must be for the same TSG file.
+    pub fn absorb(&mut self, other: StanzaCoverage) {
+        for (stanza, other) in self.stanzas.iter_mut().zip(other.stanzas.into_iter()) {
+            stanza.matches += other.matches;
+            stanza.nodes += other.nodes;
+            stanza.match_time += other.match_time;
+        }
+    }
+
+    fn stanza_for_line_mut(&mut self, line: usize) -> Option<&mut StanzaHits> {
+        self.stanzas
+            .iter_mut()
+            .find(|s| s.start_line <= line && line <= s.end_line)
+    }
+
+    /// Writes a human-readable summary of the coverage.
+    pub fn write_text<W: Write>(&self, w: &mut W) -> std::io::Result<()> {
+        writeln!(
+            w,
+            "{}: {}/{} stanzas hit",
+            self.tsg_path.display(),
+            self.stanzas.iter().filter(|s| s.is_hit()).count(),
+            self.stanzas.len(),
+        )?;
+        for stanza in &self.stanzas {
+            writeln!(
+                w,
+                "  {}:{}: {} matches, {} nodes",
+                self.tsg_path.display(),
+                stanza.start_line + 1,
+                stanza.matches,
+                stanza.nodes,
+            )?;
+        }
+        if self.iter_unhit_stanzas().next().is_some() {
+            writeln!(w, "  never hit:")?;
+            for stanza in self.iter_unhit_stanzas() {
+                writeln!(
+                    w,
+                    "    {}:{}",
+                    self.tsg_path.display(),
+                    stanza.start_line + 1
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the coverage in the LCOV tracefile format. Every stanza is reported as a function,
+    /// with the number of matches as its hit count. The number of matches is also reported for
+    /// every line of the stanza.
+    pub fn write_lcov<W: Write>(&self, w: &mut W) -> std::io::Result<()> {
+        writeln!(w, "TN:")?;
+        writeln!(w, "SF:{}", self.tsg_path.display())?;
+        for stanza in &self.stanzas {
+            writeln!(
+                w,
+                "FN:{},stanza_{}",
+                stanza.start_line + 1,
+                stanza.start_line + 1
+            )?;
+        }
+        for stanza in &self.stanzas {
+            writeln!(
+                w,
+                "FNDA:{},stanza_{}",
+                stanza.matches,
+                stanza.start_line + 1
+            )?;
+        }
+        writeln!(w, "FNF:{}", self.stanzas.len())?;
+        writeln!(
+            w,
+            "FNH:{}",
+            self.stanzas.iter().filter(|s| s.is_hit()).count()
+        )?;
+        let mut line_count = 0;
+        let mut line_hit_count = 0;
+        for stanza in &self.stanzas {
+            for line in stanza.start_line..=stanza.end_line {
+                writeln!(w, "DA:{},{}", line + 1, stanza.matches)?;
+                line_count += 1;
+                if stanza.is_hit() {
+                    line_hit_count += 1;
+                }
+            }
+        }
+        writeln!(w, "LF:{}", line_count)?;
+        writeln!(w, "LH:{}", line_hit_count)?;
+        writeln!(w, "end_of_record")?;
+        Ok(())
+    }
+}
@@ -189,6 +189,32 @@
 //!
 //! Definiens are optional and setting them to `#null` explicitly is allowed.
 //!
+//! ### Annotating definitions with documentation
+//!
+//! You can annotate definitions with documentation, such as the doc comment of a function, which
+//! can be shown by hover and symbol tools. To do this, add a `documentation` attribute, whose value
+//! is either a string, or a syntax node whose source text is the documentation.
+//!
+//! ``` skip
+//! (
+//!   (comment)? @doc
+//!   .
+//!   (function_definition name: (identifier) @id) @func
+//! ) {
+//!   node def
+//!   ; ...
+//!   attr (def) documentation = @doc
+//! }
+//! ```
+//!
+//! Documentation is optional and setting it to `#null` explicitly is allowed, which is convenient
+//! when the syntax node is captured by an optional quantifier.
+//!
+//! The documentation is stored verbatim. The source text of a syntax node includes any comment
+//! markers or string delimiters, such as `/**`, ` * `, and `*/`, which are not stripped, because
+//! they differ per language. Either strip them in the rules, for example using the `replace`
+//! function on the `source-text` of the node, or leave it to consumers of the documentation.
+//!
 //! ### Connecting stack graph nodes with edges
 //!
 //! To connect two stack graph nodes, use the `edge` statement to add an edge between them:
@@ -293,6 +319,63 @@
 //! }
 //! ```
 //!
+//! ### Connecting injected languages
+//!
+//! Files can contain code in other languages, such as JavaScript inside an HTML `<script>`
+//! element.  If a language has an injections query (see
+//! [`StackGraphLanguage::set_injections_query`][]), the injected regions of a file are found using
+//! tree-sitter's [language injection][] conventions: the `@injection.content` capture marks the
+//! injected syntax nodes, and the `@injection.language` capture or the `injection.language`
+//! property names the injected language.  The `injection.combined` and
+//! `injection.include-children` properties are supported as well.  Each injected region is parsed
+//! with its own language, and its stack graph nodes are created in the same file as the host's.
+//!
+//! To connect the host and injected code, the host creates a scope node with an `injection_node`
+//! attribute, whose value is the syntax node captured as `@injection.content`:
+//!
+//! ``` skip
+//! (script_element (raw_text) @content) {
+//!   node @content.injection_scope
+//!   attr (@content.injection_scope) injection_node = @content
+//!   edge @content.injection_scope -> ROOT_NODE
+//! }
+//! ```
+//!
+//! The injected code can refer to that node using the `INJECTION_SCOPE` global variable, for
+//! example to look up names in the host, or to make its definitions visible to the host.  In a file
+//! that is not injected, or if the host did not create a scope for the injection, `INJECTION_SCOPE`
+//! is the root node.
+//!
+//! ``` skip
+//! global INJECTION_SCOPE
+//!
+//! (program) @prog {
+//!   node @prog.defs
+//!   edge INJECTION_SCOPE -> @prog.defs
+//! }
+//! ```
+//!
+//! [language injection]: https://tree-sitter.github.io/tree-sitter/syntax-highlighting#language-injection
+//!
+//! ### Incremental builds
+//!
+//! When a source file is edited, the stack graph can be rebuilt using
+//! [`StackGraphLanguage::build_stack_graph_incrementally_into`][], which takes the parse tree of
+//! the previous build, as returned in its [`BuildOutput`][], and the edits that were made to the
+//! source since.  The source is then reparsed incrementally, and the [`SourceChanges`][] in the
+//! output describe which parts of the source changed.  If only comments or whitespace changed,
+//! the partial paths computed for the previous version of the file can be reused.
+//!
+//! ### Tolerating parse errors
+//!
+//! By default, building a stack graph fails if the source file has any parse errors.  When a
+//! language is made error tolerant using [`StackGraphLanguage::set_error_tolerant`][], the
+//! construction rules are executed for files with parse errors as well.  The text of `ERROR`
+//! syntax nodes is blanked out before the rules are executed, so that stanzas cannot match inside
+//! them.  Stack graph nodes that are created by stanzas that matched a `MISSING` syntax node are
+//! skipped, together with their edges, so that the rest of the file can still be resolved.  The
+//! parse errors are returned in the [`BuildOutput`][] of the build.
+//!
 //! ## Using this crate from Rust
 //!
 //! If you need very fine-grained control over how to use the resulting stack graphs, you can
@@ -348,6 +431,7 @@ use std::collections::HashMap;
 use std::collections::HashSet;
 use std::mem::transmute;
 use std::ops::BitOr;
+use std::ops::Range;
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::atomic::AtomicBool;
@@ -356,7 +440,10 @@ use std::sync::Arc;
 use std::time::Duration;
 use std::time::Instant;
 use thiserror::Error;
+use tree_sitter::InputEdit;
 use tree_sitter::Parser;
+use tree_sitter::Query;
+use tree_sitter::QueryCursor;
 use tree_sitter_graph::functions::Functions;
 use tree_sitter_graph::graph::Edge;
 use tree_sitter_graph::graph::Graph;
@@ -373,8 +460,10 @@ use util::TreeSitterCancellationFlag;
 pub mod ci;
 #[cfg(feature = "cli")]
 pub mod cli;
+pub mod coverage;
 pub mod functions;
 pub mod loader;
+pub mod plugin;
 pub mod test;
 mod util;
 
@@ -393,8 +482,11 @@ static SCOPE_TYPE: &'static str = "scope";
 
 // Node attribute names
 static DEBUG_ATTR_PREFIX: &'static str = "debug_";
+static DEBUG_TSG_MATCH_NODE_ATTR: &'static str = "debug_tsg_match_node";
 static DEFINIENS_NODE_ATTR: &'static str = "definiens_node";
+static DOCUMENTATION_ATTR: &'static str = "documentation";
 static EMPTY_SOURCE_SPAN_ATTR: &'static str = "empty_source_span";
+static INJECTION_NODE_ATTR: &'static str = "injection_node";
 static IS_DEFINITION_ATTR: &'static str = "is_definition";
 static IS_ENDPOINT_ATTR: &'static str = "is_endpoint";
 static IS_EXPORTED_ATTR: &'static str = "is_exported";
@@ -412,6 +504,7 @@ static POP_SCOPED_SYMBOL_ATTRS: Lazy<HashSet<&'static str>> = Lazy::new(|| {
         SYMBOL_ATTR,
         IS_DEFINITION_ATTR,
         DEFINIENS_NODE_ATTR,
+        DOCUMENTATION_ATTR,
         SYNTAX_TYPE_ATTR,
     ])
 });
@@ -421,6 +514,7 @@ static POP_SYMBOL_ATTRS: Lazy<HashSet<&'static str>> = Lazy::new(|| {
         SYMBOL_ATTR,
         IS_DEFINITION_ATTR,
         DEFINIENS_NODE_ATTR,
+        DOCUMENTATION_ATTR,
         SYNTAX_TYPE_ATTR,
     ])
 });
@@ -428,8 +522,14 @@ static PUSH_SCOPED_SYMBOL_ATTRS: Lazy<HashSet<&'static str>> =
     Lazy::new(|| HashSet::from([TYPE_ATTR, SYMBOL_ATTR, SCOPE_ATTR, IS_REFERENCE_ATTR]));
 static PUSH_SYMBOL_ATTRS: Lazy<HashSet<&'static str>> =
     Lazy::new(|| HashSet::from([TYPE_ATTR, SYMBOL_ATTR, IS_REFERENCE_ATTR]));
-static SCOPE_ATTRS: Lazy<HashSet<&'static str>> =
-    Lazy::new(|| HashSet::from([TYPE_ATTR, IS_EXPORTED_ATTR, IS_ENDPOINT_ATTR]));
+static SCOPE_ATTRS: Lazy<HashSet<&'static str>> = Lazy::new(|| {
+    HashSet::from([
+        TYPE_ATTR,
+        IS_EXPORTED_ATTR,
+        IS_ENDPOINT_ATTR,
+        INJECTION_NODE_ATTR,
+    ])
+});
 
 // Edge attribute names
 static PRECEDENCE_ATTR: &'static str = "precedence";
@@ -438,6 +538,14 @@ static PRECEDENCE_ATTR: &'static str = "precedence";
 static ROOT_NODE_VAR: &'static str = "ROOT_NODE";
 static JUMP_TO_SCOPE_NODE_VAR: &'static str = "JUMP_TO_SCOPE_NODE";
 static FILE_PATH_VAR: &'static str = "FILE_PATH";
+static INJECTION_SCOPE_VAR: &'static str = "INJECTION_SCOPE";
+
+// Injection query captures and properties
+static INJECTION_CONTENT_CAPTURE: &'static str = "injection.content";
+static INJECTION_LANGUAGE_CAPTURE: &'static str = "injection.language";
+static INJECTION_LANGUAGE_PROPERTY: &'static str = "injection.language";
+static INJECTION_COMBINED_PROPERTY: &'static str = "injection.combined";
+static INJECTION_INCLUDE_CHILDREN_PROPERTY: &'static str = "injection.include-children";
 
 /// Holds information about how to construct stack graphs for a particular language.
 pub struct StackGraphLanguage {
@@ -446,6 +554,8 @@ pub struct StackGraphLanguage {
     tsg_path: PathBuf,
     tsg_source: std::borrow::Cow<'static, str>,
     functions: Functions,
+    injections: Option<Query>,
+    error_tolerant: bool,
 }
 
 impl StackGraphLanguage {
@@ -462,6 +572,8 @@ impl StackGraphLanguage {
             tsg_path: PathBuf::from("<tsg>"),
             tsg_source: Cow::from(String::new()),
             functions: Self::default_functions(),
+            injections: None,
+            error_tolerant: false,
         }
     }
 
@@ -479,6 +591,8 @@ impl StackGraphLanguage {
             tsg_path: PathBuf::from("<missing tsg path>"),
             tsg_source: Cow::from(tsg_source.to_string()),
             functions: Self::default_functions(),
+            injections: None,
+            error_tolerant: false,
         })
     }
 
@@ -511,6 +625,30 @@ impl StackGraphLanguage {
         &mut self.functions
     }
 
+    /// Sets the tree-sitter injections query that finds regions of source files that are written
+    /// in other languages.  See [Connecting injected languages](crate#connecting-injected-languages)
+    /// for the supported captures and properties.
+    pub fn set_injections_query(&mut self, source: &str) -> Result<(), LanguageError> {
+        self.injections = Some(Query::new(self.language, source)?);
+        Ok(())
+    }
+
+    /// Returns whether this language has an injections query.
+    pub fn has_injections(&self) -> bool {
+        self.injections.is_some()
+    }
+
+    /// Sets whether stack graphs are built for source files with parse errors.  See
+    /// [Tolerating parse errors](crate#tolerating-parse-errors) for details.
+    pub fn set_error_tolerant(&mut self, error_tolerant: bool) {
+        self.error_tolerant = error_tolerant;
+    }
+
+    /// Returns whether stack graphs are built for source files with parse errors.
+    pub fn is_error_tolerant(&self) -> bool {
+        self.error_tolerant
+    }
+
     pub fn language(&self) -> tree_sitter::Language {
         self.language
     }
@@ -533,6 +671,8 @@ impl StackGraphLanguage {
 pub enum LanguageError {
     #[error(transparent)]
     ParseError(#[from] tree_sitter_graph::ParseError),
+    #[error(transparent)]
+    InjectionsQueryError(#[from] tree_sitter::QueryError),
 }
 
 impl LanguageError {
@@ -541,9 +681,11 @@ impl LanguageError {
         path: &'a Path,
         source: &'a str,
     ) -> impl std::fmt::Display + 'a {
-        match self {
-            Self::ParseError(err) => err.display_pretty(path, source),
-        }
+        let display: Box<dyn std::fmt::Display + 'a> = match self {
+            Self::ParseError(err) => Box::new(err.display_pretty(path, source)),
+            Self::InjectionsQueryError(err) => Box::new(format!("{}: {}", path.display(), err)),
+        };
+        display
     }
 }
 
@@ -564,6 +706,62 @@ impl StackGraphLanguage {
             .build(globals, cancellation_flag)
     }
 
+    /// Executes the graph construction rules for this language against a source file, like
+    /// [`Self::build_stack_graph_into`][], and returns the [`BuildOutput`][], which contains the
+    /// regions of the file that are written in other languages, as found by the injections query,
+    /// and any tolerated parse errors.  Use [`Self::build_injection_into`][] with the language of
+    /// each injection to add the injected code to the stack graph.
+    pub fn build_stack_graph_with_output_into<'a>(
+        &'a self,
+        stack_graph: &'a mut StackGraph,
+        file: Handle<File>,
+        source: &'a str,
+        globals: &'a Variables<'a>,
+        cancellation_flag: &'a dyn CancellationFlag,
+    ) -> Result<BuildOutput, BuildError> {
+        self.builder_into_stack_graph(stack_graph, file, source)
+            .build_with_output(globals, cancellation_flag)
+    }
+
+    /// Executes the graph construction rules for this language against a source file, like
+    /// [`Self::build_stack_graph_with_output_into`][], but reparses the source incrementally.  The
+    /// `previous_tree` is the tree of a previous build of the file, and `edits` are the edits that
+    /// were made to the source since, in the order they were made.  The returned
+    /// [`BuildOutput`][] describes the changes compared to the previous tree.
+    pub fn build_stack_graph_incrementally_into<'a>(
+        &'a self,
+        stack_graph: &'a mut StackGraph,
+        file: Handle<File>,
+        source: &'a str,
+        previous_tree: &tree_sitter::Tree,
+        edits: &[InputEdit],
+        globals: &'a Variables<'a>,
+        cancellation_flag: &'a dyn CancellationFlag,
+    ) -> Result<BuildOutput, BuildError> {
+        let mut builder = self.builder_into_stack_graph(stack_graph, file, source);
+        builder.set_previous_tree(previous_tree, edits);
+        builder.build_with_output(globals, cancellation_flag)
+    }
+
+    /// Executes the graph construction rules for this language against an injected region of a
+    /// source file, creating new nodes and edges in `stack_graph`.  The `source` is the content of
+    /// the whole file, and the new nodes belong to the same `file` as the host's nodes.  The
+    /// returned [`BuildOutput`][] contains any regions nested in the injected region that are
+    /// written in yet other languages.
+    pub fn build_injection_into<'a>(
+        &'a self,
+        stack_graph: &'a mut StackGraph,
+        file: Handle<File>,
+        source: &'a str,
+        injection: &Injection,
+        globals: &'a Variables<'a>,
+        cancellation_flag: &'a dyn CancellationFlag,
+    ) -> Result<BuildOutput, BuildError> {
+        let mut builder = self.builder_into_stack_graph(stack_graph, file, source);
+        builder.set_injection(injection);
+        builder.build_with_output(globals, cancellation_flag)
+    }
+
     /// Create a builder that will execute the graph construction rules for this language against
     /// a source file, creating new nodes and edges in `stack_graph`.  Any new nodes created during
     /// execution will belong to `file`.  (The source file must be implemented in this language,
@@ -578,6 +776,97 @@ impl StackGraphLanguage {
     }
 }
 
+/// A region of a source file that is written in another language.
+#[derive(Clone, Debug)]
+pub struct Injection {
+    /// The name of the injected language, as given by the injections query.
+    pub language: String,
+    /// The ranges of the source file that make up the injected code.
+    pub ranges: Vec<tree_sitter::Range>,
+    /// The host scope node that was created for the injection using the `injection_node`
+    /// attribute, if any.
+    pub scope: Option<NodeID>,
+}
+
+/// The output of a successful stack graph build.
+#[derive(Debug)]
+pub struct BuildOutput {
+    /// The parse tree of the source, which can be used as the previous tree of an incremental
+    /// build after the source is edited.
+    pub tree: tree_sitter::Tree,
+    /// The parse tree that the graph construction rules were executed on. This is the same as
+    /// `tree`, unless parse errors were tolerated, in which case the text of error nodes was
+    /// replaced by whitespace, and the result was parsed again.
+    pub execution_tree: tree_sitter::Tree,
+    /// The source that the graph construction rules were executed on, if it differs from the
+    /// source of the build because parse errors were tolerated.
+    pub execution_source: Option<String>,
+    /// The regions of the source that are written in other languages.
+    pub injections: Vec<Injection>,
+    /// The parse errors in the source, if the language is error tolerant and the source could not
+    /// be parsed without errors.
+    pub parse_errors: Option<TreeWithParseErrorVec>,
+    /// The changes compared to the previous tree, if this was an incremental build.
+    pub changes: Option<SourceChanges>,
+    /// The time spent in the phases of the build.
+    pub timings: BuildTimings,
+}
+
+impl BuildOutput {
+    /// Returns the source that the graph construction rules were executed on, given the source of
+    /// the build.
+    pub fn execution_source<'s>(&'s self, source: &'s str) -> &'s str {
+        self.execution_source.as_deref().unwrap_or(source)
+    }
+}
+
+/// The time spent in the phases of a stack graph build.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BuildTimings {
+    /// The time spent parsing the source.
+    pub parsing: Duration,
+    /// The time spent executing the graph construction rules. The rules of all stanzas are
+    /// executed together, so this is not broken down per stanza. The time spent finding the
+    /// matches of each stanza is recorded by [`StanzaCoverage`][crate::coverage::StanzaCoverage].
+    pub execution: Duration,
+    /// The time spent loading the constructed graph into the stack graph.
+    pub loading: Duration,
+}
+
+impl std::ops::AddAssign<Self> for BuildTimings {
+    fn add_assign(&mut self, rhs: Self) {
+        self.parsing += rhs.parsing;
+        self.execution += rhs.execution;
+        self.loading += rhs.loading;
+    }
+}
+
+/// The changes of a source file between two builds.
+#[derive(Clone, Debug)]
+pub struct SourceChanges {
+    /// The byte ranges of the new source that changed, either because they were edited, or because
+    /// their syntax changed.  The ranges are sorted and do not overlap.
+    pub ranges: Vec<Range<usize>>,
+    /// Whether the changes only affected comments, whitespace, or other syntax nodes that are
+    /// extras in the grammar.
+    pub only_extras: bool,
+}
+
+impl SourceChanges {
+    /// Returns whether the stack graph of the file may have changed, and its partial paths need
+    /// to be recomputed.
+    pub fn affects_paths(&self) -> bool {
+        !self.only_extras
+    }
+}
+
+/// The previous tree of an incremental build, with the edits applied.
+struct PreviousTree {
+    tree: tree_sitter::Tree,
+    edited_ranges: Vec<Range<usize>>,
+    only_extras_edited: bool,
+}
+
 pub struct Builder<'a> {
     sgl: &'a StackGraphLanguage,
     stack_graph: &'a mut StackGraph,
@@ -587,6 +876,10 @@ pub struct Builder<'a> {
     remapped_nodes: HashMap<usize, NodeID>,
     injected_node_count: usize,
     span_calculator: SpanCalculator<'a>,
+    included_ranges: Vec<tree_sitter::Range>,
+    injection_scope: Option<NodeID>,
+    injection_scopes: HashMap<Range<usize>, NodeID>,
+    previous_tree: Option<PreviousTree>,
 }
 
 impl<'a> Builder<'a> {
@@ -606,33 +899,104 @@ impl<'a> Builder<'a> {
             remapped_nodes: HashMap::new(),
             injected_node_count: 0,
             span_calculator,
+            included_ranges: Vec::new(),
+            injection_scope: None,
+            injection_scopes: HashMap::new(),
+            previous_tree: None,
+        }
+    }
+
+    /// Restricts this builder to the given injected region of the source.  The injection's scope,
+    /// or the root node if it has none, is available as the `INJECTION_SCOPE` global variable.
+    pub fn set_injection(&mut self, injection: &Injection) {
+        self.included_ranges = injection.ranges.clone();
+        self.included_ranges.sort_by_key(|range| range.start_byte);
+        self.injection_scope = injection.scope;
+    }
+
+    /// Reparses the source incrementally, using the tree of a previous build and the edits that
+    /// were made to the source since, in the order they were made.
+    pub fn set_previous_tree(&mut self, tree: &tree_sitter::Tree, edits: &[InputEdit]) {
+        let mut tree = tree.clone();
+        let mut edited_ranges: Vec<Range<usize>> = Vec::new();
+        let mut only_extras_edited = true;
+        for edit in edits {
+            // Each edit is relative to the source after the edits before it, so the replaced
+            // region is checked against the tree as edited so far.
+            only_extras_edited &=
+                Self::only_extras_in_range(tree.root_node(), &(edit.start_byte..edit.old_end_byte));
+            for range in &mut edited_ranges {
+                *range = Self::map_range_through_edit(range, edit);
+            }
+            edited_ranges.push(edit.start_byte..edit.new_end_byte);
+            tree.edit(edit);
         }
+        self.previous_tree = Some(PreviousTree {
+            tree,
+            edited_ranges,
+            only_extras_edited,
+        });
     }
 
     /// Executes this builder.
     pub fn build(
-        mut self,
+        self,
         globals: &'a Variables<'a>,
         cancellation_flag: &dyn CancellationFlag,
     ) -> Result<(), BuildError> {
-        let tree = {
-            let mut parser = Parser::new();
-            parser.set_language(self.sgl.language)?;
-            let ts_cancellation_flag = TreeSitterCancellationFlag::from(cancellation_flag);
-            // The parser.set_cancellation_flag` is unsafe, because it does not tie the
-            // lifetime of the parser to the lifetime of the cancellation flag in any way.
-            // To make it more obvious that the parser does not outlive the cancellation flag,
-            // it is put into its own block here, instead of extending to the end of the method.
-            unsafe { parser.set_cancellation_flag(Some(ts_cancellation_flag.as_ref())) };
-            parser
-                .parse(self.source, None)
-                .ok_or(BuildError::ParseError)?
+        self.build_with_output(globals, cancellation_flag)
+            .map(|_| ())
+    }
+
+    /// Executes this builder, and returns the regions of the source that are written in other
+    /// languages, as found by the language's injections query, and any tolerated parse errors.
+    pub fn build_with_output(
+        mut self,
+        globals: &'a Variables<'a>,
+        cancellation_flag: &dyn CancellationFlag,
+    ) -> Result<BuildOutput, BuildError> {
+        let mut timings = BuildTimings::default();
+        let parsing_start = Instant::now();
+        let tree = self.parse(
+            self.source,
+            self.previous_tree.as_ref().map(|previous| &previous.tree),
+            cancellation_flag,
+        )?;
+        let changes = self
+            .previous_tree
+            .take()
+            .map(|previous| Self::compute_changes(previous, &tree));
+        // The tree is cheap to clone, and is kept to execute the construction rules on if parse
+        // errors are tolerated.
+        let parse_errors = ParseError::into_all(tree.clone());
+        let parse_errors = if parse_errors.errors().len() > 0 {
+            if !self.sgl.error_tolerant {
+                return Err(BuildError::ParseErrors(parse_errors));
+            }
+            Some(parse_errors)
+        } else {
+            None
         };
-        let parse_errors = ParseError::into_all(tree);
-        if parse_errors.errors().len() > 0 {
-            return Err(BuildError::ParseErrors(parse_errors));
+
+        // Stanzas that match inside error nodes see incomplete syntax, and can fail to execute.
+        // To prevent them from matching, the text of all error nodes is replaced by whitespace,
+        // and the result is parsed again.  Byte offsets and line breaks are kept intact, so that
+        // the rules produce the same spans as for the original source.  This is repeated while
+        // the parser recovers with new error nodes, which ends because every round blanks out
+        // more of the source.  Nodes from stanzas that match on missing nodes, which cannot be
+        // removed this way, are skipped when the graph is loaded.
+        let mut execution_source = None;
+        let mut execution_tree = tree.clone();
+        if parse_errors.is_some() {
+            while let Some(blanked_source) = Self::blank_error_nodes(
+                execution_source.as_deref().unwrap_or(self.source),
+                &execution_tree,
+            ) {
+                execution_tree = self.parse(&blanked_source, None, cancellation_flag)?;
+                execution_source = Some(blanked_source);
+            }
         }
-        let tree = parse_errors.into_tree();
+        timings.parsing = parsing_start.elapsed();
 
         let mut globals = Variables::nested(globals);
         if globals.get(&ROOT_NODE_VAR.into()).is_none() {
@@ -651,16 +1015,25 @@ impl<'a> Builder<'a> {
                 .add(FILE_PATH_VAR.into(), file_name.into())
                 .expect("Failed to set FILE_PATH");
         }
+        if globals.get(&INJECTION_SCOPE_VAR.into()).is_none() {
+            let injection_scope = match self.injection_scope {
+                Some(id) => self.inject_node(id).into(),
+                None => globals
+                    .get(&ROOT_NODE_VAR.into())
+                    .expect("Missing ROOT_NODE")
+                    .clone(),
+            };
+            globals
+                .add(INJECTION_SCOPE_VAR.into(), injection_scope)
+                .expect("Failed to set INJECTION_SCOPE");
+        }
 
         let mut config = ExecutionConfig::new(&self.sgl.functions, &globals)
             .lazy(true)
             .debug_attributes(
                 [DEBUG_ATTR_PREFIX, "tsg_location"].concat().as_str().into(),
                 [DEBUG_ATTR_PREFIX, "tsg_variable"].concat().as_str().into(),
-                [DEBUG_ATTR_PREFIX, "tsg_match_node"]
-                    .concat()
-                    .as_str()
-                    .into(),
+                DEBUG_TSG_MATCH_NODE_ATTR.into(),
             );
 
         // The execute_into() method requires that the reference to the tree matches the lifetime
@@ -672,16 +1045,168 @@ impl<'a> Builder<'a> {
         // (1) this method takes ownership of the Builder; and
         // (2) it returns no values connected to 'a.
         // These together guarantee that no values connected to the lifetime 'a outlive the Tree.
-        let tree: &'a tree_sitter::Tree = unsafe { transmute(&tree) };
+        // The same holds for the blanked source that the tree may have been parsed from.
+        // The tree in the output is the original tree, and the execution tree and source in the
+        // output are copies, none of which are connected to 'a.
+        let output_tree = tree;
+        let tree: &'a tree_sitter::Tree = unsafe { transmute(&execution_tree) };
+        let source: &'a str = match &execution_source {
+            Some(execution_source) => unsafe { transmute(execution_source.as_str()) },
+            None => self.source,
+        };
+        let execution_start = Instant::now();
         self.sgl.tsg.execute_into(
             &mut self.graph,
             tree,
-            self.source,
+            source,
             &mut config,
             &(cancellation_flag as &dyn CancellationFlag),
         )?;
+        timings.execution = execution_start.elapsed();
+
+        let injections = self.find_injections(tree);
+        let loading_start = Instant::now();
+        self.load(cancellation_flag)?;
+        timings.loading = loading_start.elapsed();
+
+        let injections = injections
+            .into_iter()
+            .map(|(mut injection, content_ranges)| {
+                injection.scope = content_ranges
+                    .iter()
+                    .find_map(|range| self.injection_scopes.get(range).cloned());
+                injection
+            })
+            .collect();
+        Ok(BuildOutput {
+            tree: output_tree,
+            execution_tree: execution_tree.clone(),
+            execution_source: execution_source.clone(),
+            injections,
+            parse_errors,
+            changes,
+            timings,
+        })
+    }
+
+    /// Parses the source, reusing the previous tree if it is given.
+    fn parse(
+        &self,
+        source: &str,
+        previous_tree: Option<&tree_sitter::Tree>,
+        cancellation_flag: &dyn CancellationFlag,
+    ) -> Result<tree_sitter::Tree, BuildError> {
+        let mut parser = Parser::new();
+        parser.set_language(self.sgl.language)?;
+        if !self.included_ranges.is_empty() {
+            parser
+                .set_included_ranges(&self.included_ranges)
+                .map_err(|_| BuildError::ParseError)?;
+        }
+        let ts_cancellation_flag = TreeSitterCancellationFlag::from(cancellation_flag);
+        // The parser.set_cancellation_flag` is unsafe, because it does not tie the
+        // lifetime of the parser to the lifetime of the cancellation flag in any way.
+        // To make it more obvious that the parser does not outlive the cancellation flag,
+        // it is kept local to this method.
+        unsafe { parser.set_cancellation_flag(Some(ts_cancellation_flag.as_ref())) };
+        parser
+            .parse(source, previous_tree)
+            .ok_or(BuildError::ParseError)
+    }
+
+    /// Returns the source with the text of all error nodes in the tree replaced by spaces, keeping
+    /// line breaks.  Returns `None` if that does not change the source.
+    fn blank_error_nodes(source: &str, tree: &tree_sitter::Tree) -> Option<String> {
+        let mut bytes = source.as_bytes().to_vec();
+        let mut changed = false;
+        let mut cursor = tree.walk();
+        'walk: loop {
+            let node = cursor.node();
+            if node.is_error() {
+                for byte in &mut bytes[node.byte_range()] {
+                    if !byte.is_ascii_whitespace() {
+                        *byte = b' ';
+                        changed = true;
+                    }
+                }
+            } else if node.has_error() && cursor.goto_first_child() {
+                continue;
+            }
+            while !cursor.goto_next_sibling() {
+                if !cursor.goto_parent() {
+                    break 'walk;
+                }
+            }
+        }
+        if !changed {
+            return None;
+        }
+        // Error nodes start and end at character boundaries, and all bytes of the characters in
+        // them are replaced, so the result is still valid UTF-8.
+        Some(String::from_utf8(bytes).expect("blanked source should be valid UTF-8"))
+    }
 
-        self.load(cancellation_flag)
+    /// Computes the changes between the edited previous tree and the new tree.
+    fn compute_changes(previous: PreviousTree, tree: &tree_sitter::Tree) -> SourceChanges {
+        let mut ranges = previous.edited_ranges;
+        ranges.extend(
+            tree.changed_ranges(&previous.tree)
+                .into_iter()
+                .map(|range| range.start_byte..range.end_byte),
+        );
+        ranges.sort_by_key(|range| (range.start, range.end));
+        let mut merged_ranges: Vec<Range<usize>> = Vec::new();
+        for range in ranges {
+            match merged_ranges.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged_ranges.push(range),
+            }
+        }
+        let only_extras = previous.only_extras_edited
+            && merged_ranges
+                .iter()
+                .all(|range| Self::only_extras_in_range(tree.root_node(), range));
+        SourceChanges {
+            ranges: merged_ranges,
+            only_extras,
+        }
+    }
+
+    /// Returns whether all leaf syntax nodes that overlap with the range are extras.  An empty
+    /// range overlaps with the nodes that strictly contain it, so that joining two tokens is
+    /// considered a change, but removing whitespace between them is not.
+    fn only_extras_in_range(node: tree_sitter::Node, range: &Range<usize>) -> bool {
+        let overlaps = if range.is_empty() {
+            node.start_byte() < range.start && range.start < node.end_byte()
+        } else {
+            node.start_byte() < range.end && range.start < node.end_byte()
+        };
+        if !overlaps || node.is_extra() {
+            return true;
+        }
+        if node.child_count() == 0 {
+            return false;
+        }
+        let mut cursor = node.walk();
+        let result = node
+            .children(&mut cursor)
+            .all(|child| Self::only_extras_in_range(child, range));
+        result
+    }
+
+    /// Maps a byte range of the source before the edit to the source after the edit.  Offsets in
+    /// the replaced region are mapped to the boundaries of the replacement.
+    fn map_range_through_edit(range: &Range<usize>, edit: &InputEdit) -> Range<usize> {
+        let map_offset = |offset: usize, inside: usize| {
+            if offset <= edit.start_byte {
+                offset
+            } else if offset >= edit.old_end_byte {
+                offset - edit.old_end_byte + edit.new_end_byte
+            } else {
+                inside
+            }
+        };
+        map_offset(range.start, edit.start_byte)..map_offset(range.end, edit.new_end_byte)
     }
 
     /// Create a graph node to represent the stack graph node. It is the callers responsibility to
@@ -692,6 +1217,109 @@ impl<'a> Builder<'a> {
         self.injected_node_count += 1;
         node
     }
+
+    /// Finds the injected regions in the tree using the language's injections query.  Returns
+    /// each injection together with the byte ranges of its content nodes, which are used to find
+    /// the host scope of the injection.
+    fn find_injections(&self, tree: &tree_sitter::Tree) -> Vec<(Injection, Vec<Range<usize>>)> {
+        let query = match &self.sgl.injections {
+            Some(query) => query,
+            None => return Vec::new(),
+        };
+        let content_capture = query.capture_index_for_name(INJECTION_CONTENT_CAPTURE);
+        let language_capture = query.capture_index_for_name(INJECTION_LANGUAGE_CAPTURE);
+
+        let mut injections: Vec<(Injection, Vec<Range<usize>>)> = Vec::new();
+        let mut combined_injections = HashMap::new();
+        let mut cursor = QueryCursor::new();
+        for mat in cursor.matches(query, tree.root_node(), self.source.as_bytes()) {
+            let mut language = None;
+            let mut combined = false;
+            let mut include_children = false;
+            for property in query.property_settings(mat.pattern_index) {
+                let key: &str = &property.key;
+                if key == INJECTION_LANGUAGE_PROPERTY {
+                    language = property.value.as_ref().map(|value| value.to_string());
+                } else if key == INJECTION_COMBINED_PROPERTY {
+                    combined = true;
+                } else if key == INJECTION_INCLUDE_CHILDREN_PROPERTY {
+                    include_children = true;
+                }
+            }
+
+            let mut ranges = Vec::new();
+            let mut content_ranges = Vec::new();
+            for capture in mat.captures {
+                if Some(capture.index) == language_capture {
+                    language = Some(self.source[capture.node.byte_range()].to_string());
+                } else if Some(capture.index) == content_capture {
+                    ranges.extend(Self::injection_ranges(capture.node, include_children));
+                    content_ranges.push(capture.node.byte_range());
+                }
+            }
+            let language = match language {
+                Some(language) if !ranges.is_empty() => language,
+                _ => continue,
+            };
+
+            // Combined injections collect the content of all matches of the same pattern.
+            if combined {
+                let key = (mat.pattern_index, language.clone());
+                if let Some(index) = combined_injections.get(&key) {
+                    let combined_injection = &mut injections[*index];
+                    combined_injection.0.ranges.extend(ranges);
+                    combined_injection.1.extend(content_ranges);
+                    continue;
+                }
+                combined_injections.insert(key, injections.len());
+            }
+            injections.push((
+                Injection {
+                    language,
+                    ranges,
+                    scope: None,
+                },
+                content_ranges,
+            ));
+        }
+        injections
+    }
+
+    /// Returns the ranges of an injected syntax node.  Unless `include_children` is set, the
+    /// ranges of the node's children are excluded, as in tree-sitter's own injection handling.
+    fn injection_ranges(
+        node: tree_sitter::Node,
+        include_children: bool,
+    ) -> Vec<tree_sitter::Range> {
+        if include_children {
+            return vec![node.range()];
+        }
+        let mut ranges = Vec::new();
+        let mut start_byte = node.start_byte();
+        let mut start_point = node.start_position();
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            if child.start_byte() > start_byte {
+                ranges.push(tree_sitter::Range {
+                    start_byte,
+                    end_byte: child.start_byte(),
+                    start_point,
+                    end_point: child.start_position(),
+                });
+            }
+            start_byte = child.end_byte();
+            start_point = child.end_position();
+        }
+        if node.end_byte() > start_byte {
+            ranges.push(tree_sitter::Range {
+                start_byte,
+                end_byte: node.end_byte(),
+                start_point,
+                end_point: node.end_position(),
+            });
+        }
+        ranges
+    }
 }
 
 /// Trait to signal that the execution is cancelled
@@ -893,7 +1521,7 @@ impl std::fmt::Display for DisplayBuildErrorPretty<'_> {
 }
 
 impl<'a> Builder<'a> {
-    fn load(mut self, cancellation_flag: &dyn CancellationFlag) -> Result<(), BuildError> {
+    fn load(&mut self, cancellation_flag: &dyn CancellationFlag) -> Result<(), BuildError> {
         let cancellation_flag: &dyn stack_graphs::CancellationFlag = &cancellation_flag;
 
         // By default graph ids are used for stack graph local_ids. A remapping is computed
@@ -917,10 +1545,15 @@ impl<'a> Builder<'a> {
                 .map(|_| panic!("index already remapped"));
         }
 
+        let skipped_nodes = self.find_skipped_nodes();
+
         // First create a stack graph node for each TSG node.  (The skip(...) is because the first
         // DSL nodes that we create are the proxies for the injected stack graph nodes.)
         for node_ref in self.graph.iter_nodes().skip(self.injected_node_count) {
             cancellation_flag.check("loading graph nodes")?;
+            if skipped_nodes.contains(&node_ref.index()) {
+                continue;
+            }
             let node_type = self.get_node_type(node_ref)?;
             let handle = match node_type {
                 NodeType::DropScopes => self.load_drop_scopes(node_ref),
@@ -943,11 +1576,17 @@ impl<'a> Builder<'a> {
         // (Technically the caller could add outgoing nodes from “jump to scope” as well, but those
         // are invalid according to the stack graph semantics and will never be followed.
         for source_ref in self.graph.iter_nodes() {
+            if skipped_nodes.contains(&source_ref.index()) {
+                continue;
+            }
             let source = &self.graph[source_ref];
             let source_node_id = self.node_id_for_graph_node(source_ref);
             let source_handle = self.stack_graph.node_for_id(source_node_id).unwrap();
             for (sink_ref, edge) in source.iter_edges() {
                 cancellation_flag.check("loading graph edges")?;
+                if skipped_nodes.contains(&sink_ref.index()) {
+                    continue;
+                }
                 let precedence = match edge.attributes.get(PRECEDENCE_ATTR) {
                     Some(precedence) => precedence.as_integer()? as i32,
                     None => 0,
@@ -968,6 +1607,65 @@ impl<'a> Builder<'a> {
         Ok(())
     }
 
+    /// Returns the indices of the graph nodes that are skipped because they were created by
+    /// stanzas that matched inside an `ERROR` or `MISSING` syntax node.  Push scoped symbol nodes
+    /// whose scope is skipped are skipped as well.  Nothing is skipped if the language is not
+    /// error tolerant.
+    fn find_skipped_nodes(&self) -> HashSet<usize> {
+        let mut skipped_nodes = HashSet::new();
+        if !self.sgl.error_tolerant {
+            return skipped_nodes;
+        }
+        for node_ref in self.graph.iter_nodes().skip(self.injected_node_count) {
+            let match_node = match self.graph[node_ref]
+                .attributes
+                .get(DEBUG_TSG_MATCH_NODE_ATTR)
+                .and_then(|value| value.as_syntax_node_ref().ok())
+            {
+                Some(match_node) => self.graph[match_node],
+                None => continue,
+            };
+            if Self::is_in_error(match_node) {
+                skipped_nodes.insert(node_ref.index());
+            }
+        }
+        // Scopes can be attached to nodes that are themselves only skipped because of their scope,
+        // so this is repeated until no more nodes are skipped.
+        loop {
+            let skipped_node_count = skipped_nodes.len();
+            for node_ref in self.graph.iter_nodes().skip(self.injected_node_count) {
+                if let Some(scope) = self.graph[node_ref]
+                    .attributes
+                    .get(SCOPE_ATTR)
+                    .and_then(|value| value.as_graph_node_ref().ok())
+                {
+                    if skipped_nodes.contains(&scope.index()) {
+                        skipped_nodes.insert(node_ref.index());
+                    }
+                }
+            }
+            if skipped_nodes.len() == skipped_node_count {
+                break;
+            }
+        }
+        skipped_nodes
+    }
+
+    /// Returns whether the syntax node is missing, or is, or is contained in, an error node.
+    fn is_in_error(node: tree_sitter::Node) -> bool {
+        if node.is_missing() {
+            return true;
+        }
+        let mut node = Some(node);
+        while let Some(current) = node {
+            if current.is_error() {
+                return true;
+            }
+            node = current.parent();
+        }
+        false
+    }
+
     fn get_node_type(&self, node_ref: GraphNodeRef) -> Result<NodeType, BuildError> {
         let node = &self.graph[node_ref];
         let node_type = match node.attributes.get(TYPE_ATTR) {
@@ -1123,6 +1821,11 @@ impl<'a> Builder<'a> {
         let id = self.node_id_for_graph_node(node_ref);
         let is_exported =
             self.load_flag(node, IS_EXPORTED_ATTR)? || self.load_flag(node, IS_ENDPOINT_ATTR)?;
+        if let Some(injection_node) = node.attributes.get(INJECTION_NODE_ATTR) {
+            let injection_node = &self.graph[injection_node.as_syntax_node_ref()?];
+            self.injection_scopes
+                .insert(injection_node.byte_range(), id);
+        }
         self.verify_attributes(node, SCOPE_TYPE, &SCOPE_ATTRS);
         Ok(self.stack_graph.add_scope_node(id, is_exported).unwrap())
     }
@@ -1174,6 +1877,22 @@ impl<'a> Builder<'a> {
             source_info.syntax_type = syntax_type.into();
         }
 
+        if let Some(documentation) = node.attributes.get(DOCUMENTATION_ATTR) {
+            let documentation = match documentation {
+                Value::Null => None,
+                Value::SyntaxNode(syntax_node_ref) => {
+                    let syntax_node = &self.graph[*syntax_node_ref];
+                    Some(&self.source[syntax_node.byte_range()])
+                }
+                value => Some(value.as_str()?),
+            };
+            if let Some(documentation) = documentation {
+                let documentation = self.stack_graph.add_string(documentation);
+                let source_info = self.stack_graph.source_info_mut(node_handle);
+                source_info.documentation = documentation.into();
+            }
+        }
+
         Ok(())
     }
 
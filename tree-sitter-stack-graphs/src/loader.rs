@@ -32,6 +32,8 @@ use crate::StackGraphLanguage;
 
 pub static DEFAULT_TSG_PATHS: Lazy<Vec<LoadPath>> =
     Lazy::new(|| vec![LoadPath::Grammar("queries/stack-graphs".into())]);
+static INJECTIONS_QUERY_PATH: &'static str = "queries/injections.scm";
+
 pub static DEFAULT_BUILTINS_PATHS: Lazy<Vec<LoadPath>> =
     Lazy::new(|| vec![LoadPath::Grammar("queries/builtins".into())]);
 
@@ -112,21 +114,35 @@ impl LanguageConfiguration {
         path: &Path,
         content: &mut dyn ContentProvider,
     ) -> std::io::Result<Option<&'a LanguageConfiguration>> {
+        Ok(Self::best_index_for_file(languages, path, content)?.map(|index| &languages[index]))
+    }
+
+    fn best_index_for_file(
+        languages: &Vec<LanguageConfiguration>,
+        path: &Path,
+        content: &mut dyn ContentProvider,
+    ) -> std::io::Result<Option<usize>> {
         let mut best_score = -1isize;
         let mut best = None;
-        for language in languages {
+        for (index, language) in languages.iter().enumerate() {
             if let Some(score) =
                 matches_file(&language.file_types, &language.content_regex, path, content)?
             {
                 if score > best_score {
                     best_score = score;
-                    best = Some(language);
+                    best = Some(index);
                 }
             }
         }
         Ok(best)
     }
 
+    /// Returns whether the language has the given name. A language matches a name if its scope is
+    /// the name, or `source.` followed by the name, or if the name is one of its file types.
+    pub fn matches_name(&self, language_name: &str) -> bool {
+        matches_injection(&self.scope, &self.file_types, &None, language_name)
+    }
+
     pub fn matches_file(
         &self,
         path: &Path,
@@ -225,6 +241,7 @@ impl Loader {
             tsg_paths,
             builtins_paths,
             cache: Vec::new(),
+            error_tolerant: false,
         })))
     }
 
@@ -241,6 +258,7 @@ impl Loader {
             tsg_paths,
             builtins_paths,
             cache: Vec::new(),
+            error_tolerant: false,
         })))
     }
 
@@ -257,6 +275,21 @@ impl Loader {
         })))
     }
 
+    /// Create a loader for the languages of the plugins at the given paths. See the
+    /// [`plugin`][crate::plugin] module for details.
+    #[cfg(feature = "plugins")]
+    pub fn from_plugins(
+        paths: Vec<PathBuf>,
+        scope: Option<String>,
+        cancellation_flag: &dyn CancellationFlag,
+    ) -> Result<Self, LoadError<'static>> {
+        let mut configurations = Vec::new();
+        for path in paths {
+            configurations.extend(crate::plugin::load_plugin(&path, cancellation_flag)?);
+        }
+        Self::from_language_configurations(configurations, scope)
+    }
+
     /// Load a Tree-sitter language for the given file. Loading is based on the loader configuration and the given file path.
     /// Most users should use [`Self::load_for_file`], but this method can be useful if only the underlying Tree-sitter language
     /// is necessary, as it will not attempt to load the TSG file.
@@ -286,6 +319,71 @@ impl Loader {
         }
     }
 
+    /// Load the primary stack graph language for the given file, and return the index of its
+    /// configuration in [`Self::iter_loaded_language_configurations`][], or `None` if the file is
+    /// not supported. Loading more languages does not change the index of a loaded configuration.
+    pub fn load_index_for_file(
+        &mut self,
+        path: &Path,
+        content: &mut dyn ContentProvider,
+        cancellation_flag: &dyn CancellationFlag,
+    ) -> Result<Option<usize>, LoadError<'static>> {
+        match &mut self.0 {
+            LoaderImpl::Paths(loader) => {
+                loader.load_index_for_file(path, content, cancellation_flag)
+            }
+            LoaderImpl::Provided(loader) => Ok(LanguageConfiguration::best_index_for_file(
+                &loader.configurations,
+                path,
+                content,
+            )?),
+        }
+    }
+
+    /// Load the stack graph language for an injected region, which is selected by the language
+    /// name given by the injections query.  A language matches the name if its scope is the name,
+    /// or `source.` followed by the name, or if the name is one of its file types.  Languages
+    /// loaded from tree-sitter grammars also match if their injection regex matches the name.
+    pub fn load_for_injection<'a>(
+        &'a mut self,
+        language_name: &str,
+        cancellation_flag: &dyn CancellationFlag,
+    ) -> Result<Option<&'a LanguageConfiguration>, LoadError<'static>> {
+        match &mut self.0 {
+            LoaderImpl::Paths(loader) => {
+                loader.load_for_injection(language_name, cancellation_flag)
+            }
+            LoaderImpl::Provided(loader) => Ok(loader.load_for_injection(language_name)),
+        }
+    }
+
+    /// Sets whether the languages of this loader build stack graphs for source files with parse
+    /// errors.  This applies to languages that were already loaded, as well as to languages that
+    /// are loaded later.  Builtins are always required to parse without errors.
+    pub fn set_error_tolerant(&mut self, error_tolerant: bool) {
+        match &mut self.0 {
+            LoaderImpl::Paths(loader) => loader.set_error_tolerant(error_tolerant),
+            LoaderImpl::Provided(loader) => {
+                for lc in &mut loader.configurations {
+                    lc.sgl.set_error_tolerant(error_tolerant);
+                }
+            }
+        }
+    }
+
+    /// Iterate over all language configurations that have been loaded so far. Loaded language
+    /// configurations are never removed, so the position of a language configuration in this
+    /// iterator does not change when more languages are loaded.
+    pub fn iter_loaded_language_configurations(
+        &self,
+    ) -> impl Iterator<Item = &LanguageConfiguration> + '_ {
+        let configurations: Box<dyn Iterator<Item = &LanguageConfiguration>> = match &self.0 {
+            LoaderImpl::Paths(loader) => Box::new(loader.cache.iter().map(|(_, lc)| lc)),
+            LoaderImpl::Provided(loader) => Box::new(loader.configurations.iter()),
+        };
+        configurations
+    }
+
     pub fn load_globals_from_config_path(
         path: &Path,
         globals: &mut Variables,
@@ -404,6 +502,8 @@ pub enum LoadError<'a> {
     NoLanguagesFound(String),
     #[error("No TSG file found")]
     NoTsgFound,
+    #[error("{0}")]
+    Plugin(String),
     #[error(transparent)]
     Reader(Box<dyn std::error::Error + Send + Sync>),
     #[error("{inner}")]
@@ -507,6 +607,13 @@ impl LanguageConfigurationsLoader {
         }
         Ok(FileLanguageConfigurations { primary, secondary })
     }
+
+    /// Load a stack graph language for an injected region, selected by the given language name.
+    pub fn load_for_injection(&self, language_name: &str) -> Option<&LanguageConfiguration> {
+        self.configurations
+            .iter()
+            .find(|lc| lc.matches_name(language_name))
+    }
 }
 
 // ------------------------------------------------------------------------------------------------
@@ -519,6 +626,7 @@ struct PathLoader {
     tsg_paths: Vec<LoadPath>,
     builtins_paths: Vec<LoadPath>,
     cache: Vec<(Language, LanguageConfiguration)>,
+    error_tolerant: bool,
 }
 
 impl PathLoader {
@@ -569,13 +677,86 @@ impl PathLoader {
             Some(selected_language) => selected_language.clone(),
             None => return Ok(FileLanguageConfigurations::default()),
         };
+        let lc = self.load_language(language, cancellation_flag)?;
+        Ok(FileLanguageConfigurations {
+            primary: Some(lc),
+            secondary: Vec::default(),
+        })
+    }
+
+    pub fn load_index_for_file(
+        &mut self,
+        path: &Path,
+        content: &mut dyn ContentProvider,
+        cancellation_flag: &dyn CancellationFlag,
+    ) -> Result<Option<usize>, LoadError<'static>> {
+        let selected_language = self.select_language_for_file(path, content)?;
+        let language = match selected_language {
+            Some(selected_language) => selected_language.clone(),
+            None => return Ok(None),
+        };
+        Ok(Some(self.load_language_index(language, cancellation_flag)?))
+    }
+
+    pub fn load_for_injection<'a>(
+        &'a mut self,
+        language_name: &str,
+        cancellation_flag: &dyn CancellationFlag,
+    ) -> Result<Option<&'a LanguageConfiguration>, LoadError<'static>> {
+        let mut selected_language = None;
+        for path in &self.paths {
+            let languages = match self.loader.languages_at_path(path, self.scope.as_deref()) {
+                Ok(languages) => languages,
+                Err(LoadError::NoLanguagesFound(_)) => continue,
+                Err(LoadError::TreeSitter(err)) => return Err(LoadError::TreeSitter(err)),
+                Err(err) => return Err(LoadError::Reader(err.to_string().into())),
+            };
+            selected_language = languages
+                .into_iter()
+                .find(|language| language.matches_injection(language_name))
+                .cloned();
+            if selected_language.is_some() {
+                break;
+            }
+        }
+        match selected_language {
+            Some(language) => Ok(Some(self.load_language(language, cancellation_flag)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn set_error_tolerant(&mut self, error_tolerant: bool) {
+        self.error_tolerant = error_tolerant;
+        for (_, lc) in &mut self.cache {
+            lc.sgl.set_error_tolerant(error_tolerant);
+        }
+    }
+
+    // Load the language configuration for the given language, or return it from the cache if it
+    // was loaded before
+    fn load_language(
+        &mut self,
+        language: SupplementedLanguage,
+        cancellation_flag: &dyn CancellationFlag,
+    ) -> Result<&LanguageConfiguration, LoadError<'static>> {
+        let index = self.load_language_index(language, cancellation_flag)?;
+        Ok(&self.cache[index].1)
+    }
+
+    // Load the language configuration for the given language, and return its index in the cache
+    fn load_language_index(
+        &mut self,
+        language: SupplementedLanguage,
+        cancellation_flag: &dyn CancellationFlag,
+    ) -> Result<usize, LoadError<'static>> {
         // the borrow checker is a hard master...
         let index = self.cache.iter().position(|e| &e.0 == &language.language);
         let index = match index {
             Some(index) => index,
             None => {
                 let tsg = self.load_tsg_from_paths(&language)?;
-                let sgl = StackGraphLanguage::new(language.language, tsg);
+                let mut sgl = StackGraphLanguage::new(language.language, tsg);
+                self.load_injections_query_from_path(&language, &mut sgl)?;
 
                 let mut builtins = StackGraph::new();
                 self.load_builtins_from_paths_into(
@@ -584,6 +765,7 @@ impl PathLoader {
                     &mut builtins,
                     cancellation_flag,
                 )?;
+                sgl.set_error_tolerant(self.error_tolerant);
 
                 let lc = LanguageConfiguration {
                     language: language.language,
@@ -601,11 +783,7 @@ impl PathLoader {
                 self.cache.len() - 1
             }
         };
-        let lc = &self.cache[index].1;
-        Ok(FileLanguageConfigurations {
-            primary: Some(lc),
-            secondary: Vec::default(),
-        })
+        Ok(index)
     }
 
     // Select language for the given file, considering paths and scope fields
@@ -686,6 +864,25 @@ impl PathLoader {
         return Err(LoadError::NoTsgFound);
     }
 
+    // Load the injections query for the given language, if the grammar has one
+    fn load_injections_query_from_path(
+        &self,
+        language: &SupplementedLanguage,
+        sgl: &mut StackGraphLanguage,
+    ) -> Result<(), LoadError<'static>> {
+        let injections_path = language.root_path.join(INJECTIONS_QUERY_PATH);
+        if !injections_path.exists() {
+            return Ok(());
+        }
+        let injections_source = std::fs::read_to_string(&injections_path)?;
+        sgl.set_injections_query(&injections_source)
+            .map_err(|err| LoadError::SglParse {
+                inner: err,
+                tsg_path: injections_path,
+                tsg: Cow::from(injections_source),
+            })
+    }
+
     // Builtins are loaded from queries/builtins.EXT and an optional queries/builtins.cfg configuration.
     // In the future, we may extend this to support builtins spread over multiple files queries/builtins/NAME.EXT
     // and optional corresponding configuration files queries/builtins/NAME.cfg.
@@ -789,6 +986,7 @@ struct SupplementedLanguage {
     pub language: Language,
     pub scope: Option<String>,
     pub content_regex: Option<Regex>,
+    pub injection_regex: Option<Regex>,
     pub file_types: Vec<String>,
     pub root_path: PathBuf,
 }
@@ -798,6 +996,15 @@ impl SupplementedLanguage {
         self.scope.as_ref().map_or(false, |s| s == scope)
     }
 
+    pub fn matches_injection(&self, language_name: &str) -> bool {
+        matches_injection(
+            &self.scope,
+            &self.file_types,
+            &self.injection_regex,
+            language_name,
+        )
+    }
+
     // Extracted from tree_sitter_loader::Loader::language_configuration_for_file_name
     pub fn matches_file(
         &self,
@@ -832,6 +1039,7 @@ impl From<(Language, &TSLanguageConfiguration<'_>)> for SupplementedLanguage {
         Self {
             scope: config.scope.clone(),
             content_regex: config.content_regex.clone(),
+            injection_regex: config.injection_regex.clone(),
             file_types: config.file_types.clone(),
             root_path: config.root_path.clone(),
             language,
@@ -839,6 +1047,21 @@ impl From<(Language, &TSLanguageConfiguration<'_>)> for SupplementedLanguage {
     }
 }
 
+// Match an injection language name against a language's scope, file types, and injection regex
+fn matches_injection(
+    scope: &Option<String>,
+    file_types: &Vec<String>,
+    injection_regex: &Option<Regex>,
+    language_name: &str,
+) -> bool {
+    scope.as_deref().map_or(false, |scope| {
+        scope == language_name || scope.strip_prefix("source.") == Some(language_name)
+    }) || file_types.iter().any(|ft| ft == language_name)
+        || injection_regex
+            .as_ref()
+            .map_or(false, |regex| regex.is_match(language_name))
+}
+
 // Extracted from tree_sitter_loader::Loader::language_configuration_for_file_name
 pub fn matches_file(
     file_types: &Vec<String>,
@@ -0,0 +1,196 @@
+// -*- coding: utf-8 -*-
+// ------------------------------------------------------------------------------------------------
+// Copyright © 2023, stack-graphs authors.
+// Licensed under either of Apache License, Version 2.0, or MIT license, at your option.
+// Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
+// ------------------------------------------------------------------------------------------------
+
+//! Defines language plugins, which make languages available to a prebuilt program as shared
+//! libraries.
+//!
+//! A plugin is a `cdylib` crate that exports a [`PluginDeclaration`][] using the
+//! [`export_plugin!`][crate::export_plugin] macro. The declaration lists the languages of the
+//! plugin, each with its tree-sitter language, TSG source, optional builtins, and any special file
+//! analyzers. The TSG and builtins sources are compiled when the plugin is loaded, so the program
+//! loading the plugin does not need a C compiler or any of the grammar sources.
+//!
+//! ``` ignore
+//! use tree_sitter_stack_graphs::loader::FileAnalyzers;
+//! use tree_sitter_stack_graphs::plugin::PluginLanguage;
+//!
+//! fn languages() -> Vec<PluginLanguage> {
+//!     vec![PluginLanguage {
+//!         language: tree_sitter_python::language(),
+//!         scope: Some("source.python".into()),
+//!         content_regex: None,
+//!         file_types: vec!["py".into()],
+//!         tsg_path: "src/stack-graphs.tsg",
+//!         tsg_source: include_str!("../src/stack-graphs.tsg"),
+//!         builtins: None,
+//!         builtins_config: None,
+//!         special_files: FileAnalyzers::new(),
+//!         no_similar_paths_in_file: false,
+//!     }]
+//! }
+//!
+//! tree_sitter_stack_graphs::export_plugin!(languages);
+//! ```
+//!
+//! Rust has no stable ABI, so a plugin must be compiled with the same compiler and against the same
+//! version of this crate as the program that loads it. Both are checked when the plugin is loaded.
+//! Loading plugins requires the `plugins` feature.
+
+use regex::Regex;
+#[cfg(feature = "plugins")]
+use std::path::Path;
+use std::path::PathBuf;
+use tree_sitter::Language;
+
+use crate::loader::FileAnalyzers;
+use crate::loader::LanguageConfiguration;
+use crate::loader::LoadError;
+use crate::CancellationFlag;
+
+/// The version of the plugin interface. This is incremented whenever [`PluginDeclaration`][] or
+/// [`PluginLanguage`][] change.
+pub const PLUGIN_API_VERSION: u32 = 1;
+
+/// The version of this crate, which plugins must match.
+pub const CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The version of the compiler used to build this crate, which plugins must match.
+pub const RUSTC_VERSION: &str = env!("TSSG_RUSTC_VERSION");
+
+/// The name of the symbol that plugins export their declaration as.
+pub const PLUGIN_DECLARATION_SYMBOL: &str = "tree_sitter_stack_graphs_plugin";
+
+/// A language provided by a plugin.
+pub struct PluginLanguage {
+    pub language: Language,
+    pub scope: Option<String>,
+    /// A regular expression that is matched against file content, compiled when the plugin is
+    /// loaded.
+    pub content_regex: Option<&'static str>,
+    pub file_types: Vec<String>,
+    /// The path of the TSG file, used for informational purposes only.
+    pub tsg_path: &'static str,
+    pub tsg_source: &'static str,
+    /// The path and source of the builtins file.
+    pub builtins: Option<(&'static str, &'static str)>,
+    pub builtins_config: Option<&'static str>,
+    pub special_files: FileAnalyzers,
+    pub no_similar_paths_in_file: bool,
+}
+
+impl PluginLanguage {
+    /// Build a language configuration for this language, compiling its TSG and builtins sources.
+    pub fn into_language_configuration(
+        self,
+        cancellation_flag: &dyn CancellationFlag,
+    ) -> Result<LanguageConfiguration, LoadError<'static>> {
+        let content_regex = self
+            .content_regex
+            .map(Regex::new)
+            .transpose()
+            .map_err(|err| LoadError::Reader(Box::new(err)))?;
+        let mut lc = LanguageConfiguration::from_sources(
+            self.language,
+            self.scope,
+            content_regex,
+            self.file_types,
+            PathBuf::from(self.tsg_path),
+            self.tsg_source,
+            self.builtins
+                .map(|(path, source)| (PathBuf::from(path), source)),
+            self.builtins_config,
+            cancellation_flag,
+        )?;
+        lc.special_files = self.special_files;
+        lc.no_similar_paths_in_file = self.no_similar_paths_in_file;
+        Ok(lc)
+    }
+}
+
+/// The declaration a plugin exports, using [`export_plugin!`][crate::export_plugin].
+#[repr(C)]
+pub struct PluginDeclaration {
+    pub api_version: u32,
+    pub rustc_version: &'static str,
+    pub crate_version: &'static str,
+    pub languages: fn() -> Vec<PluginLanguage>,
+}
+
+impl PluginDeclaration {
+    /// Check that the plugin is compatible with this crate, and build language configurations for
+    /// all its languages.
+    pub fn language_configurations(
+        &self,
+        cancellation_flag: &dyn CancellationFlag,
+    ) -> Result<Vec<LanguageConfiguration>, LoadError<'static>> {
+        if self.api_version != PLUGIN_API_VERSION {
+            return Err(LoadError::Plugin(format!(
+                "plugin API version {} is incompatible with version {}",
+                self.api_version, PLUGIN_API_VERSION
+            )));
+        }
+        if self.rustc_version != RUSTC_VERSION {
+            return Err(LoadError::Plugin(format!(
+                "plugin built with {} is incompatible with {}",
+                self.rustc_version, RUSTC_VERSION
+            )));
+        }
+        if self.crate_version != CRATE_VERSION {
+            return Err(LoadError::Plugin(format!(
+                "plugin built against tree-sitter-stack-graphs {} is incompatible with {}",
+                self.crate_version, CRATE_VERSION
+            )));
+        }
+        (self.languages)()
+            .into_iter()
+            .map(|language| language.into_language_configuration(cancellation_flag))
+            .collect()
+    }
+}
+
+/// Export a plugin declaration from a `cdylib` crate. The argument is a function of type
+/// `fn() -> Vec<PluginLanguage>` that returns the languages of the plugin.
+#[macro_export]
+macro_rules! export_plugin {
+    ($languages:expr) => {
+        #[doc(hidden)]
+        #[allow(non_upper_case_globals)]
+        #[no_mangle]
+        pub static tree_sitter_stack_graphs_plugin: $crate::plugin::PluginDeclaration =
+            $crate::plugin::PluginDeclaration {
+                api_version: $crate::plugin::PLUGIN_API_VERSION,
+                rustc_version: $crate::plugin::RUSTC_VERSION,
+                crate_version: $crate::plugin::CRATE_VERSION,
+                languages: $languages,
+            };
+    };
+}
+
+/// Load the plugin in the shared library at the given path, and build language configurations for
+/// all its languages. The library is never unloaded, because the language configurations refer to
+/// code and data in it.
+#[cfg(feature = "plugins")]
+pub fn load_plugin(
+    path: &Path,
+    cancellation_flag: &dyn CancellationFlag,
+) -> Result<Vec<LanguageConfiguration>, LoadError<'static>> {
+    let library = unsafe { libloading::Library::new(path) }.map_err(|err| {
+        LoadError::Plugin(format!("cannot open plugin {}: {}", path.display(), err))
+    })?;
+    let library: &'static libloading::Library = Box::leak(Box::new(library));
+    let declaration =
+        unsafe { library.get::<*const PluginDeclaration>(PLUGIN_DECLARATION_SYMBOL.as_bytes()) }
+            .map_err(|err| {
+                LoadError::Plugin(format!(
+                    "{} is not a stack graphs plugin: {}",
+                    path.display(),
+                    err
+                ))
+            })?;
+    let declaration: &'static PluginDeclaration = unsafe { &**declaration };
+    declaration.language_configurations(cancellation_flag)
+}
@@ -36,6 +36,17 @@
 //!    with the given names.
 //!  - `refers`: takes a comma-separated list of names, and expects references at this position
 //!    with the given names.
+//!  - `defined_syntax_type`: takes a single syntax type, and expects a reference at this position
+//!    to resolve only to definitions with that syntax type.
+//!  - `defined_fully_qualified_name`: takes a single name, and expects a reference at this position
+//!    to resolve only to definitions with that fully qualified name.
+//!  - `defined_uniquely`: takes no values, and expects a reference at this position to resolve to
+//!    exactly one definition.
+//!  - `undefined`: takes no values, and expects a reference at this position to not resolve to
+//!    any definitions.
+//!
+//! Line numbers in `defined` assertions can be prefixed with the path of a test fragment, such as
+//! `defined: other.py:3`, to require that the definition is part of that fragment.
 //!
 //! ## Fragments for multi-file testing
 //!
@@ -56,7 +67,24 @@
 //! to a fragment.
 //!
 //! Any content before the first fragment header of the file is ignored, and will not be part of the test.
+//!
+//! ## Resolution snapshots
+//!
+//! Instead of writing assertions by hand, the resolution of every reference in a test can be recorded
+//! in a [`ResolutionSnapshot`]. A snapshot contains one line per reference, listing the position and
+//! symbol of the reference, followed by the positions of the definitions it resolves to:
+//!
+//! ``` skip
+//! one.py:5:7 x -> one.py:2:1
+//! one.py:5:10 z ->
+//! ```
+//!
+//! Positions in a test without fragments use the file name of the test, and positions in fragments
+//! use the fragment path, so that snapshots do not depend on the directory tests are run from.
+//! Snapshots can be compared against the snapshot of a later run using [`Test::check_snapshot`],
+//! which ignores the order of the entries, but not how often they occur.
 
+use itertools::EitherOrBoth;
 use itertools::Itertools;
 use lsp_positions::Position;
 use lsp_positions::PositionedSubstring;
@@ -72,8 +100,11 @@ use stack_graphs::graph::File;
 use stack_graphs::graph::Node;
 use stack_graphs::graph::SourceInfo;
 use stack_graphs::graph::StackGraph;
+use stack_graphs::partial::PartialPath;
 use stack_graphs::partial::PartialPaths;
 use stack_graphs::stitching::Database;
+use stack_graphs::stitching::DatabaseCandidates;
+use stack_graphs::stitching::ForwardPartialPathStitcher;
 use stack_graphs::stitching::StitcherConfig;
 use std::collections::HashMap;
 use std::path::Path;
@@ -86,6 +117,11 @@ use crate::CancellationFlag;
 const DEFINED: &'static str = "defined";
 const DEFINES: &'static str = "defines";
 const REFERS: &'static str = "refers";
+const DEFINED_SYNTAX_TYPE: &'static str = "defined_syntax_type";
+const DEFINED_FULLY_QUALIFIED_NAME: &'static str = "defined_fully_qualified_name";
+const DEFINED_UNIQUELY: &'static str = "defined_uniquely";
+const UNDEFINED: &'static str = "undefined";
+const SNAPSHOT: &'static str = "snapshot";
 
 static PATH_REGEX: Lazy<Regex> =
     Lazy::new(|| Regex::new(r#"---\s*path:\s*([^\s]+)\s*---"#).unwrap());
@@ -93,7 +129,7 @@ static GLOBAL_REGEX: Lazy<Regex> =
     Lazy::new(|| Regex::new(r#"---\s*global:\s*([^\s]+)=([^\s]+)\s*---"#).unwrap());
 static ASSERTION_REGEX: Lazy<Regex> =
     Lazy::new(|| Regex::new(r#"(\^)\s*(\w+):\s*([^\s,]+(?:\s*,\s*[^\s,]+)*)?"#).unwrap());
-static LINE_NUMBER_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r#"\d+"#).unwrap());
+static TARGET_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r#"(?:([^\s,]+):)?(\d+)"#).unwrap());
 static NAME_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r#"[^\s,]+"#).unwrap());
 
 /// An error that can occur while parsing tests
@@ -105,6 +141,8 @@ pub enum TestError {
     GlobalBeforeFirstFragment(usize),
     InvalidAssertion(usize, String),
     InvalidColumn(usize, usize, usize),
+    LineNotInFragment(usize, usize, String),
+    UnknownFragmentPath(usize, String),
 }
 
 impl std::fmt::Display for TestError {
@@ -141,6 +179,23 @@ impl std::fmt::Display for TestError {
                 column + 1,
                 regular_line + 1
             ),
+            Self::LineNotInFragment(line, target_line, path) => {
+                write!(
+                    f,
+                    "Assertion on line {} refers to line {} outside fragment {}",
+                    line + 1,
+                    target_line + 1,
+                    path
+                )
+            }
+            Self::UnknownFragmentPath(line, path) => {
+                write!(
+                    f,
+                    "Assertion on line {} refers to unknown fragment {}",
+                    line + 1,
+                    path
+                )
+            }
         }
     }
 }
@@ -150,6 +205,8 @@ pub struct Test {
     pub path: PathBuf,
     pub fragments: Vec<TestFragment>,
     pub graph: StackGraph,
+    /// The file of the single fragment of a test without `path` sections, if any.
+    default_fragment: Option<Handle<File>>,
 }
 
 /// A fragment from a stack graph test
@@ -265,10 +322,16 @@ impl Test {
                 .parse_assertions(&mut graph, |line| line_files.get(line).cloned().flatten())?;
         }
 
+        let default_fragment = if have_fragments {
+            None
+        } else {
+            fragments.first().map(|f| f.file)
+        };
         Ok(Self {
             path: path.to_path_buf(),
             fragments,
             graph,
+            default_fragment,
         })
     }
 
@@ -346,13 +409,32 @@ impl TestFragment {
                 match assertion_match.as_str() {
                     DEFINED => {
                         let mut targets = Vec::new();
-                        for line in LINE_NUMBER_REGEX
-                            .find_iter(values_match.map(|m| m.as_str()).unwrap_or(""))
+                        for target in TARGET_REGEX
+                            .captures_iter(values_match.map(|m| m.as_str()).unwrap_or(""))
                         {
-                            let line = line.as_str().parse::<usize>().unwrap() - 1;
-                            let file = line_file(line).ok_or(
-                                TestError::AssertionRefersToNonSourceLine(current_line_number),
-                            )?;
+                            let line =
+                                target.get(2).unwrap().as_str().parse::<usize>().unwrap() - 1;
+                            let file = match target.get(1) {
+                                Some(path) => {
+                                    let file = graph.get_file(path.as_str()).ok_or_else(|| {
+                                        TestError::UnknownFragmentPath(
+                                            current_line_number,
+                                            path.as_str().to_string(),
+                                        )
+                                    })?;
+                                    if line_file(line) != Some(file) {
+                                        return Err(TestError::LineNotInFragment(
+                                            current_line_number,
+                                            line,
+                                            path.as_str().to_string(),
+                                        ));
+                                    }
+                                    file
+                                }
+                                None => line_file(line).ok_or(
+                                    TestError::AssertionRefersToNonSourceLine(current_line_number),
+                                )?,
+                            };
                             targets.push(AssertionTarget { file, line });
                         }
                         self.assertions.push(Assertion::Defined { source, targets });
@@ -377,6 +459,46 @@ impl TestFragment {
                         }
                         self.assertions.push(Assertion::Refers { source, symbols });
                     }
+                    DEFINED_SYNTAX_TYPE => {
+                        let syntax_type = Self::single_value(
+                            current_line_number,
+                            assertion_match.as_str(),
+                            values_match.map(|m| m.as_str()),
+                        )?;
+                        let syntax_type = graph.add_string(syntax_type);
+                        self.assertions.push(Assertion::DefinedSyntaxType {
+                            source,
+                            syntax_type,
+                        });
+                    }
+                    DEFINED_FULLY_QUALIFIED_NAME => {
+                        let fully_qualified_name = Self::single_value(
+                            current_line_number,
+                            assertion_match.as_str(),
+                            values_match.map(|m| m.as_str()),
+                        )?;
+                        let fully_qualified_name = graph.add_string(fully_qualified_name);
+                        self.assertions.push(Assertion::DefinedFullyQualifiedName {
+                            source,
+                            fully_qualified_name,
+                        });
+                    }
+                    DEFINED_UNIQUELY => {
+                        Self::no_values(
+                            current_line_number,
+                            assertion_match.as_str(),
+                            values_match.map(|m| m.as_str()),
+                        )?;
+                        self.assertions.push(Assertion::DefinedUniquely { source });
+                    }
+                    UNDEFINED => {
+                        Self::no_values(
+                            current_line_number,
+                            assertion_match.as_str(),
+                            values_match.map(|m| m.as_str()),
+                        )?;
+                        self.assertions.push(Assertion::Undefined { source });
+                    }
                     _ => {
                         return Err(TestError::InvalidAssertion(
                             current_line_number,
@@ -393,29 +515,67 @@ impl TestFragment {
 
         Ok(())
     }
+
+    /// Returns the single value of an assertion, or an error if there are no or multiple values.
+    fn single_value<'a>(
+        line: usize,
+        assertion: &str,
+        values: Option<&'a str>,
+    ) -> Result<&'a str, TestError> {
+        let mut names = NAME_REGEX.find_iter(values.unwrap_or(""));
+        match (names.next(), names.next()) {
+            (Some(name), None) => Ok(name.as_str()),
+            _ => Err(TestError::InvalidAssertion(line, assertion.to_string())),
+        }
+    }
+
+    /// Returns an error if the assertion has any values.
+    fn no_values(line: usize, assertion: &str, values: Option<&str>) -> Result<(), TestError> {
+        if values.map_or(false, |v| !v.trim().is_empty()) {
+            return Err(TestError::InvalidAssertion(line, assertion.to_string()));
+        }
+        Ok(())
+    }
 }
 
 /// Result of running a stack graph test.
 #[derive(Debug, Clone)]
 pub struct TestResult {
     success_count: usize,
-    failures: Vec<TestFailure>,
+    assertion_results: Vec<AssertionResult>,
 }
 
 impl TestResult {
     pub fn new() -> Self {
         Self {
-            failures: Vec::new(),
             success_count: 0,
+            assertion_results: Vec::new(),
         }
     }
 
-    fn add_success(&mut self) {
+    fn add_success(&mut self, path: PathBuf, position: Position, kind: &'static str) {
         self.success_count += 1;
+        self.assertion_results.push(AssertionResult {
+            path,
+            position,
+            kind,
+            failure: None,
+        });
     }
 
-    fn add_failure(&mut self, reason: TestFailure) {
-        self.failures.push(reason);
+    fn add_failure(
+        &mut self,
+        path: PathBuf,
+        position: Position,
+        kind: &'static str,
+        reason: TestFailure,
+    ) {
+        self.assertion_results.push(AssertionResult {
+            path,
+            position,
+            kind,
+            failure: Some(reason),
+        });
     }
 
     /// Number of successfull assertions.
@@ -425,15 +585,22 @@ impl TestResult {
 
     /// Number of failed assertions.
     pub fn failure_count(&self) -> usize {
-        self.failures.len()
+        self.failures_iter().count()
     }
 
-    pub fn failures_iter(&self) -> std::slice::Iter<'_, TestFailure> {
-        self.failures.iter()
+    pub fn failures_iter(&self) -> impl Iterator<Item = &TestFailure> + '_ {
+        self.assertion_results
+            .iter()
+            .filter_map(|r| r.failure.as_ref())
     }
 
-    pub fn into_failures_iter(self) -> std::vec::IntoIter<TestFailure> {
-        self.failures.into_iter()
+    pub fn into_failures_iter(self) -> impl Iterator<Item = TestFailure> {
+        self.assertion_results.into_iter().filter_map(|r| r.failure)
+    }
+
+    /// Iterate over the results of all assertions that were run, in the order they were run.
+    pub fn assertion_results_iter(&self) -> std::slice::Iter<'_, AssertionResult> {
+        self.assertion_results.iter()
     }
 
     /// Total number of assertions that were run.
@@ -443,8 +610,8 @@ impl TestResult {
 
     pub fn absorb(&mut self, other: TestResult) {
         self.success_count += other.success_count;
-        let mut failures = other.failures;
-        self.failures.append(&mut failures);
+        let mut assertion_results = other.assertion_results;
+        self.assertion_results.append(&mut assertion_results);
     }
 }
 
@@ -460,6 +627,38 @@ impl std::fmt::Display for TestResult {
     }
 }
 
+/// Result of a single assertion of a stack graph test.
+#[derive(Debug, Clone)]
+pub struct AssertionResult {
+    /// Path of the test fragment that contains the assertion.
+    pub path: PathBuf,
+    /// Position the assertion applies to.
+    pub position: Position,
+    /// Kind of the assertion, such as `defined` or `refers`.
+    pub kind: &'static str,
+    /// The failure, if the assertion did not succeed.
+    pub failure: Option<TestFailure>,
+}
+
+impl AssertionResult {
+    pub fn is_success(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+impl std::fmt::Display for AssertionResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: {}",
+            self.path.display(),
+            self.position.line + 1,
+            self.position.column.grapheme_offset + 1,
+            self.kind
+        )
+    }
+}
+
 /// Description of test failures.
 // This mirrors AssertionError, but provides cleaner error messages. The underlying
 // assertions report errors in terms of the virtual files in the test. This type
@@ -491,6 +690,42 @@ pub enum TestFailure {
         missing_symbols: Vec<String>,
         unexpected_symbols: Vec<String>,
     },
+    NoDefinitions {
+        path: PathBuf,
+        position: Position,
+        references: Vec<String>,
+    },
+    IncorrectDefinitionSyntaxTypes {
+        path: PathBuf,
+        position: Position,
+        references: Vec<String>,
+        syntax_type: String,
+        unexpected_definitions: Vec<String>,
+    },
+    IncorrectDefinitionFullyQualifiedNames {
+        path: PathBuf,
+        position: Position,
+        references: Vec<String>,
+        fully_qualified_name: String,
+        unexpected_definitions: Vec<String>,
+    },
+    NotUniquelyDefined {
+        path: PathBuf,
+        position: Position,
+        references: Vec<String>,
+        definitions: Vec<String>,
+    },
+    UnexpectedlyDefined {
+        path: PathBuf,
+        position: Position,
+        references: Vec<String>,
+        definitions: Vec<String>,
+    },
+    IncorrectSnapshot {
+        path: PathBuf,
+        missing_entries: Vec<String>,
+        unexpected_entries: Vec<String>,
+    },
     Cancelled(stack_graphs::CancellationError),
 }
 
@@ -611,6 +846,129 @@ impl std::fmt::Display for TestFailure {
                 }
                 Ok(())
             }
+            Self::NoDefinitions {
+                path,
+                position,
+                references,
+            } => {
+                write!(
+                    f,
+                    "{}:{}:{}: no definitions found for reference(s)",
+                    path.display(),
+                    position.line + 1,
+                    position.column.grapheme_offset + 1
+                )?;
+                for reference in references {
+                    write!(f, " ‘{}’", reference)?;
+                }
+                Ok(())
+            }
+            Self::IncorrectDefinitionSyntaxTypes {
+                path,
+                position,
+                references,
+                syntax_type,
+                unexpected_definitions,
+            } => {
+                write!(
+                    f,
+                    "{}:{}:{}: definition(s) for reference(s)",
+                    path.display(),
+                    position.line + 1,
+                    position.column.grapheme_offset + 1
+                )?;
+                for reference in references {
+                    write!(f, " ‘{}’", reference)?;
+                }
+                write!(
+                    f,
+                    " expected syntax type ‘{}’ found unexpected {}",
+                    syntax_type,
+                    unexpected_definitions.iter().format(", ")
+                )
+            }
+            Self::IncorrectDefinitionFullyQualifiedNames {
+                path,
+                position,
+                references,
+                fully_qualified_name,
+                unexpected_definitions,
+            } => {
+                write!(
+                    f,
+                    "{}:{}:{}: definition(s) for reference(s)",
+                    path.display(),
+                    position.line + 1,
+                    position.column.grapheme_offset + 1
+                )?;
+                for reference in references {
+                    write!(f, " ‘{}’", reference)?;
+                }
+                write!(
+                    f,
+                    " expected fully qualified name ‘{}’ found unexpected {}",
+                    fully_qualified_name,
+                    unexpected_definitions.iter().format(", ")
+                )
+            }
+            Self::NotUniquelyDefined {
+                path,
+                position,
+                references,
+                definitions,
+            } => {
+                write!(
+                    f,
+                    "{}:{}:{}: definition(s) for reference(s)",
+                    path.display(),
+                    position.line + 1,
+                    position.column.grapheme_offset + 1
+                )?;
+                for reference in references {
+                    write!(f, " ‘{}’", reference)?;
+                }
+                write!(
+                    f,
+                    " expected exactly one found {}",
+                    definitions.iter().format(", ")
+                )
+            }
+            Self::UnexpectedlyDefined {
+                path,
+                position,
+                references,
+                definitions,
+            } => {
+                write!(
+                    f,
+                    "{}:{}:{}: definition(s) for reference(s)",
+                    path.display(),
+                    position.line + 1,
+                    position.column.grapheme_offset + 1
+                )?;
+                for reference in references {
+                    write!(f, " ‘{}’", reference)?;
+                }
+                write!(
+                    f,
+                    " expected none found {}",
+                    definitions.iter().format(", ")
+                )
+            }
+            Self::IncorrectSnapshot {
+                path,
+                missing_entries,
+                unexpected_entries,
+            } => {
+                write!(f, "{}: resolution snapshot differs", path.display())?;
+                for entry in missing_entries {
+                    write!(f, "\n  - {}", entry)?;
+                }
+                for entry in unexpected_entries {
+                    write!(f, "\n  + {}", entry)?;
+                }
+                Ok(())
+            }
             Self::Cancelled(err) => write!(f, "{}", err),
         }
     }
@@ -630,6 +988,8 @@ impl Test {
         let mut result = TestResult::new();
         for fragment in &self.fragments {
             for assertion in &fragment.assertions {
+                let position = assertion.source().position.clone();
+                let kind = Self::assertion_kind(assertion);
                 match assertion
                     .run(
                         &self.graph,
@@ -640,14 +1000,27 @@ impl Test {
                     )
                     .map_or_else(|e| self.from_error(e), |v| Ok(v))
                 {
-                    Ok(_) => result.add_success(),
-                    Err(f) => result.add_failure(f),
+                    Ok(_) => result.add_success(fragment.path.clone(), position, kind),
+                    Err(f) => result.add_failure(fragment.path.clone(), position, kind, f),
                 }
             }
         }
         Ok(result)
     }
 
+    /// Returns the keyword used for the given assertion in test files.
+    fn assertion_kind(assertion: &Assertion) -> &'static str {
+        match assertion {
+            Assertion::Defined { .. } => DEFINED,
+            Assertion::Defines { .. } => DEFINES,
+            Assertion::Refers { .. } => REFERS,
+            Assertion::DefinedSyntaxType { .. } => DEFINED_SYNTAX_TYPE,
+            Assertion::DefinedFullyQualifiedName { .. } => DEFINED_FULLY_QUALIFIED_NAME,
+            Assertion::DefinedUniquely { .. } => DEFINED_UNIQUELY,
+            Assertion::Undefined { .. } => UNDEFINED,
+        }
+    }
+
     /// Construct a TestFailure from an AssertionError.
     fn from_error(&self, err: AssertionError) -> Result<(), TestFailure> {
         match err {
@@ -661,12 +1034,7 @@ impl Test {
                 missing_targets,
                 unexpected_paths,
             } => {
-                let references = references
-                    .into_iter()
-                    .map(|r| self.graph[self.graph[r].symbol().unwrap()].to_string())
-                    .unique()
-                    .sorted()
-                    .collect();
+                let references = self.reference_symbols(references);
                 let missing_lines = missing_targets
                     .into_iter()
                     .map(|t| t.line)
@@ -743,10 +1111,290 @@ impl Test {
                     unexpected_symbols,
                 })
             }
+            AssertionError::NoDefinitions { source, references } => {
+                Err(TestFailure::NoDefinitions {
+                    path: self.path.clone(),
+                    position: source.position,
+                    references: self.reference_symbols(references),
+                })
+            }
+            AssertionError::IncorrectDefinitionSyntaxTypes {
+                source,
+                references,
+                syntax_type,
+                unexpected_paths,
+            } => {
+                let unexpected_definitions = unexpected_paths
+                    .into_iter()
+                    .map(|p| {
+                        let syntax_type = self
+                            .graph
+                            .source_info(p.end_node)
+                            .and_then(|si| si.syntax_type.into_option())
+                            .map(|ty| self.graph[ty].to_string())
+                            .unwrap_or("?".into());
+                        format!("{} ({})", self.describe_definition(p.end_node), syntax_type)
+                    })
+                    .unique()
+                    .sorted()
+                    .collect();
+                Err(TestFailure::IncorrectDefinitionSyntaxTypes {
+                    path: self.path.clone(),
+                    position: source.position,
+                    references: self.reference_symbols(references),
+                    syntax_type: self.graph[syntax_type].to_string(),
+                    unexpected_definitions,
+                })
+            }
+            AssertionError::IncorrectDefinitionFullyQualifiedNames {
+                source,
+                references,
+                fully_qualified_name,
+                unexpected_paths,
+            } => {
+                let unexpected_definitions = unexpected_paths
+                    .into_iter()
+                    .map(|p| {
+                        let fully_qualified_name = self
+                            .graph
+                            .source_info(p.end_node)
+                            .and_then(|si| si.fully_qualified_name.into_option())
+                            .map(|fqn| self.graph[fqn].to_string())
+                            .unwrap_or("?".into());
+                        format!(
+                            "{} ({})",
+                            self.describe_definition(p.end_node),
+                            fully_qualified_name
+                        )
+                    })
+                    .unique()
+                    .sorted()
+                    .collect();
+                Err(TestFailure::IncorrectDefinitionFullyQualifiedNames {
+                    path: self.path.clone(),
+                    position: source.position,
+                    references: self.reference_symbols(references),
+                    fully_qualified_name: self.graph[fully_qualified_name].to_string(),
+                    unexpected_definitions,
+                })
+            }
+            AssertionError::NotUniquelyDefined {
+                source,
+                references,
+                actual_paths,
+            } => Err(TestFailure::NotUniquelyDefined {
+                path: self.path.clone(),
+                position: source.position,
+                references: self.reference_symbols(references),
+                definitions: self.describe_definitions(actual_paths),
+            }),
+            AssertionError::UnexpectedlyDefined {
+                source,
+                references,
+                actual_paths,
+            } => Err(TestFailure::UnexpectedlyDefined {
+                path: self.path.clone(),
+                position: source.position,
+                references: self.reference_symbols(references),
+                definitions: self.describe_definitions(actual_paths),
+            }),
             AssertionError::Cancelled(err) => Err(TestFailure::Cancelled(err)),
         }
     }
 
+    /// Returns the unique and sorted symbols of the given references.
+    fn reference_symbols(&self, references: Vec<Handle<Node>>) -> Vec<String> {
+        references
+            .into_iter()
+            .map(|r| self.graph[self.graph[r].symbol().unwrap()].to_string())
+            .unique()
+            .sorted()
+            .collect()
+    }
+
+    /// Returns unique and sorted descriptions of the definitions the given paths end in.
+    fn describe_definitions(&self, paths: Vec<PartialPath>) -> Vec<String> {
+        paths
+            .into_iter()
+            .map(|p| self.describe_definition(p.end_node))
+            .unique()
+            .sorted()
+            .collect()
+    }
+
+    /// Describes a definition by its symbol and location.
+    fn describe_definition(&self, node: Handle<Node>) -> String {
+        let symbol = self.graph[self.graph[node].symbol().unwrap()].to_string();
+        format!("‘{}’ at {}", symbol, self.display_node_location(node))
+    }
+
+    /// Compute a snapshot of the resolution of all references in the test fragments. It is the
+    /// responsibility of the caller to ensure that the stack graph for the test fragments has been
+    /// constructed, and the database has been filled with partial paths before computing the snapshot.
+    pub fn resolution_snapshot(
+        &self,
+        partials: &mut PartialPaths,
+        db: &mut Database,
+        stitcher_config: StitcherConfig,
+        cancellation_flag: &dyn CancellationFlag,
+    ) -> Result<ResolutionSnapshot, stack_graphs::CancellationError> {
+        let mut entries = Vec::new();
+        for fragment in &self.fragments {
+            let references = self
+                .graph
+                .nodes_for_file(fragment.file)
+                .filter(|n| self.graph[*n].is_reference())
+                .filter_map(|n| self.get_source_info(n).map(|si| (n, si.span.start.clone())))
+                .sorted_by_key(|(_, p)| (p.line, p.column.utf8_offset))
+                .collect::<Vec<_>>();
+            for (reference, position) in references {
+                let mut reference_paths = Vec::new();
+                ForwardPartialPathStitcher::find_all_complete_partial_paths(
+                    &mut DatabaseCandidates::new(&self.graph, partials, db),
+                    std::iter::once(reference),
+                    stitcher_config,
+                    &cancellation_flag,
+                    |_, _, p| {
+                        reference_paths.push(p.clone());
+                    },
+                )?;
+                let mut definitions = Vec::new();
+                for reference_path in &reference_paths {
+                    if reference_paths
+                        .iter()
+                        .all(|other| !other.shadows(partials, reference_path))
+                    {
+                        definitions.push(self.display_snapshot_location(reference_path.end_node));
+                    }
+                }
+                let symbol = self.graph[self.graph[reference].symbol().unwrap()].to_string();
+                let entry = format!(
+                    "{}:{}:{} {} -> {}",
+                    self.snapshot_file_name(fragment.file),
+                    position.line + 1,
+                    position.column.grapheme_offset + 1,
+                    symbol,
+                    definitions.into_iter().unique().sorted().format(", "),
+                );
+                entries.push(entry.trim_end().to_string());
+            }
+        }
+        Ok(ResolutionSnapshot { entries })
+    }
+
+    /// Compare a snapshot of this test against an expected snapshot. The result contains a single
+    /// success if the snapshots are equal, and a single failure listing the differences otherwise.
+    pub fn check_snapshot(
+        &self,
+        expected: &ResolutionSnapshot,
+        actual: &ResolutionSnapshot,
+    ) -> TestResult {
+        let mut result = TestResult::new();
+        let mut missing_entries = Vec::new();
+        let mut unexpected_entries = Vec::new();
+        for entry in expected
+            .entries
+            .iter()
+            .sorted()
+            .merge_join_by(actual.entries.iter().sorted(), |e, a| e.cmp(a))
+        {
+            match entry {
+                EitherOrBoth::Left(e) => missing_entries.push(e.clone()),
+                EitherOrBoth::Right(a) => unexpected_entries.push(a.clone()),
+                EitherOrBoth::Both(_, _) => {}
+            }
+        }
+        if missing_entries.is_empty() && unexpected_entries.is_empty() {
+            result.add_success(self.path.clone(), Position::default(), SNAPSHOT);
+        } else {
+            // report the failure at the first differing entry, if it is still a valid position
+            let position = missing_entries
+                .iter()
+                .chain(unexpected_entries.iter())
+                .filter_map(|e| self.snapshot_entry_position(e))
+                .min_by_key(|p| (p.line, p.column.grapheme_offset))
+                .unwrap_or_default();
+            result.add_failure(
+                self.path.clone(),
+                position,
+                SNAPSHOT,
+                TestFailure::IncorrectSnapshot {
+                    path: self.path.clone(),
+                    missing_entries,
+                    unexpected_entries,
+                },
+            );
+        }
+        result
+    }
+
+    /// Get the position of a snapshot entry, which starts with `PATH:LINE:COLUMN`. Returns `None`
+    /// if the path is not one of the fragments, or the position is not in the fragment.
+    fn snapshot_entry_position(&self, entry: &str) -> Option<Position> {
+        self.fragments.iter().find_map(|fragment| {
+            let location = entry
+                .strip_prefix(&self.snapshot_file_name(fragment.file))?
+                .strip_prefix(':')?
+                .split(' ')
+                .next()?;
+            let (line, column) = location.split_once(':')?;
+            let line = line.parse::<usize>().ok()?.checked_sub(1)?;
+            let column = column.parse::<usize>().ok()?.checked_sub(1)?;
+            let containing_line = PositionedSubstring::lines_iter(&fragment.source).nth(line)?;
+            if column > containing_line.grapheme_length {
+                return None;
+            }
+            Some(SpanCalculator::new(&fragment.source).for_line_and_grapheme(
+                line,
+                containing_line.utf8_bounds.start,
+                column,
+            ))
+        })
+    }
+
+    /// Format the location of a node as `PATH:LINE:COLUMN`, or `PATH:?` if the node has no source info.
+    fn display_node_location(&self, node: Handle<Node>) -> String {
+        let path = self.graph[node]
+            .file()
+            .map(|f| self.graph[f].to_string())
+            .unwrap_or_default();
+        self.display_location(path, node)
+    }
+
+    /// Format the location of a node like [`Self::display_node_location`], using the file name
+    /// used in snapshots.
+    fn display_snapshot_location(&self, node: Handle<Node>) -> String {
+        let path = self.graph[node]
+            .file()
+            .map(|f| self.snapshot_file_name(f))
+            .unwrap_or_default();
+        self.display_location(path, node)
+    }
+
+    /// Returns the name of a file in snapshots. The file of a test without `path` sections is
+    /// named after the test file, so that snapshots do not depend on the directory the tests are
+    /// run from. Fragment paths are used as they appear in the test.
+    fn snapshot_file_name(&self, file: Handle<File>) -> String {
+        match (self.default_fragment, self.path.file_name()) {
+            (Some(default_fragment), Some(file_name)) if default_fragment == file => {
+                file_name.to_string_lossy().to_string()
+            }
+            _ => self.graph[file].to_string(),
+        }
+    }
+
+    fn display_location(&self, path: String, node: Handle<Node>) -> String {
+        match self.get_source_info(node) {
+            Some(si) => format!(
+                "{}:{}:{}",
+                path,
+                si.span.start.line + 1,
+                si.span.start.column.grapheme_offset + 1
+            ),
+            None => format!("{}:?", path),
+        }
+    }
+
     /// Get source info for a node, using a heuristic to rule default null source info results.
     fn get_source_info(&self, node: Handle<Node>) -> Option<&SourceInfo> {
         self.graph.source_info(node).filter(|si| {
@@ -758,6 +1406,42 @@ impl Test {
     }
 }
 
+/// A snapshot of the resolution of all references in a test.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResolutionSnapshot {
+    entries: Vec<String>,
+}
+
+impl ResolutionSnapshot {
+    /// Iterate over the entries of this snapshot, one per reference.
+    pub fn iter_entries(&self) -> impl Iterator<Item = &str> + '_ {
+        self.entries.iter().map(|e| e.as_str())
+    }
+}
+
+impl std::fmt::Display for ResolutionSnapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for entry in &self.entries {
+            writeln!(f, "{}", entry)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for ResolutionSnapshot {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let entries = s
+            .lines()
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty())
+            .map(|l| l.to_string())
+            .collect();
+        Ok(Self { entries })
+    }
+}
+
 impl TestFragment {
     pub fn add_globals_to(&self, variables: &mut Variables) {
         for (name, value) in self.globals.iter() {
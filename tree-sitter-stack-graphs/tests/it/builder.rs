@@ -6,8 +6,12 @@
 // ------------------------------------------------------------------------------------------------
 
 use stack_graphs::graph::StackGraph;
+use tree_sitter::InputEdit;
+use tree_sitter::Point;
 use tree_sitter_graph::Variables;
+use tree_sitter_stack_graphs::BuildError;
 use tree_sitter_stack_graphs::NoCancellation;
+use tree_sitter_stack_graphs::SourceChanges;
 use tree_sitter_stack_graphs::StackGraphLanguage;
 
 use crate::edges::check_stack_graph_edges;
@@ -72,3 +76,133 @@ fn can_support_injected_nodes() {
         &["[test.py(1) scope] -0-> [test.py(0) exported scope]"],
     );
 }
+
+#[test]
+fn can_tolerate_parse_errors() {
+    let tsg = r#"
+    (identifier) @id {
+      node def
+      attr (def) type = "pop_symbol", symbol = (source-text @id), source_node = @id, is_definition
+    }
+    "#;
+    let python = "a = 1\n)\n";
+
+    let mut language = StackGraphLanguage::from_str(tree_sitter_python::language(), tsg).unwrap();
+    let globals = Variables::new();
+
+    let mut graph = StackGraph::new();
+    let file = graph.get_or_create_file("test.py");
+    let result = language.build_stack_graph_with_output_into(
+        &mut graph,
+        file,
+        python,
+        &globals,
+        &NoCancellation,
+    );
+    assert!(matches!(result, Err(BuildError::ParseErrors(_))));
+
+    language.set_error_tolerant(true);
+    let mut graph = StackGraph::new();
+    let file = graph.get_or_create_file("test.py");
+    let output = language
+        .build_stack_graph_with_output_into(&mut graph, file, python, &globals, &NoCancellation)
+        .expect("Failed to build graph");
+    assert!(output.parse_errors.is_some());
+    let symbols = graph
+        .nodes_for_file(file)
+        .filter_map(|node| graph[node].symbol())
+        .map(|symbol| graph[symbol].to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(vec!["a".to_string()], symbols);
+}
+
+#[test]
+fn skips_nodes_from_stanzas_matching_in_parse_errors() {
+    let tsg = r#"
+    global ROOT_NODE
+    (module) @mod {
+      node @mod.scope
+    }
+    (ERROR) @error {
+      node error
+      edge ROOT_NODE -> error
+      edge error -> ROOT_NODE
+    }
+    "#;
+    let python = "a = 1\n)\n";
+
+    let mut language = StackGraphLanguage::from_str(tree_sitter_python::language(), tsg).unwrap();
+    language.set_error_tolerant(true);
+    let globals = Variables::new();
+
+    let mut graph = StackGraph::new();
+    let file = graph.get_or_create_file("test.py");
+    let output = language
+        .build_stack_graph_with_output_into(&mut graph, file, python, &globals, &NoCancellation)
+        .expect("Failed to build graph");
+    assert!(output.parse_errors.is_some());
+    check_stack_graph_nodes(&graph, file, &["[test.py(0) scope]"]);
+    check_stack_graph_edges(&graph, &[]);
+}
+
+fn edit_and_build_incrementally(
+    old_source: &str,
+    new_source: &str,
+    start_byte: usize,
+    old_end_byte: usize,
+    new_end_byte: usize,
+) -> SourceChanges {
+    let tsg = r#"
+    (identifier) @id {
+      node def
+      attr (def) type = "pop_symbol", symbol = (source-text @id), source_node = @id, is_definition
+    }
+    "#;
+    let language = StackGraphLanguage::from_str(tree_sitter_python::language(), tsg).unwrap();
+    let globals = Variables::new();
+
+    let mut graph = StackGraph::new();
+    let file = graph.get_or_create_file("test.py");
+    let output = language
+        .build_stack_graph_with_output_into(&mut graph, file, old_source, &globals, &NoCancellation)
+        .expect("Failed to build graph");
+    assert!(output.changes.is_none());
+
+    // all test sources are single line
+    let edit = InputEdit {
+        start_byte,
+        old_end_byte,
+        new_end_byte,
+        start_position: Point::new(0, start_byte),
+        old_end_position: Point::new(0, old_end_byte),
+        new_end_position: Point::new(0, new_end_byte),
+    };
+    let mut graph = StackGraph::new();
+    let file = graph.get_or_create_file("test.py");
+    let output = language
+        .build_stack_graph_incrementally_into(
+            &mut graph,
+            file,
+            new_source,
+            &output.tree,
+            &[edit],
+            &globals,
+            &NoCancellation,
+        )
+        .expect("Failed to build graph incrementally");
+    output.changes.expect("Expected changes")
+}
+
+#[test]
+fn can_detect_changes_to_extras_only() {
+    let changes = edit_and_build_incrementally("x = 1\n", "x = 1  # note\n", 5, 5, 13);
+    assert!(changes.ranges.iter().any(|r| r.start <= 5 && 13 <= r.end));
+    assert!(!changes.affects_paths());
+}
+
+#[test]
+fn can_detect_changes_to_syntax() {
+    let changes = edit_and_build_incrementally("x = 1\n", "y = 1\n", 0, 1, 1);
+    assert!(changes.ranges.iter().any(|r| r.start == 0 && 1 <= r.end));
+    assert!(changes.affects_paths());
+}
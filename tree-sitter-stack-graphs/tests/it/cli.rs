@@ -0,0 +1,164 @@
+// -*- coding: utf-8 -*-
+// ------------------------------------------------------------------------------------------------
+// Copyright © 2023, stack-graphs authors.
+// Licensed under either of Apache License, Version 2.0, or MIT license, at your option.
+// Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
+// ------------------------------------------------------------------------------------------------
+
+use stack_graphs::arena::Handle;
+use stack_graphs::graph::File;
+use stack_graphs::graph::StackGraph;
+use stack_graphs::partial::PartialPaths;
+use stack_graphs::stitching::ForwardPartialPathStitcher;
+use stack_graphs::stitching::StitcherConfig;
+use stack_graphs::storage::SQLiteWriter;
+use std::cell::RefCell;
+use std::path::Path;
+use std::path::PathBuf;
+use tree_sitter_graph::Variables;
+use tree_sitter_stack_graphs::cli::util::reporter::Reporter;
+use tree_sitter_stack_graphs::loader::FileAnalyzers;
+use tree_sitter_stack_graphs::loader::LanguageConfiguration;
+use tree_sitter_stack_graphs::loader::Loader;
+use tree_sitter_stack_graphs::NoCancellation;
+use tree_sitter_stack_graphs::StackGraphLanguage;
+
+use crate::test::TSG;
+
+mod export;
+mod git;
+mod index;
+mod report;
+#[cfg(feature = "server")]
+mod serve;
+mod test;
+mod util;
+
+/// A temporary directory, which is removed when dropped.
+pub(crate) struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Creates a new, empty temporary directory. The name must be unique among the tests.
+    pub(crate) fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!(
+            "tree-sitter-stack-graphs-{}-{}",
+            name,
+            std::process::id()
+        ));
+        if path.exists() {
+            std::fs::remove_dir_all(&path).expect("Expected removing directory to succeed");
+        }
+        std::fs::create_dir_all(&path).expect("Expected creating directory to succeed");
+        Self(path.canonicalize().unwrap())
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+
+    /// Writes a file with the given content, relative to this directory, and returns its path.
+    pub(crate) fn write(&self, path: &str, content: &str) -> PathBuf {
+        let path = self.0.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, content).expect("Expected writing file to succeed");
+        path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Builds stack graphs for the given Python files with the test TSG, and stores them in a new
+/// database at the given path. The given function is called with the graph of every file before
+/// it is stored, and can be used to add information that the TSG cannot express.
+pub(crate) fn write_database<F>(db_path: &Path, source_paths: &[PathBuf], mut f: F)
+where
+    F: FnMut(&mut StackGraph, Handle<File>),
+{
+    let language = StackGraphLanguage::from_str(tree_sitter_python::language(), &TSG).unwrap();
+    let mut db = SQLiteWriter::open(db_path).expect("Expected opening database to succeed");
+    for source_path in source_paths {
+        let source = std::fs::read_to_string(source_path).unwrap();
+        let mut graph = StackGraph::new();
+        let file = graph.get_or_create_file(&source_path.to_string_lossy());
+        language
+            .build_stack_graph_into(
+                &mut graph,
+                file,
+                &source,
+                &Variables::new(),
+                &NoCancellation,
+            )
+            .expect("Expected building stack graph to succeed");
+        f(&mut graph, file);
+
+        let mut partials = PartialPaths::new();
+        let mut paths = Vec::new();
+        ForwardPartialPathStitcher::find_minimal_partial_path_set_in_file(
+            &graph,
+            &mut partials,
+            file,
+            StitcherConfig::default(),
+            &stack_graphs::NoCancellation,
+            |_, _, path| paths.push(path.clone()),
+        )
+        .expect("should never be cancelled");
+        db.store_result_for_file(&graph, file, "", &mut partials, &paths)
+            .expect("Expected storing graph to succeed");
+    }
+}
+
+/// Returns a loader for Python, using the test TSG.
+pub(crate) fn python_loader() -> Loader {
+    let language = tree_sitter_python::language();
+    let lc = LanguageConfiguration {
+        language,
+        scope: Some("source.py".into()),
+        content_regex: None,
+        file_types: vec!["py".into()],
+        sgl: StackGraphLanguage::from_str(language, &TSG).unwrap(),
+        builtins: StackGraph::new(),
+        special_files: FileAnalyzers::new(),
+        no_similar_paths_in_file: false,
+    };
+    Loader::from_language_configurations(vec![lc], None).expect("Expected loader to succeed")
+}
+
+/// A reporter that records the final status of every file.
+#[derive(Default)]
+pub(crate) struct RecordingReporter(RefCell<Vec<(PathBuf, String)>>);
+
+impl RecordingReporter {
+    fn record(&self, path: &Path, summary: &str) {
+        self.0
+            .borrow_mut()
+            .push((path.to_path_buf(), summary.to_string()));
+    }
+
+    pub(crate) fn take(&self) -> Vec<(PathBuf, String)> {
+        self.0.take()
+    }
+}
+
+impl Reporter for RecordingReporter {
+    fn skipped(&self, path: &Path, summary: &str, _details: Option<&dyn std::fmt::Display>) {
+        self.record(path, summary);
+    }
+
+    fn started(&self, _path: &Path) {}
+
+    fn succeeded(&self, path: &Path, summary: &str, _details: Option<&dyn std::fmt::Display>) {
+        self.record(path, summary);
+    }
+
+    fn failed(&self, path: &Path, summary: &str, _details: Option<&dyn std::fmt::Display>) {
+        self.record(path, summary);
+    }
+
+    fn cancelled(&self, path: &Path, summary: &str, _details: Option<&dyn std::fmt::Display>) {
+        self.record(path, summary);
+    }
+}
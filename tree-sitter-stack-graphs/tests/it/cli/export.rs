@@ -0,0 +1,282 @@
+// -*- coding: utf-8 -*-
+// ------------------------------------------------------------------------------------------------
+// Copyright © 2023, stack-graphs authors.
+// Licensed under either of Apache License, Version 2.0, or MIT license, at your option.
+// Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
+// ------------------------------------------------------------------------------------------------
+
+use controlled_option::ControlledOption;
+use pretty_assertions::assert_eq;
+use protobuf::Message;
+use scip::types::Document;
+use scip::types::Index;
+use scip::types::SymbolRole;
+use serde_json::Value;
+use std::path::Path;
+use tree_sitter_stack_graphs::cli::export::ExportArgs;
+use tree_sitter_stack_graphs::cli::export::ExportFormat;
+use url::Url;
+
+use crate::cli::write_database;
+use crate::cli::TempDir;
+
+fn export(
+    db_path: &Path,
+    format: ExportFormat,
+    project_root: &Path,
+    qualified_name_separator: &str,
+    output: &Path,
+) {
+    ExportArgs {
+        source_paths: Vec::new(),
+        format,
+        output: Some(output.to_path_buf()),
+        project_root: Some(project_root.to_path_buf()),
+        qualified_name_separator: qualified_name_separator.to_string(),
+    }
+    .run(db_path)
+    .expect("Expected export to succeed");
+}
+
+fn scip_document<'a>(index: &'a Index, relative_path: &str) -> &'a Document {
+    index
+        .documents
+        .iter()
+        .find(|d| d.relative_path == relative_path)
+        .unwrap_or_else(|| panic!("Expected document {}", relative_path))
+}
+
+#[test]
+fn can_export_scip() {
+    let dir = TempDir::new("export-scip");
+    let a = dir.write("project/a.py", "x = 1\ny = 2\n");
+    let b = dir.write("project/b.py", "x\ny\n");
+    let c = dir.write("outside/c.py", "z = 3\n");
+    let db_path = dir.path().join("db.sqlite");
+    write_database(&db_path, &[a.clone(), b, c.clone()], |graph, file| {
+        if graph[file].name() != a.to_string_lossy() {
+            return;
+        }
+        // the TSG cannot set qualified names, so set it for the definition of x here
+        let x = graph
+            .nodes_for_file(file)
+            .find(|n| {
+                graph[*n].is_definition() && graph[*n].symbol().map(|s| &graph[s]) == Some("x")
+            })
+            .unwrap();
+        let fully_qualified_name = graph.add_string("pkg::mod::x");
+        graph.source_info_mut(x).fully_qualified_name =
+            ControlledOption::some(fully_qualified_name);
+    });
+
+    let project_root = dir.path().join("project");
+    let output = dir.path().join("index.scip");
+    export(&db_path, ExportFormat::Scip, &project_root, "::", &output);
+    let index = Index::parse_from_bytes(&std::fs::read(&output).unwrap())
+        .expect("Expected valid SCIP index");
+    assert_eq!(
+        Url::from_file_path(&project_root).unwrap().to_string(),
+        index.metadata.project_root
+    );
+    assert_eq!(3, index.documents.len());
+
+    let document_a = scip_document(&index, "a.py");
+    let definitions = document_a
+        .occurrences
+        .iter()
+        .map(|o| (o.range.clone(), o.symbol.clone(), o.symbol_roles))
+        .collect::<Vec<_>>();
+    assert_eq!(2, definitions.len());
+    let x_symbol = "stack-graphs . . . pkg/mod/x.".to_string();
+    assert_eq!(
+        (
+            vec![0, 0, 1],
+            x_symbol.clone(),
+            SymbolRole::Definition as i32
+        ),
+        definitions[0]
+    );
+    let y_symbol = definitions[1].1.clone();
+    assert_eq!(
+        (vec![1, 0, 1], SymbolRole::Definition as i32),
+        (definitions[1].0.clone(), definitions[1].2)
+    );
+    // y has no qualified name, and gets a symbol based on its file
+    assert!(y_symbol.starts_with("stack-graphs . . . `a.py`/`y@"));
+    assert_eq!(
+        vec![x_symbol.clone(), y_symbol.clone()],
+        document_a
+            .symbols
+            .iter()
+            .map(|s| s.symbol.clone())
+            .collect::<Vec<_>>()
+    );
+
+    let document_b = scip_document(&index, "b.py");
+    let mut references = document_b
+        .occurrences
+        .iter()
+        .map(|o| (o.range.clone(), o.symbol.clone(), o.symbol_roles))
+        .collect::<Vec<_>>();
+    references.sort();
+    assert_eq!(
+        vec![(vec![0, 0, 1], x_symbol, 0), (vec![1, 0, 1], y_symbol, 0)],
+        references
+    );
+    assert!(document_b.symbols.is_empty());
+
+    // files outside the project root keep their full path
+    let document_c = scip_document(&index, &c.to_string_lossy());
+    assert_eq!(1, document_c.occurrences.len());
+    assert_eq!(vec![0, 0, 1], document_c.occurrences[0].range);
+    assert!(document_c.occurrences[0]
+        .symbol
+        .starts_with(&format!("stack-graphs . . . `{}`/`z@", c.to_string_lossy())));
+}
+
+/// The elements of an LSIF dump.
+struct Lsif(Vec<Value>);
+
+impl Lsif {
+    fn read(path: &Path) -> Self {
+        let content = std::fs::read_to_string(path).unwrap();
+        Self(
+            content
+                .lines()
+                .map(|line| serde_json::from_str(line).expect("Expected valid JSON"))
+                .collect(),
+        )
+    }
+
+    fn vertices<'a>(&'a self, label: &'a str) -> impl Iterator<Item = &'a Value> + 'a {
+        self.0
+            .iter()
+            .filter(move |e| e["type"] == "vertex" && e["label"] == label)
+    }
+
+    fn edges<'a>(&'a self, label: &'a str, out_v: &'a Value) -> Vec<&'a Value> {
+        self.0
+            .iter()
+            .filter(|e| e["type"] == "edge" && e["label"] == label && &e["outV"] == out_v)
+            .collect()
+    }
+
+    /// Returns the in vertex of the single edge with the given label from the given vertex.
+    fn next(&self, label: &str, out_v: &Value) -> Value {
+        let edges = self.edges(label, out_v);
+        assert_eq!(1, edges.len(), "Expected one {} edge from {}", label, out_v);
+        edges[0]["inV"].clone()
+    }
+
+    fn document(&self, path: &Path) -> Value {
+        let uri = Url::from_file_path(path).unwrap().to_string();
+        self.vertices("document")
+            .find(|d| d["uri"] == uri.as_str())
+            .unwrap_or_else(|| panic!("Expected document {}", uri))["id"]
+            .clone()
+    }
+
+    /// Returns the range vertex in the given document that starts at the given line and UTF-16
+    /// offset.
+    fn range(&self, document: &Value, line: usize, character: usize) -> Value {
+        let ranges = &self.edges("contains", document)[0]["inVs"];
+        self.vertices("range")
+            .find(|r| {
+                ranges.as_array().unwrap().contains(&r["id"])
+                    && r["start"]["line"] == line
+                    && r["start"]["character"] == character
+            })
+            .unwrap_or_else(|| panic!("Expected range at {}:{}", line, character))["id"]
+            .clone()
+    }
+
+    /// Returns the documents and ranges of the item edges from the given result.
+    fn items(&self, result: &Value, property: Option<&str>) -> Vec<(Value, Value)> {
+        let mut items = Vec::new();
+        for edge in self.edges("item", result) {
+            if edge["property"].as_str() != property {
+                continue;
+            }
+            for range in edge["inVs"].as_array().unwrap() {
+                items.push((edge["document"].clone(), range.clone()));
+            }
+        }
+        items
+    }
+}
+
+#[test]
+fn can_export_lsif() {
+    let dir = TempDir::new("export-lsif");
+    let a = dir.write("my project/a.py", "a = \"😀\"; x = 1\n");
+    let b = dir.write("my project/b.py", "x = 2\n");
+    let c = dir.write("my project/c.py", "a\nx\n");
+    let db_path = dir.path().join("db.sqlite");
+    write_database(&db_path, &[a.clone(), b.clone(), c.clone()], |_, _| {});
+
+    let project_root = dir.path().join("my project");
+    let output = dir.path().join("dump.lsif");
+    export(&db_path, ExportFormat::Lsif, &project_root, ".", &output);
+    let lsif = Lsif::read(&output);
+
+    let meta_data = lsif.vertices("metaData").next().unwrap();
+    assert_eq!("utf-16", meta_data["positionEncoding"]);
+    assert!(meta_data["projectRoot"]
+        .as_str()
+        .unwrap()
+        .ends_with("/my%20project"));
+
+    let document_a = lsif.document(&a);
+    let document_b = lsif.document(&b);
+    let document_c = lsif.document(&c);
+
+    // the emoji is one grapheme, four UTF-8 bytes, and two UTF-16 code units
+    let a_a = lsif.range(&document_a, 0, 0);
+    let a_x = lsif.range(&document_a, 0, 10);
+    let b_x = lsif.range(&document_b, 0, 0);
+    let c_a = lsif.range(&document_c, 0, 0);
+    let c_x = lsif.range(&document_c, 1, 0);
+    assert_eq!(
+        2,
+        lsif.edges("contains", &document_a)[0]["inVs"]
+            .as_array()
+            .unwrap()
+            .len()
+    );
+    let range_a_x = lsif.vertices("range").find(|r| r["id"] == a_x).unwrap();
+    assert_eq!(11, range_a_x["end"]["character"]);
+
+    // definitions have a result set with definition and reference results
+    let a_x_result_set = lsif.next("next", &a_x);
+    let a_x_definitions = lsif.next("textDocument/definition", &a_x_result_set);
+    assert_eq!(
+        vec![(document_a.clone(), a_x.clone())],
+        lsif.items(&a_x_definitions, None)
+    );
+    let a_x_references = lsif.next("textDocument/references", &a_x_result_set);
+    assert_eq!(
+        vec![(document_a.clone(), a_x.clone())],
+        lsif.items(&a_x_references, Some("definitions"))
+    );
+    assert_eq!(
+        vec![(document_c.clone(), c_x.clone())],
+        lsif.items(&a_x_references, Some("references"))
+    );
+
+    // references with a single definition share its result set
+    let a_a_result_set = lsif.next("next", &a_a);
+    assert_eq!(a_a_result_set, lsif.next("next", &c_a));
+
+    // references with multiple definitions get their own result set
+    let c_x_result_set = lsif.next("next", &c_x);
+    let b_x_result_set = lsif.next("next", &b_x);
+    assert_ne!(a_x_result_set, c_x_result_set);
+    assert_ne!(b_x_result_set, c_x_result_set);
+    assert!(lsif
+        .edges("textDocument/references", &c_x_result_set)
+        .is_empty());
+    let c_x_definitions = lsif.next("textDocument/definition", &c_x_result_set);
+    let mut definitions = lsif.items(&c_x_definitions, None);
+    definitions.sort_by_key(|(document, _)| document.as_u64());
+    assert_eq!(vec![(document_a, a_x), (document_b, b_x)], definitions);
+}
@@ -0,0 +1,51 @@
+// -*- coding: utf-8 -*-
+// ------------------------------------------------------------------------------------------------
+// Copyright © 2023, stack-graphs authors.
+// Licensed under either of Apache License, Version 2.0, or MIT license, at your option.
+// Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
+// ------------------------------------------------------------------------------------------------
+
+use pretty_assertions::assert_eq;
+use std::path::Path;
+use std::path::PathBuf;
+use tree_sitter_stack_graphs::cli::util::git::parse_name_status;
+
+fn paths(paths: &[&str]) -> Vec<PathBuf> {
+    paths.iter().map(|p| Path::new("/repo").join(p)).collect()
+}
+
+#[test]
+fn can_parse_name_status() {
+    let output = "M\0src/a.py\0A\0src/b.py\0D\0src/c.py\0R087\0src/d.py\0src/e.py\0";
+    let changes = parse_name_status(Path::new("/repo"), output).unwrap();
+    assert_eq!(
+        paths(&["src/a.py", "src/b.py", "src/e.py"]),
+        changes.changed
+    );
+    assert_eq!(paths(&["src/c.py", "src/d.py"]), changes.removed);
+}
+
+#[test]
+fn can_parse_name_status_with_tabs_and_newlines_in_paths() {
+    let output = "A\0src/a\tb.py\0R100\0src/c\nd.py\0src/e\tf\n.py\0";
+    let changes = parse_name_status(Path::new("/repo"), output).unwrap();
+    assert_eq!(paths(&["src/a\tb.py", "src/e\tf\n.py"]), changes.changed);
+    assert_eq!(paths(&["src/c\nd.py"]), changes.removed);
+}
+
+#[test]
+fn can_parse_empty_name_status() {
+    let changes = parse_name_status(Path::new("/repo"), "").unwrap();
+    assert!(changes.changed.is_empty());
+    assert!(changes.removed.is_empty());
+}
+
+#[test]
+fn cannot_parse_name_status_with_missing_path() {
+    assert!(parse_name_status(Path::new("/repo"), "R100\0src/a.py\0").is_err());
+}
+
+#[test]
+fn cannot_parse_unknown_status() {
+    assert!(parse_name_status(Path::new("/repo"), "X\0src/a.py\0").is_err());
+}
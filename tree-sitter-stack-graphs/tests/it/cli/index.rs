@@ -0,0 +1,161 @@
+// -*- coding: utf-8 -*-
+// ------------------------------------------------------------------------------------------------
+// Copyright © 2023, stack-graphs authors.
+// Licensed under either of Apache License, Version 2.0, or MIT license, at your option.
+// Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
+// ------------------------------------------------------------------------------------------------
+
+use notify::event::CreateKind;
+use notify::event::DataChange;
+use notify::event::ModifyKind;
+use notify::event::RemoveKind;
+use notify::event::RenameMode;
+use notify::Event;
+use notify::EventKind;
+use pretty_assertions::assert_eq;
+use stack_graphs::storage::FileStatus;
+use stack_graphs::storage::SQLiteWriter;
+use std::path::Path;
+use std::path::PathBuf;
+use tree_sitter_stack_graphs::cli::index::Indexer;
+use tree_sitter_stack_graphs::cli::util::git::Changes;
+use tree_sitter_stack_graphs::NoCancellation;
+
+use crate::cli::python_loader;
+use crate::cli::RecordingReporter;
+use crate::cli::TempDir;
+
+fn event(kind: EventKind, paths: &[&Path]) -> notify::Result<Event> {
+    let mut event = Event::new(kind);
+    for path in paths {
+        event = event.add_path(path.to_path_buf());
+    }
+    Ok(event)
+}
+
+/// Returns the paths of the files in the database, in order.
+fn indexed_files(db: SQLiteWriter) -> Vec<PathBuf> {
+    let mut db = db.into_reader();
+    let mut files = db.list_all().expect("Expected listing files to succeed");
+    let mut paths = files
+        .try_iter()
+        .expect("Expected listing files to succeed")
+        .map(|entry| {
+            let entry = entry.expect("Expected reading file entry to succeed");
+            assert!(
+                matches!(entry.status, FileStatus::Indexed),
+                "Expected {} to be indexed",
+                entry.path.display()
+            );
+            entry.path
+        })
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn watched_changes_update_index() {
+    let dir = TempDir::new("index-watched-changes");
+    let a = dir.write("src/a.py", "x = 1\n");
+    let b = dir.write("src/b.py", "y = 1\n");
+    let d = dir.write("src/d.py", "z = 1\n");
+    let source_paths = vec![dir.path().join("src")];
+    let mut db = SQLiteWriter::open(&dir.path().join("db.sqlite"))
+        .expect("Expected opening database to succeed");
+    let mut loader = python_loader();
+    let reporter = RecordingReporter::default();
+
+    let mut indexer = Indexer::new(&mut db, &mut loader, &reporter);
+    indexer
+        .index_all(&source_paths, None::<&Path>, &NoCancellation)
+        .expect("Expected indexing to succeed");
+    reporter.take();
+
+    dir.write("src/a.py", "x = 2\n");
+    std::fs::remove_file(&b).unwrap();
+    let bad = dir.path().join("src/bad.py");
+    std::fs::write(&bad, b"\xff\xfe\n").unwrap();
+    let c = dir.write("src/c.py", "w = 1\n");
+    let e = dir.path().join("src/e.py");
+    std::fs::rename(&d, &e).unwrap();
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let events = vec![
+        event(
+            EventKind::Modify(ModifyKind::Data(DataChange::Content)),
+            &[&a],
+        ),
+        event(EventKind::Remove(RemoveKind::File), &[&b]),
+        event(EventKind::Create(CreateKind::File), &[&bad]),
+        event(EventKind::Create(CreateKind::File), &[&c]),
+        event(
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)),
+            &[&d, &e],
+        ),
+    ];
+    for event in events {
+        sender.send(event).unwrap();
+    }
+    drop(sender);
+    indexer
+        .index_watched_changes(&source_paths, &receiver)
+        .expect("Expected watching to succeed");
+
+    let expected = vec![
+        (a.clone(), "indexed".to_string()),
+        (b.clone(), "removed".to_string()),
+        (bad.clone(), "error".to_string()),
+        (c.clone(), "indexed".to_string()),
+        (d.clone(), "removed".to_string()),
+        (e.clone(), "indexed".to_string()),
+    ];
+    assert_eq!(expected, reporter.take());
+
+    assert_eq!(vec![a, c, e], indexed_files(db));
+}
+
+#[test]
+fn git_changes_update_index() {
+    let dir = TempDir::new("index-git-changes");
+    let a = dir.write("src/a.py", "x = 1\n");
+    let b = dir.write("src/b.py", "y = 1\n");
+    let d = dir.write("src/d.py", "z = 1\n");
+    let source_paths = vec![dir.path().join("src")];
+    let mut db = SQLiteWriter::open(&dir.path().join("db.sqlite"))
+        .expect("Expected opening database to succeed");
+    let mut loader = python_loader();
+    let reporter = RecordingReporter::default();
+
+    let mut indexer = Indexer::new(&mut db, &mut loader, &reporter);
+    indexer
+        .index_all(&source_paths, None::<&Path>, &NoCancellation)
+        .expect("Expected indexing to succeed");
+    reporter.take();
+
+    dir.write("src/a.py", "x = 2\n");
+    std::fs::remove_file(&b).unwrap();
+    let c = dir.write("src/c.py", "w = 1\n");
+    let e = dir.path().join("src/e.py");
+    std::fs::rename(&d, &e).unwrap();
+    let outside = dir.write("other/f.py", "v = 1\n");
+
+    let changes = Changes {
+        changed: vec![a.clone(), c.clone(), e.clone(), outside],
+        removed: vec![b.clone(), d.clone()],
+    };
+    indexer
+        .index_changes(&source_paths, changes)
+        .expect("Expected indexing changes to succeed");
+
+    let expected = vec![
+        (b, "removed".to_string()),
+        (d, "removed".to_string()),
+        (a.clone(), "indexed".to_string()),
+        (c.clone(), "indexed".to_string()),
+        (e.clone(), "indexed".to_string()),
+    ];
+    assert_eq!(expected, reporter.take());
+
+    assert_eq!(vec![a, c, e], indexed_files(db));
+}
@@ -0,0 +1,165 @@
+// -*- coding: utf-8 -*-
+// ------------------------------------------------------------------------------------------------
+// Copyright © 2023, stack-graphs authors.
+// Licensed under either of Apache License, Version 2.0, or MIT license, at your option.
+// Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
+// ------------------------------------------------------------------------------------------------
+
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+use stack_graphs::stitching::StitcherConfig;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use tree_sitter_stack_graphs::cli::test::report::TestFileReport;
+use tree_sitter_stack_graphs::cli::test::report::TestFileStatus;
+use tree_sitter_stack_graphs::cli::test::report::TestReport;
+use tree_sitter_stack_graphs::test::TestResult;
+use tree_sitter_stack_graphs::NoCancellation;
+
+use crate::test::build_test;
+use crate::test::TSG;
+
+fn run_test(path: &Path, python_source: &str) -> TestResult {
+    let (mut test, mut partials, mut db) = build_test(path, python_source, &TSG);
+    test.run(
+        &mut partials,
+        &mut db,
+        StitcherConfig::default(),
+        &NoCancellation,
+    )
+    .expect("should never be cancelled")
+}
+
+fn file_report(path: &str, status: TestFileStatus) -> TestFileReport {
+    let mut file = TestFileReport::new(Path::new(path));
+    file.fragments = vec![PathBuf::from(path)];
+    file.status = status;
+    file.duration = Duration::from_millis(1500);
+    file
+}
+
+/// Returns a report with a skipped file, a file with an error, a passing file with one
+/// assertion, and a failing file with a passing and a failing assertion.
+fn test_report() -> TestReport {
+    let passed = run_test(
+        Path::new("passed.py"),
+        r#"
+          x = 1
+            x
+          # ^ defined: 2
+        "#,
+    );
+    let failed = run_test(
+        Path::new("a&b.py"),
+        r#"
+          x = 1
+            x
+          # ^ defined: 2
+            x
+          # ^ defined: 3
+        "#,
+    );
+
+    let mut report = TestReport::new();
+    report.add(file_report("skipped.py", TestFileStatus::Skipped));
+    report.add(file_report(
+        "error.py",
+        TestFileStatus::Error("Parse error at <1:1>\n\u{1b}[31mcaused by\u{1b}[0m".into()),
+    ));
+    report.add(file_report("passed.py", TestFileStatus::Ran(passed)));
+    report.add(file_report("a&b.py", TestFileStatus::Ran(failed)));
+    report.add(file_report("unsupported.txt", TestFileStatus::Unsupported));
+    report
+}
+
+#[test]
+fn can_write_json_report() {
+    let mut output = Vec::new();
+    test_report()
+        .write_json(&mut output)
+        .expect("Expected writing report to succeed");
+    let json: Value = serde_json::from_slice(&output).expect("Expected valid JSON");
+
+    let files = json["files"].as_array().unwrap();
+    let statuses = files
+        .iter()
+        .map(|f| (f["path"].as_str().unwrap(), f["status"].as_str().unwrap()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            ("skipped.py", "skipped"),
+            ("error.py", "error"),
+            ("passed.py", "passed"),
+            ("a&b.py", "failed"),
+        ],
+        statuses
+    );
+
+    assert_eq!(
+        "Parse error at <1:1>\n\u{1b}[31mcaused by\u{1b}[0m",
+        files[1]["error"].as_str().unwrap()
+    );
+    assert_eq!(1.5, files[1]["time"].as_f64().unwrap());
+    assert_eq!(0, files[1]["assertions"].as_array().unwrap().len());
+
+    let assertions = files[3]["assertions"].as_array().unwrap();
+    assert_eq!(2, assertions.len());
+    assert_eq!("passed", assertions[0]["status"]);
+    assert_eq!(Value::Null, assertions[0]["failure"]);
+    assert_eq!("failed", assertions[1]["status"]);
+    assert_eq!("defined", assertions[1]["kind"]);
+    assert_eq!(5, assertions[1]["line"]);
+    assert_eq!(13, assertions[1]["column"]);
+    assert!(assertions[1]["failure"]
+        .as_str()
+        .unwrap()
+        .contains("missing expected on line(s) 3"));
+}
+
+#[test]
+fn can_write_junit_report() {
+    let mut output = Vec::new();
+    test_report()
+        .write_junit(&mut output)
+        .expect("Expected writing report to succeed");
+    let xml = String::from_utf8(output).expect("Expected valid UTF-8");
+
+    assert!(xml.starts_with(r#"<?xml version="1.0" encoding="UTF-8"?>"#));
+    assert!(xml.contains(
+        r#"<testsuites name="stack-graphs" tests="5" failures="1" errors="1" skipped="1">"#
+    ));
+    assert!(xml.contains(
+        r#"<testsuite name="skipped.py" tests="1" failures="0" errors="0" skipped="1" time="1.500">"#
+    ));
+    assert!(xml.contains(
+        r#"<testsuite name="error.py" tests="1" failures="0" errors="1" skipped="0" time="1.500">"#
+    ));
+    assert!(xml.contains(
+        r#"<testsuite name="passed.py" tests="1" failures="0" errors="0" skipped="0" time="1.500">"#
+    ));
+    assert!(xml.contains(
+        r#"<testsuite name="a&amp;b.py" tests="2" failures="1" errors="0" skipped="0" time="1.500">"#
+    ));
+    assert!(xml.contains(r#"<property name="fragment" value="a&amp;b.py"/>"#));
+    assert!(!xml.contains("unsupported.txt"));
+
+    // error messages are escaped, and characters that are illegal in XML are replaced
+    assert!(xml.contains(
+        "<error message=\"Parse error at &lt;1:1&gt;\">Parse error at &lt;1:1&gt;\n\u{fffd}[31mcaused by\u{fffd}[0m</error>"
+    ));
+    assert!(!xml.contains('\u{1b}'));
+
+    // passing assertions are empty test cases, failing assertions have a failure
+    assert!(xml.contains(r#"<testcase name="a&amp;b.py:3:13: defined" classname="a&amp;b.py"/>"#));
+    let failure_start = xml
+        .find(r#"<testcase name="a&amp;b.py:5:13: defined" classname="a&amp;b.py">"#)
+        .expect("Expected test case for failing assertion");
+    let failure = &xml[failure_start..];
+    let failure = &failure[..failure.find("</testcase>").unwrap()];
+    assert!(
+        failure.contains(r#"<failure message="a&amp;b.py:5:13: definition(s) for reference(s)"#)
+    );
+    assert!(failure.contains("missing expected on line(s) 3"));
+    assert!(failure.contains("</failure>"));
+}
@@ -0,0 +1,108 @@
+// -*- coding: utf-8 -*-
+// ------------------------------------------------------------------------------------------------
+// Copyright © 2023, stack-graphs authors.
+// Licensed under either of Apache License, Version 2.0, or MIT license, at your option.
+// Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
+// ------------------------------------------------------------------------------------------------
+
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use serde_json::Value;
+use tree_sitter_stack_graphs::cli::serve::Worker;
+
+use crate::cli::write_database;
+use crate::cli::TempDir;
+
+/// Returns a worker for a database with a single file `a.py`, which defines and references `x`.
+fn worker(dir: &TempDir) -> Worker {
+    let a = dir.write("a.py", "x = 1\nx\n");
+    let db_path = dir.path().join("db.sqlite");
+    write_database(&db_path, &[a], |_, _| {});
+    Worker::open_all(&db_path, 1, None)
+        .expect("Expected opening database to succeed")
+        .pop()
+        .unwrap()
+}
+
+fn error_code(response: &Value) -> &Value {
+    &response["error"]["code"]
+}
+
+#[test]
+fn reports_parse_error() {
+    let dir = TempDir::new("serve-parse-error");
+    let mut worker = worker(&dir);
+    let response = worker.handle_message("{").expect("Expected a response");
+    assert_eq!(&Value::Null, &response["id"]);
+    assert_eq!(&json!(-32700), error_code(&response));
+}
+
+#[test]
+fn reports_unknown_method() {
+    let dir = TempDir::new("serve-unknown-method");
+    let mut worker = worker(&dir);
+    let response = worker
+        .handle_message(r#"{"jsonrpc": "2.0", "id": 1, "method": "unknown"}"#)
+        .expect("Expected a response");
+    assert_eq!(&json!(1), &response["id"]);
+    assert_eq!(&json!(-32601), error_code(&response));
+}
+
+#[test]
+fn does_not_respond_to_notification() {
+    let dir = TempDir::new("serve-notification");
+    let mut worker = worker(&dir);
+    let message = json!({
+        "jsonrpc": "2.0",
+        "method": "file-status",
+        "params": { "path": dir.path().join("a.py") },
+    });
+    assert_eq!(None, worker.handle_message(&message.to_string()));
+}
+
+#[test]
+fn reports_zero_based_position_as_invalid() {
+    let dir = TempDir::new("serve-invalid-position");
+    let mut worker = worker(&dir);
+    let message = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "definition",
+        "params": { "path": dir.path().join("a.py"), "line": 0, "column": 0 },
+    });
+    let response = worker
+        .handle_message(&message.to_string())
+        .expect("Expected a response");
+    assert_eq!(&json!(-32602), error_code(&response));
+}
+
+#[test]
+fn can_find_definition() {
+    let dir = TempDir::new("serve-definition");
+    let mut worker = worker(&dir);
+    let a = dir.path().join("a.py");
+    let message = json!({
+        "jsonrpc": "2.0",
+        "id": "x",
+        "method": "definition",
+        "params": { "path": a, "line": 2, "column": 1 },
+    });
+    let response = worker
+        .handle_message(&message.to_string())
+        .expect("Expected a response");
+    let span = |line: usize| {
+        json!({
+            "path": a.to_string_lossy(),
+            "start": { "line": line, "column": 1 },
+            "end": { "line": line, "column": 2 },
+        })
+    };
+    assert_eq!(
+        json!({
+            "jsonrpc": "2.0",
+            "id": "x",
+            "result": [{ "source": span(2), "targets": [span(1)] }],
+        }),
+        response
+    );
+}
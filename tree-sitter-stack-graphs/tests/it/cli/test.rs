@@ -0,0 +1,59 @@
+// -*- coding: utf-8 -*-
+// ------------------------------------------------------------------------------------------------
+// Copyright © 2023, stack-graphs authors.
+// Licensed under either of Apache License, Version 2.0, or MIT license, at your option.
+// Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
+// ------------------------------------------------------------------------------------------------
+
+use pretty_assertions::assert_eq;
+use std::path::PathBuf;
+use tree_sitter_stack_graphs::cli::test::TestArgs;
+
+use crate::cli::python_loader;
+use crate::cli::RecordingReporter;
+use crate::cli::TempDir;
+
+/// Writes test files of different sizes, so that they take different times to run, and returns
+/// the expected status of every test file, in order.
+fn write_test_files(dir: &TempDir) -> Vec<(PathBuf, String)> {
+    let mut expected = Vec::new();
+    for i in 0..8 {
+        let mut source = String::new();
+        // larger files come first, so that they are likely to finish after smaller ones
+        for j in 0..(8 - i) * 200 {
+            source += &format!("v{} = {}\n", j, j);
+        }
+        let (reference, status) = if i % 3 == 1 {
+            ("y", "1/1 assertions failed")
+        } else {
+            ("x", "success")
+        };
+        source += &format!("x = 1\n {}\n#^ defined: {}\n", reference, (8 - i) * 200 + 1);
+        let path = dir.write(&format!("test{}.py", i), &source);
+        expected.push((path, status.to_string()));
+    }
+    expected
+}
+
+fn run_tests(dir: &TempDir, jobs: usize) -> Vec<(PathBuf, String)> {
+    let reporter = RecordingReporter::default();
+    let mut args = TestArgs::new(vec![dir.path().to_path_buf()]);
+    args.jobs = Some(jobs);
+    let result = args.run_with_reporter(python_loader(), &reporter);
+    assert!(result.is_err(), "Expected failing tests to fail the run");
+    reporter.take()
+}
+
+#[test]
+fn parallel_tests_are_reported_in_order() {
+    let dir = TempDir::new("test-parallel-order");
+    let expected = write_test_files(&dir);
+    assert_eq!(expected, run_tests(&dir, 4));
+}
+
+#[test]
+fn sequential_tests_are_reported_in_order() {
+    let dir = TempDir::new("test-sequential-order");
+    let expected = write_test_files(&dir);
+    assert_eq!(expected, run_tests(&dir, 1));
+}
@@ -0,0 +1,52 @@
+// -*- coding: utf-8 -*-
+// ------------------------------------------------------------------------------------------------
+// Copyright © 2023, stack-graphs authors.
+// Licensed under either of Apache License, Version 2.0, or MIT license, at your option.
+// Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
+// ------------------------------------------------------------------------------------------------
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+use tree_sitter_stack_graphs::cli::util::iter_filtered_files_and_directories;
+use tree_sitter_stack_graphs::cli::util::FileFilter;
+
+use crate::cli::TempDir;
+
+#[test]
+fn file_filter_selects_the_same_files_as_the_directory_walk() {
+    let dir = TempDir::new("file-filter");
+    // outside a git repository only .ignore files apply
+    dir.write(".gitignore", "not-ignored.py\n");
+    dir.write(".ignore", "ignored.py\n");
+    let a = dir.write("a.py", "");
+    let not_ignored = dir.write("not-ignored.py", "");
+    let ignored = dir.write("ignored.py", "");
+    // inside a git repository .gitignore files and the repository excludes apply as well
+    dir.write("repo/.git/info/exclude", "excluded.py\n");
+    dir.write("repo/.gitignore", "build/\n");
+    let b = dir.write("repo/b.py", "");
+    let excluded = dir.write("repo/excluded.py", "");
+    let built = dir.write("repo/build/c.py", "");
+
+    let filter = FileFilter::new(&[], &[], true).unwrap();
+    let walked = iter_filtered_files_and_directories(vec![dir.path()], filter.clone())
+        .map(|(_, path, _)| path)
+        .collect::<HashSet<PathBuf>>();
+
+    for (path, expected) in [
+        (&a, true),
+        (&not_ignored, true),
+        (&ignored, false),
+        (&b, true),
+        (&excluded, false),
+        (&built, false),
+    ] {
+        assert_eq!(expected, walked.contains(path), "walked {}", path.display());
+        assert_eq!(
+            expected,
+            filter.is_selected(dir.path(), path),
+            "selected {}",
+            path.display()
+        );
+    }
+}
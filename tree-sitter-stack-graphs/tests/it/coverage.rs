@@ -0,0 +1,101 @@
+// -*- coding: utf-8 -*-
+// ------------------------------------------------------------------------------------------------
+// Copyright © 2023, stack-graphs authors.
+// Licensed under either of Apache License, Version 2.0, or MIT license, at your option.
+// Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
+// ------------------------------------------------------------------------------------------------
+
+use stack_graphs::graph::StackGraph;
+use tree_sitter_graph::Variables;
+use tree_sitter_stack_graphs::coverage::StanzaCoverage;
+use tree_sitter_stack_graphs::NoCancellation;
+use tree_sitter_stack_graphs::StackGraphLanguage;
+
+static TSG: &'static str = r#"
+  (module) @mod {
+    node @mod.defs
+  }
+
+  (identifier) @id {
+    node @id.def
+    node @id.ref
+  }
+
+  (class_definition) @class {
+    node @class.def
+  }
+"#;
+
+fn coverage_for(python_source: &str) -> StanzaCoverage {
+    let language = StackGraphLanguage::from_str(tree_sitter_python::language(), TSG).unwrap();
+    let mut graph = StackGraph::new();
+    let file = graph.get_or_create_file("test.py");
+    let output = language
+        .build_stack_graph_with_output_into(
+            &mut graph,
+            file,
+            python_source,
+            &Variables::new(),
+            &NoCancellation,
+        )
+        .expect("Could not build graph");
+    let mut coverage = StanzaCoverage::new(&language);
+    coverage
+        .add_matches(&language, python_source, &output)
+        .expect("Could not match stanzas");
+    coverage.add_nodes(&graph, file);
+    coverage
+}
+
+#[test]
+fn can_record_stanza_matches() {
+    let coverage = coverage_for("a = b\n");
+    let matches = coverage
+        .iter_stanzas()
+        .map(|s| (s.start_line, s.matches))
+        .collect::<Vec<_>>();
+    assert_eq!(vec![(1, 1), (5, 2), (10, 0)], matches);
+}
+
+#[test]
+fn can_record_stanza_nodes() {
+    let coverage = coverage_for("a = b\n");
+    let nodes = coverage
+        .iter_stanzas()
+        .map(|s| (s.start_line, s.nodes))
+        .collect::<Vec<_>>();
+    assert_eq!(vec![(1, 1), (5, 4), (10, 0)], nodes);
+}
+
+#[test]
+fn can_list_unhit_stanzas() {
+    let coverage = coverage_for("a = b\n");
+    let unhit = coverage
+        .iter_unhit_stanzas()
+        .map(|s| s.start_line)
+        .collect::<Vec<_>>();
+    assert_eq!(vec![10], unhit);
+}
+
+#[test]
+fn can_absorb_coverage() {
+    let mut coverage = coverage_for("a = b\n");
+    coverage.absorb(coverage_for("class A:\n  pass\n"));
+    let matches = coverage
+        .iter_stanzas()
+        .map(|s| (s.start_line, s.matches))
+        .collect::<Vec<_>>();
+    assert_eq!(vec![(1, 2), (5, 3), (10, 1)], matches);
+}
+
+#[test]
+fn can_write_lcov() {
+    let coverage = coverage_for("a = b\n");
+    let mut output = Vec::new();
+    coverage.write_lcov(&mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("FN:2,stanza_2\n"));
+    assert!(output.contains("FNDA:0,stanza_11\n"));
+    assert!(output.contains("FNF:3\nFNH:2\n"));
+    assert!(output.ends_with("end_of_record\n"));
+}
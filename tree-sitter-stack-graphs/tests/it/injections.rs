@@ -0,0 +1,112 @@
+// -*- coding: utf-8 -*-
+// ------------------------------------------------------------------------------------------------
+// Copyright © 2023, stack-graphs authors.
+// Licensed under either of Apache License, Version 2.0, or MIT license, at your option.
+// Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
+// ------------------------------------------------------------------------------------------------
+
+use pretty_assertions::assert_eq;
+use stack_graphs::graph::NodeID;
+use stack_graphs::graph::StackGraph;
+use tree_sitter_graph::Variables;
+use tree_sitter_stack_graphs::NoCancellation;
+use tree_sitter_stack_graphs::StackGraphLanguage;
+
+static INJECTIONS_QUERY: &str = r#"
+  ((string) @injection.content
+   (#set! injection.language "python"))
+"#;
+
+static PYTHON: &str = "x = 1\n\"x\"\n";
+
+/// Builds the stack graph for `PYTHON` and its injected string, and returns the ID of the sink of
+/// the outgoing edge of the reference in the injected code, and the host scope of the injection.
+fn build_and_find_injected_reference_sink(tsg_source: &str) -> (NodeID, Option<NodeID>) {
+    let mut language =
+        StackGraphLanguage::from_str(tree_sitter_python::language(), tsg_source).unwrap();
+    language.set_injections_query(INJECTIONS_QUERY).unwrap();
+    let mut graph = StackGraph::new();
+    let file = graph.get_or_create_file("test.py");
+    let globals = Variables::new();
+
+    let injections = language
+        .build_stack_graph_with_output_into(&mut graph, file, PYTHON, &globals, &NoCancellation)
+        .expect("Expected building host to succeed")
+        .injections;
+    assert_eq!(1, injections.len());
+    let injection = &injections[0];
+    assert_eq!("python", injection.language);
+    assert_eq!(
+        vec![(7, 8)],
+        injection
+            .ranges
+            .iter()
+            .map(|r| (r.start_byte, r.end_byte))
+            .collect::<Vec<_>>()
+    );
+    assert!(graph
+        .nodes_for_file(file)
+        .all(|node| !graph[node].is_reference()));
+
+    let nested_injections = language
+        .build_injection_into(
+            &mut graph,
+            file,
+            PYTHON,
+            injection,
+            &globals,
+            &NoCancellation,
+        )
+        .expect("Expected building injection to succeed")
+        .injections;
+    assert!(nested_injections.is_empty());
+
+    let reference = graph
+        .nodes_for_file(file)
+        .find(|node| graph[*node].is_reference())
+        .expect("Expected reference in injected code");
+    assert_eq!(1, graph.source_info(reference).unwrap().span.start.line);
+    let sink = graph
+        .outgoing_edges(reference)
+        .next()
+        .expect("Expected edge from reference")
+        .sink;
+    (graph[sink].id(), injection.scope)
+}
+
+#[test]
+fn can_connect_injected_code_to_host_scope() {
+    let tsg = r#"
+      global INJECTION_SCOPE
+
+      (module (expression_statement (identifier) @name)) {
+        node ref
+        attr (ref) type = "push_symbol", symbol = (source-text @name), source_node = @name, is_reference
+        edge ref -> INJECTION_SCOPE
+      }
+
+      (string) @string {
+        node scope
+        attr (scope) injection_node = @string
+      }
+    "#;
+    let (sink, injection_scope) = build_and_find_injected_reference_sink(tsg);
+    assert!(injection_scope.is_some());
+    assert_eq!(injection_scope, Some(sink));
+}
+
+#[test]
+fn injection_scope_is_root_node_without_host_scope() {
+    let tsg = r#"
+      global INJECTION_SCOPE
+
+      (module (expression_statement (identifier) @name)) {
+        node ref
+        attr (ref) type = "push_symbol", symbol = (source-text @name), source_node = @name, is_reference
+        edge ref -> INJECTION_SCOPE
+      }
+    "#;
+    let (sink, injection_scope) = build_and_find_injected_reference_sink(tsg);
+    assert_eq!(None, injection_scope);
+    assert_eq!(NodeID::root(), sink);
+}
@@ -14,9 +14,14 @@ use tree_sitter_stack_graphs::NoCancellation;
 use tree_sitter_stack_graphs::StackGraphLanguage;
 
 mod builder;
+#[cfg(feature = "cli")]
+mod cli;
+mod coverage;
 mod edges;
+mod injections;
 mod loader;
 mod nodes;
+mod plugin;
 mod test;
 
 pub(self) fn build_stack_graph(
@@ -357,3 +357,57 @@ fn can_set_syntax_type() {
         .unwrap_or("MISSING");
     assert_eq!("function", syntax_type)
 }
+
+#[test]
+fn can_set_documentation_from_string() {
+    let tsg = r#"
+      (function_definition name: (identifier) @name) {
+         node result
+         attr (result) type = "pop_symbol", symbol = (source-text @name), is_definition, documentation = "Does foo."
+      }
+    "#;
+    let python = r#"
+      def foo():
+        pass
+    "#;
+
+    let (graph, file) = build_stack_graph(python, tsg).unwrap();
+    let node_handle = graph.nodes_for_file(file).next().unwrap();
+    let source_info = graph.source_info(node_handle).unwrap();
+
+    let documentation = source_info
+        .documentation
+        .into_option()
+        .map(|s| &graph[s])
+        .unwrap_or("MISSING");
+    assert_eq!("Does foo.", documentation)
+}
+
+#[test]
+fn can_set_documentation_from_syntax_node() {
+    let tsg = r#"
+      (function_definition
+        name: (identifier) @name
+        body: (block . (expression_statement (string) @doc))
+      ) {
+         node result
+         attr (result) type = "pop_symbol", symbol = (source-text @name), is_definition, documentation = @doc
+      }
+    "#;
+    let python = r#"
+      def foo():
+        """Does foo."""
+        pass
+    "#;
+
+    let (graph, file) = build_stack_graph(python, tsg).unwrap();
+    let node_handle = graph.nodes_for_file(file).next().unwrap();
+    let source_info = graph.source_info(node_handle).unwrap();
+
+    let documentation = source_info
+        .documentation
+        .into_option()
+        .map(|s| &graph[s])
+        .unwrap_or("MISSING");
+    assert_eq!(r#""""Does foo.""""#, documentation)
+}
@@ -0,0 +1,177 @@
+// -*- coding: utf-8 -*-
+// ------------------------------------------------------------------------------------------------
+// Copyright © 2023, stack-graphs authors.
+// Licensed under either of Apache License, Version 2.0, or MIT license, at your option.
+// Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
+// ------------------------------------------------------------------------------------------------
+
+#[cfg(feature = "plugins")]
+use once_cell::sync::Lazy;
+#[cfg(feature = "plugins")]
+use stack_graphs::graph::StackGraph;
+use std::path::Path;
+#[cfg(feature = "plugins")]
+use std::path::PathBuf;
+#[cfg(feature = "plugins")]
+use std::process::Command;
+#[cfg(feature = "plugins")]
+use tree_sitter_graph::Variables;
+use tree_sitter_stack_graphs::loader::FileAnalyzers;
+use tree_sitter_stack_graphs::loader::LoadError;
+use tree_sitter_stack_graphs::loader::Loader;
+#[cfg(feature = "plugins")]
+use tree_sitter_stack_graphs::plugin::load_plugin;
+use tree_sitter_stack_graphs::plugin::PluginDeclaration;
+use tree_sitter_stack_graphs::plugin::PluginLanguage;
+use tree_sitter_stack_graphs::plugin::PLUGIN_API_VERSION;
+use tree_sitter_stack_graphs::NoCancellation;
+
+fn languages() -> Vec<PluginLanguage> {
+    vec![PluginLanguage {
+        language: tree_sitter_python::language(),
+        scope: Some("source.python".into()),
+        content_regex: None,
+        file_types: vec!["py".into()],
+        tsg_path: "test.tsg",
+        tsg_source: "(module) @mod { node @mod.defs }",
+        builtins: Some(("builtins.py", "pass\n")),
+        builtins_config: None,
+        special_files: FileAnalyzers::new(),
+        no_similar_paths_in_file: true,
+    }]
+}
+
+tree_sitter_stack_graphs::export_plugin!(languages);
+
+#[test]
+fn can_load_languages_from_plugin_declaration() {
+    let configurations = tree_sitter_stack_graphs_plugin
+        .language_configurations(&NoCancellation)
+        .expect("Expected loading plugin languages to succeed");
+    assert_eq!(1, configurations.len());
+    assert!(configurations[0].no_similar_paths_in_file);
+    assert_eq!(1, configurations[0].builtins.iter_files().count());
+
+    let mut loader = Loader::from_language_configurations(configurations, None)
+        .expect("Expected loader to succeed");
+    let lc = loader
+        .load_for_file(Path::new("test.py"), &mut None, &NoCancellation)
+        .expect("Expected loading stack graph language to succeed");
+    assert_eq!(
+        lc.primary.map(|lc| lc.language),
+        Some(tree_sitter_python::language())
+    );
+}
+
+#[test]
+fn cannot_load_plugin_with_incompatible_api_version() {
+    let declaration = PluginDeclaration {
+        api_version: PLUGIN_API_VERSION + 1,
+        ..tree_sitter_stack_graphs_plugin
+    };
+    let result = declaration.language_configurations(&NoCancellation);
+    assert!(matches!(result, Err(LoadError::Plugin(_))));
+}
+
+#[test]
+fn cannot_load_plugin_built_against_other_crate_version() {
+    let declaration = PluginDeclaration {
+        crate_version: "0.0.0",
+        ..tree_sitter_stack_graphs_plugin
+    };
+    let result = declaration.language_configurations(&NoCancellation);
+    assert!(matches!(result, Err(LoadError::Plugin(_))));
+}
+
+/// The shared libraries of the test plugin in `tests/plugin`, built with a compatible and with an
+/// incompatible plugin API version.
+#[cfg(feature = "plugins")]
+static TEST_PLUGINS: Lazy<(PathBuf, PathBuf)> = Lazy::new(|| {
+    (
+        build_test_plugin(&[], "compatible"),
+        build_test_plugin(&["incompatible-api-version"], "incompatible"),
+    )
+});
+
+/// Builds the test plugin with the given features, and returns the path of a copy of the shared
+/// library. Copies are used because every build overwrites the library in the target directory.
+/// The plugin is a workspace member, so its dependencies are locked and already downloaded by the
+/// build that runs these tests, and the build does not need network access.
+#[cfg(feature = "plugins")]
+fn build_test_plugin(features: &[&str], name: &str) -> PathBuf {
+    let manifest_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/plugin/Cargo.toml");
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("test-plugin");
+    let mut command = Command::new(env!("CARGO"));
+    command
+        .arg("build")
+        .arg("--quiet")
+        .arg("--offline")
+        .arg("--locked")
+        .arg("--manifest-path")
+        .arg(&manifest_path)
+        .arg("--target-dir")
+        .arg(&target_dir);
+    if !features.is_empty() {
+        command.arg("--features").arg(features.join(","));
+    }
+    let status = command.status().expect("Expected running cargo to succeed");
+    assert!(status.success(), "Expected building test plugin to succeed");
+
+    let library = format!(
+        "{}tree_sitter_stack_graphs_test_plugin{}",
+        std::env::consts::DLL_PREFIX,
+        std::env::consts::DLL_SUFFIX
+    );
+    let copy = target_dir.join(format!("{}-{}", name, library));
+    std::fs::copy(target_dir.join("debug").join(&library), &copy)
+        .expect("Expected copying test plugin to succeed");
+    copy
+}
+
+#[cfg(feature = "plugins")]
+#[test]
+fn can_load_languages_from_shared_library() {
+    let configurations =
+        load_plugin(&TEST_PLUGINS.0, &NoCancellation).expect("Expected loading plugin to succeed");
+    assert_eq!(1, configurations.len());
+    assert_eq!(vec!["py".to_string()], configurations[0].file_types);
+
+    let mut loader = Loader::from_language_configurations(configurations, None)
+        .expect("Expected loader to succeed");
+    let lc = loader
+        .load_for_file(Path::new("test.py"), &mut None, &NoCancellation)
+        .expect("Expected loading stack graph language to succeed")
+        .primary
+        .expect("Expected language for Python file");
+    let mut graph = StackGraph::new();
+    let file = graph.get_or_create_file("test.py");
+    lc.sgl
+        .build_stack_graph_into(
+            &mut graph,
+            file,
+            "x = 1\n",
+            &Variables::new(),
+            &NoCancellation,
+        )
+        .expect("Expected building stack graph with plugin language to succeed");
+    let definitions = graph
+        .nodes_for_file(file)
+        .filter(|n| graph[*n].is_definition())
+        .filter_map(|n| graph[n].symbol().map(|s| graph[s].to_string()))
+        .collect::<Vec<_>>();
+    assert_eq!(vec!["x".to_string()], definitions);
+}
+
+#[cfg(feature = "plugins")]
+#[test]
+fn cannot_load_shared_library_with_incompatible_api_version() {
+    match load_plugin(&TEST_PLUGINS.1, &NoCancellation) {
+        Err(LoadError::Plugin(message)) => assert!(
+            message.contains("plugin API version"),
+            "Unexpected error: {}",
+            message
+        ),
+        Err(err) => panic!("Unexpected error: {}", err),
+        Ok(_) => panic!("Loading plugin unexpectedly succeeded"),
+    }
+}
@@ -17,13 +17,14 @@ use stack_graphs::stitching::StitcherConfig;
 use std::path::Path;
 use std::path::PathBuf;
 use tree_sitter_graph::Variables;
+use tree_sitter_stack_graphs::test::ResolutionSnapshot;
 use tree_sitter_stack_graphs::test::Test;
 use tree_sitter_stack_graphs::BuildError;
 use tree_sitter_stack_graphs::NoCancellation;
 use tree_sitter_stack_graphs::StackGraphLanguage;
 
 static PATH: Lazy<PathBuf> = Lazy::new(|| PathBuf::from("test.py"));
-static TSG: Lazy<String> = Lazy::new(|| {
+pub(crate) static TSG: Lazy<String> = Lazy::new(|| {
     r#"
       global ROOT_NODE
       (module) @mod {
@@ -56,6 +57,8 @@ static TSG: Lazy<String> = Lazy::new(|| {
       }
     "#.to_string()
 });
+static TSG_WITH_SYNTAX_TYPE: Lazy<String> =
+    Lazy::new(|| TSG.replace("is_definition", "is_definition, syntax_type = \"variable\""));
 static TSG_WITH_PKG: Lazy<String> = Lazy::new(|| {
     r#"
       global PKG
@@ -77,23 +80,13 @@ fn build_stack_graph_into(
     Ok(())
 }
 
-fn check_test(
+pub(crate) fn build_test(
     python_path: &Path,
     python_source: &str,
     tsg_source: &str,
-    expected_successes: usize,
-    expected_failures: usize,
-) {
+) -> (Test, PartialPaths, Database) {
     let mut test =
         Test::from_source(python_path, python_source, python_path).expect("Could not parse test");
-    let assertion_count: usize = test.fragments.iter().map(|f| f.assertions.len()).sum();
-    assert_eq!(
-        expected_successes + expected_failures,
-        assertion_count,
-        "expected {} assertions, got {}",
-        expected_successes + expected_failures,
-        assertion_count,
-    );
     let mut globals = Variables::new();
     for fragments in &test.fragments {
         globals.clear();
@@ -122,6 +115,25 @@ fn check_test(
         )
         .expect("should nopt be cancelled");
     }
+    (test, partials, db)
+}
+
+fn check_test(
+    python_path: &Path,
+    python_source: &str,
+    tsg_source: &str,
+    expected_successes: usize,
+    expected_failures: usize,
+) {
+    let (mut test, mut partials, mut db) = build_test(python_path, python_source, tsg_source);
+    let assertion_count: usize = test.fragments.iter().map(|f| f.assertions.len()).sum();
+    assert_eq!(
+        expected_successes + expected_failures,
+        assertion_count,
+        "expected {} assertions, got {}",
+        expected_successes + expected_failures,
+        assertion_count,
+    );
 
     let results = test
         .run(
@@ -147,6 +159,24 @@ fn check_test(
     );
 }
 
+fn check_snapshot(python_path: &Path, python_source: &str, tsg_source: &str, expected: &str) {
+    let (test, mut partials, mut db) = build_test(python_path, python_source, tsg_source);
+    let actual = test
+        .resolution_snapshot(
+            &mut partials,
+            &mut db,
+            StitcherConfig::default(),
+            &NoCancellation,
+        )
+        .expect("should never be cancelled");
+    assert_eq!(expected, actual.to_string());
+
+    let expected = expected.parse::<ResolutionSnapshot>().unwrap();
+    let results = test.check_snapshot(&expected, &actual);
+    assert_eq!(1, results.success_count());
+    assert_eq!(0, results.failure_count());
+}
+
 #[test]
 fn can_assert_defined_on_one_line() {
     let python = r#"
@@ -219,6 +249,135 @@ fn can_assert_refers_no_symbols() {
     check_test(&PATH, python, &TSG, 1, 0);
 }
 
+#[test]
+fn can_assert_defined_in_fragment() {
+    let python = r#"
+      # --- path: a.py ---
+      x = 1;
+
+      # --- path: b.py ---
+        x;
+      # ^ defined: a.py:3
+    "#;
+    check_test(&PATH, python, &TSG, 1, 0);
+}
+
+#[test]
+fn test_cannot_assert_defined_in_unknown_fragment() {
+    let python = r#"
+      # --- path: a.py ---
+      x = 1;
+        x;
+      # ^ defined: b.py:3
+    "#;
+    if let Ok(_) = Test::from_source(&PATH, python, &PATH) {
+        panic!("Parsing test unexpectedly succeeded.");
+    }
+}
+
+#[test]
+fn test_cannot_assert_defined_on_line_outside_fragment() {
+    let python = r#"
+      # --- path: a.py ---
+      x = 1;
+
+      # --- path: b.py ---
+        x;
+      # ^ defined: a.py:6
+    "#;
+    if let Ok(_) = Test::from_source(&PATH, python, &PATH) {
+        panic!("Parsing test unexpectedly succeeded.");
+    }
+}
+
+#[test]
+fn can_assert_defined_syntax_type() {
+    let python = r#"
+      x = 1;
+        x;
+      # ^ defined_syntax_type: variable
+    "#;
+    check_test(&PATH, python, &TSG_WITH_SYNTAX_TYPE, 1, 0);
+}
+
+#[test]
+fn can_fail_defined_syntax_type() {
+    let python = r#"
+      x = 1;
+        x;
+      # ^ defined_syntax_type: function
+    "#;
+    check_test(&PATH, python, &TSG_WITH_SYNTAX_TYPE, 0, 1);
+}
+
+#[test]
+fn can_fail_defined_fully_qualified_name() {
+    let python = r#"
+      x = 1;
+        x;
+      # ^ defined_fully_qualified_name: test.x
+    "#;
+    check_test(&PATH, python, &TSG, 0, 1);
+}
+
+#[test]
+fn can_assert_defined_uniquely() {
+    let python = r#"
+      x = 1;
+        x;
+      # ^ defined_uniquely:
+    "#;
+    check_test(&PATH, python, &TSG, 1, 0);
+}
+
+#[test]
+fn can_fail_defined_uniquely() {
+    let python = r#"
+      # --- path: a.py ---
+      x = 1;
+
+      # --- path: b.py ---
+      x = 1;
+
+      # --- path: c.py ---
+        x;
+      # ^ defined_uniquely:
+    "#;
+    check_test(&PATH, python, &TSG, 0, 1);
+}
+
+#[test]
+fn can_assert_undefined() {
+    let python = r#"
+      y = 1;
+        x;
+      # ^ undefined:
+    "#;
+    check_test(&PATH, python, &TSG, 1, 0);
+}
+
+#[test]
+fn can_fail_undefined() {
+    let python = r#"
+      x = 1;
+        x;
+      # ^ undefined:
+    "#;
+    check_test(&PATH, python, &TSG, 0, 1);
+}
+
+#[test]
+fn test_cannot_use_values_with_undefined_assertion() {
+    let python = r#"
+      x = 1;
+        x;
+      # ^ undefined: 2
+    "#;
+    if let Ok(_) = Test::from_source(&PATH, python, &PATH) {
+        panic!("Parsing test unexpectedly succeeded.");
+    }
+}
+
 #[test]
 fn test_cannot_use_unknown_assertion() {
     let python = r#"
@@ -334,3 +493,86 @@ fn test_cannot_set_global_before_first_fragment() {
         panic!("Parsing test unexpectedly succeeded.");
     }
 }
+
+#[test]
+fn can_snapshot_resolved_and_unresolved_references() {
+    let python = r#"
+      # --- path: a.py ---
+      x = 1;
+
+      # --- path: b.py ---
+      y = x;
+      x;
+      z;
+    "#;
+    check_snapshot(
+        &PATH,
+        python,
+        &TSG,
+        "b.py:6:11 x -> a.py:3:7\nb.py:7:7 x -> a.py:3:7\nb.py:8:7 z ->\n",
+    );
+}
+
+#[test]
+fn snapshot_paths_are_relative_to_test_file() {
+    let python = r#"
+      x = 1;
+      x;
+    "#;
+    check_snapshot(
+        Path::new("some/dir/test.py"),
+        python,
+        &TSG,
+        "test.py:3:7 x -> test.py:2:7\n",
+    );
+}
+
+#[test]
+fn duplicate_snapshot_entries_are_failures() {
+    let python = r#"
+      x = 1;
+      x;
+    "#;
+    let (test, mut partials, mut db) = build_test(&PATH, python, &TSG);
+    let actual = test
+        .resolution_snapshot(
+            &mut partials,
+            &mut db,
+            StitcherConfig::default(),
+            &NoCancellation,
+        )
+        .expect("should never be cancelled");
+    let expected = "test.py:3:7 x -> test.py:2:7\ntest.py:3:7 x -> test.py:2:7\n"
+        .parse::<ResolutionSnapshot>()
+        .unwrap();
+    let results = test.check_snapshot(&expected, &actual);
+    assert_eq!(0, results.success_count());
+    assert_eq!(1, results.failure_count());
+}
+
+#[test]
+fn snapshot_differences_are_failures() {
+    let python = r#"
+      x = 1;
+      x;
+    "#;
+    let (test, mut partials, mut db) = build_test(&PATH, python, &TSG);
+    let actual = test
+        .resolution_snapshot(
+            &mut partials,
+            &mut db,
+            StitcherConfig::default(),
+            &NoCancellation,
+        )
+        .expect("should never be cancelled");
+    let expected = "test.py:3:7 x ->\n".parse::<ResolutionSnapshot>().unwrap();
+    let results = test.check_snapshot(&expected, &actual);
+    assert_eq!(0, results.success_count());
+    assert_eq!(1, results.failure_count());
+    let position = &results.assertion_results_iter().next().unwrap().position;
+    assert_eq!(
+        (2, 6),
+        (position.line, position.column.grapheme_offset),
+        "Expected failure at the first differing entry"
+    );
+}
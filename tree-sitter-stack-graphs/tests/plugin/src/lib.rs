@@ -0,0 +1,52 @@
+// -*- coding: utf-8 -*-
+// ------------------------------------------------------------------------------------------------
+// Copyright © 2023, stack-graphs authors.
+// Licensed under either of Apache License, Version 2.0, or MIT license, at your option.
+// Please see the LICENSE-APACHE or LICENSE-MIT files in this distribution for license details.
+// ------------------------------------------------------------------------------------------------
+
+//! A language plugin for Python, which is built and loaded by the plugin tests.
+
+use tree_sitter_stack_graphs::loader::FileAnalyzers;
+use tree_sitter_stack_graphs::plugin::PluginLanguage;
+
+static TSG: &str = r#"
+  (module) @mod {
+    node @mod.defs
+  }
+
+  (module (expression_statement (assignment left: (identifier) @name))) @mod {
+    node def
+    attr (def) type = "pop_symbol", symbol = (source-text @name), source_node = @name, is_definition
+    edge @mod.defs -> def
+  }
+"#;
+
+fn languages() -> Vec<PluginLanguage> {
+    vec![PluginLanguage {
+        language: tree_sitter_python::language(),
+        scope: Some("source.python".into()),
+        content_regex: None,
+        file_types: vec!["py".into()],
+        tsg_path: "test-plugin.tsg",
+        tsg_source: TSG,
+        builtins: None,
+        builtins_config: None,
+        special_files: FileAnalyzers::new(),
+        no_similar_paths_in_file: false,
+    }]
+}
+
+#[cfg(not(feature = "incompatible-api-version"))]
+tree_sitter_stack_graphs::export_plugin!(languages);
+
+#[cfg(feature = "incompatible-api-version")]
+#[allow(non_upper_case_globals)]
+#[no_mangle]
+pub static tree_sitter_stack_graphs_plugin: tree_sitter_stack_graphs::plugin::PluginDeclaration =
+    tree_sitter_stack_graphs::plugin::PluginDeclaration {
+        api_version: tree_sitter_stack_graphs::plugin::PLUGIN_API_VERSION + 1,
+        rustc_version: tree_sitter_stack_graphs::plugin::RUSTC_VERSION,
+        crate_version: tree_sitter_stack_graphs::plugin::CRATE_VERSION,
+        languages,
+    };